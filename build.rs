@@ -0,0 +1,35 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::process::Command;
+
+// Capture the git commit and build time so the API can report which build is deployed
+fn main() {
+    let git_sha = env_or_git("GIT_SHA", &["rev-parse", "--short=12", "HEAD"]);
+    let build_time = std::env::var("BUILD_TIME")
+        .unwrap_or_else(|_| chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    println!("cargo:rustc-env=GIT_SHA={}", git_sha);
+    println!("cargo:rustc-env=BUILD_TIME={}", build_time);
+
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-env-changed=BUILD_TIME");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
+
+// Prefer an explicit override (e.g. from CI or a Docker build without .git), then git itself
+fn env_or_git(var: &str, args: &[&str]) -> String {
+    if let Ok(value) = std::env::var(var) {
+        return value;
+    }
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "cetus_swap_events" DROP COLUMN "created_at";
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "created_at";
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "created_at";
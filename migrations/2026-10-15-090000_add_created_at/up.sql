@@ -0,0 +1,4 @@
+-- Ingestion time of each row, set by the database rather than the indexer
+ALTER TABLE "cetus_swap_events" ADD COLUMN "created_at" TIMESTAMPTZ DEFAULT now();
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "created_at" TIMESTAMPTZ DEFAULT now();
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "created_at" TIMESTAMPTZ DEFAULT now();
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_swap_events_pool_id_timestamp_ms_idx";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "timestamp_ms";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "pool_id";
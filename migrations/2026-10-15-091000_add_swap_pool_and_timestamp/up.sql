@@ -0,0 +1,6 @@
+-- Pool and checkpoint timestamp of each swap, needed for per-pool time series
+ALTER TABLE "cetus_swap_events" ADD COLUMN "pool_id" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_swap_events" ADD COLUMN "timestamp_ms" INT8 NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_pool_id_timestamp_ms_idx"
+    ON "cetus_swap_events" ("pool_id", "timestamp_ms");
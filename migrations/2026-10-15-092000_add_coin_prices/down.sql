@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS "coin_prices";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "coin_type_b";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "coin_type_a";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "atob";
@@ -0,0 +1,12 @@
+-- Swap direction and the pool's coin types, needed to value the input side of a swap
+ALTER TABLE "cetus_swap_events" ADD COLUMN "atob" BOOL NOT NULL DEFAULT FALSE;
+ALTER TABLE "cetus_swap_events" ADD COLUMN "coin_type_a" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_swap_events" ADD COLUMN "coin_type_b" VARCHAR NOT NULL DEFAULT '';
+
+-- Reference prices maintained out-of-band; `usd_price` is the price of one whole token
+CREATE TABLE IF NOT EXISTS "coin_prices" (
+    "coin_type" VARCHAR NOT NULL PRIMARY KEY,
+    "usd_price" FLOAT8 NOT NULL,
+    "decimals" INT2 NOT NULL,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT now()
+);
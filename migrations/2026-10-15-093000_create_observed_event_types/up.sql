@@ -0,0 +1,7 @@
+-- Histogram of every event type seen by the indexer while RECORD_EVENT_TYPES is enabled
+CREATE TABLE IF NOT EXISTS "observed_event_types" (
+    "event_type" VARCHAR NOT NULL PRIMARY KEY,
+    "matched" BOOL NOT NULL,
+    "count" INT8 NOT NULL,
+    "last_seen_at" TIMESTAMPTZ DEFAULT now()
+);
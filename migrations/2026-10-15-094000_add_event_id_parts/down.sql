@@ -0,0 +1,14 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_swap_events_tx_digest_idx";
+
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "event_index";
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "tx_digest";
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "package_id";
+
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "event_index";
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "tx_digest";
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "package_id";
+
+ALTER TABLE "cetus_swap_events" DROP COLUMN "event_index";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "tx_digest";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "package_id";
@@ -0,0 +1,14 @@
+-- Parts of the event id stored separately, so rows can be keyed by an opaque UUID
+ALTER TABLE "cetus_swap_events" ADD COLUMN "package_id" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_swap_events" ADD COLUMN "tx_digest" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_swap_events" ADD COLUMN "event_index" INT8 NOT NULL DEFAULT 0;
+
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "package_id" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "tx_digest" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "event_index" INT8 NOT NULL DEFAULT 0;
+
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "package_id" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "tx_digest" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "event_index" INT8 NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_tx_digest_idx" ON "cetus_swap_events" ("tx_digest");
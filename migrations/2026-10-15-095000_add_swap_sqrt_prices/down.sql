@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_swap_events_price_impact_bps_idx";
+
+ALTER TABLE "cetus_swap_events" DROP COLUMN "price_impact_bps";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "after_sqrt_price";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "before_sqrt_price";
@@ -0,0 +1,6 @@
+-- Pool sqrt prices (Q64.64 u128, stored as strings) around each swap, and the derived price impact
+ALTER TABLE "cetus_swap_events" ADD COLUMN "before_sqrt_price" VARCHAR NOT NULL DEFAULT '0';
+ALTER TABLE "cetus_swap_events" ADD COLUMN "after_sqrt_price" VARCHAR NOT NULL DEFAULT '0';
+ALTER TABLE "cetus_swap_events" ADD COLUMN "price_impact_bps" INT8 NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_price_impact_bps_idx" ON "cetus_swap_events" ("price_impact_bps");
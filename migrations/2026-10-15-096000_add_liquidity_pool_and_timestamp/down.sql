@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_remove_liquidity_events_pool_position_idx";
+DROP INDEX IF EXISTS "cetus_add_liquidity_events_pool_position_idx";
+
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "timestamp_ms";
+ALTER TABLE "cetus_remove_liquidity_events" ALTER COLUMN "position" DROP DEFAULT;
+ALTER TABLE "cetus_remove_liquidity_events" ALTER COLUMN "pool" DROP DEFAULT;
+
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "timestamp_ms";
+ALTER TABLE "cetus_add_liquidity_events" ALTER COLUMN "position" DROP DEFAULT;
+ALTER TABLE "cetus_add_liquidity_events" ALTER COLUMN "pool" DROP DEFAULT;
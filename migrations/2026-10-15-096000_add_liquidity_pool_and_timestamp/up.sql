@@ -0,0 +1,19 @@
+-- Pool, position and checkpoint timestamp of each liquidity event, needed for per-position views.
+-- `pool` and `position` may already exist from the initial migration, so only add them if missing
+-- and give them a default in either case.
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN IF NOT EXISTS "pool" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN IF NOT EXISTS "position" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_add_liquidity_events" ALTER COLUMN "pool" SET DEFAULT '';
+ALTER TABLE "cetus_add_liquidity_events" ALTER COLUMN "position" SET DEFAULT '';
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "timestamp_ms" INT8 NOT NULL DEFAULT 0;
+
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN IF NOT EXISTS "pool" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN IF NOT EXISTS "position" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_remove_liquidity_events" ALTER COLUMN "pool" SET DEFAULT '';
+ALTER TABLE "cetus_remove_liquidity_events" ALTER COLUMN "position" SET DEFAULT '';
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "timestamp_ms" INT8 NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "cetus_add_liquidity_events_pool_position_idx"
+    ON "cetus_add_liquidity_events" ("pool", "position", "timestamp_ms");
+CREATE INDEX IF NOT EXISTS "cetus_remove_liquidity_events_pool_position_idx"
+    ON "cetus_remove_liquidity_events" ("pool", "position", "timestamp_ms");
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_swap_events_partner_idx";
+
+ALTER TABLE "cetus_swap_events" DROP COLUMN "fee_amount";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "partner";
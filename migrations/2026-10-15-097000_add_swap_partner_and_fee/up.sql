@@ -0,0 +1,5 @@
+-- Referral partner and fee of each swap, needed for per-partner aggregates
+ALTER TABLE "cetus_swap_events" ADD COLUMN "partner" VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE "cetus_swap_events" ADD COLUMN "fee_amount" INT8 NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_partner_idx" ON "cetus_swap_events" ("partner");
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_remove_liquidity_events_checkpoint_seq_idx";
+DROP INDEX IF EXISTS "cetus_add_liquidity_events_checkpoint_seq_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_checkpoint_seq_idx";
+
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "checkpoint_seq";
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "checkpoint_seq";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "checkpoint_seq";
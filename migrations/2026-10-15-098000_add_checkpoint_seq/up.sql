@@ -0,0 +1,9 @@
+-- Sequence number of the checkpoint containing each event, used for retention pruning.
+-- Rows indexed before this migration keep 0.
+ALTER TABLE "cetus_swap_events" ADD COLUMN "checkpoint_seq" INT8 NOT NULL DEFAULT 0;
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "checkpoint_seq" INT8 NOT NULL DEFAULT 0;
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "checkpoint_seq" INT8 NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_checkpoint_seq_idx" ON "cetus_swap_events" ("checkpoint_seq");
+CREATE INDEX IF NOT EXISTS "cetus_add_liquidity_events_checkpoint_seq_idx" ON "cetus_add_liquidity_events" ("checkpoint_seq");
+CREATE INDEX IF NOT EXISTS "cetus_remove_liquidity_events_checkpoint_seq_idx" ON "cetus_remove_liquidity_events" ("checkpoint_seq");
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_remove_liquidity_events_event_seq_idx";
+DROP INDEX IF EXISTS "cetus_add_liquidity_events_event_seq_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_event_seq_idx";
+
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "event_seq";
+ALTER TABLE "cetus_remove_liquidity_events" DROP COLUMN "tx_index";
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "event_seq";
+ALTER TABLE "cetus_add_liquidity_events" DROP COLUMN "tx_index";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "event_seq";
+ALTER TABLE "cetus_swap_events" DROP COLUMN "tx_index";
@@ -0,0 +1,12 @@
+-- Position of the transaction within its checkpoint, and a total order over all events:
+-- event_seq = (checkpoint_seq << 24) | (tx_index << 10) | event_index
+ALTER TABLE "cetus_swap_events" ADD COLUMN "tx_index" INT8 NOT NULL DEFAULT 0;
+ALTER TABLE "cetus_swap_events" ADD COLUMN "event_seq" INT8 NOT NULL DEFAULT 0;
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "tx_index" INT8 NOT NULL DEFAULT 0;
+ALTER TABLE "cetus_add_liquidity_events" ADD COLUMN "event_seq" INT8 NOT NULL DEFAULT 0;
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "tx_index" INT8 NOT NULL DEFAULT 0;
+ALTER TABLE "cetus_remove_liquidity_events" ADD COLUMN "event_seq" INT8 NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_event_seq_idx" ON "cetus_swap_events" ("event_seq");
+CREATE INDEX IF NOT EXISTS "cetus_add_liquidity_events_event_seq_idx" ON "cetus_add_liquidity_events" ("event_seq");
+CREATE INDEX IF NOT EXISTS "cetus_remove_liquidity_events_event_seq_idx" ON "cetus_remove_liquidity_events" ("event_seq");
@@ -0,0 +1,6 @@
+-- Checkpoint watermark per ingestion task, for PROGRESS_STORE=postgres
+CREATE TABLE IF NOT EXISTS "progress" (
+    "task_name" VARCHAR NOT NULL PRIMARY KEY,
+    "checkpoint_number" INT8 NOT NULL,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT now()
+);
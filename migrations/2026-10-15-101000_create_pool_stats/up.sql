@@ -0,0 +1,10 @@
+-- Per-pool swap totals, refreshed periodically by the indexer and served by /api/volume
+CREATE TABLE IF NOT EXISTS "pool_stats" (
+    "pool_id" VARCHAR NOT NULL PRIMARY KEY,
+    "total_volume_in" INT8 NOT NULL,
+    "total_volume_out" INT8 NOT NULL,
+    "swap_count" INT8 NOT NULL,
+    "last_updated" TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS "pool_stats_total_volume_in_idx" ON "pool_stats" ("total_volume_in" DESC);
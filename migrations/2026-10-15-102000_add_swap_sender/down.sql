@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "cetus_swap_events_sender_event_seq_idx";
+
+ALTER TABLE "cetus_swap_events" DROP COLUMN "sender";
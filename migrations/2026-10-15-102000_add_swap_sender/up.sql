@@ -0,0 +1,4 @@
+-- Sender of the transaction that emitted each swap, for per-trader analytics
+ALTER TABLE "cetus_swap_events" ADD COLUMN "sender" VARCHAR NOT NULL DEFAULT '';
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_sender_event_seq_idx" ON "cetus_swap_events" ("sender", "event_seq" DESC);
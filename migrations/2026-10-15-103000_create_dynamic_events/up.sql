@@ -0,0 +1,17 @@
+-- Events decoded from runtime layouts in the protocols config, with their fields as JSON
+CREATE TABLE IF NOT EXISTS "dynamic_events" (
+    "id" VARCHAR NOT NULL PRIMARY KEY,
+    "event_name" VARCHAR NOT NULL,
+    "event_type" VARCHAR NOT NULL,
+    "package_id" VARCHAR NOT NULL,
+    "tx_digest" VARCHAR NOT NULL,
+    "event_index" INT8 NOT NULL,
+    "timestamp_ms" INT8 NOT NULL,
+    "checkpoint_seq" INT8 NOT NULL,
+    "tx_index" INT8 NOT NULL,
+    "event_seq" INT8 NOT NULL,
+    "data" JSONB NOT NULL,
+    "created_at" TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS "dynamic_events_event_name_event_seq_idx" ON "dynamic_events" ("event_name", "event_seq" DESC);
@@ -0,0 +1,8 @@
+-- Decimals and symbol of coins, fetched from a fullnode the first time a coin type is seen
+CREATE TABLE IF NOT EXISTS "coin_metadata" (
+    "coin_type" VARCHAR NOT NULL PRIMARY KEY,
+    "decimals" INT2 NOT NULL,
+    "symbol" VARCHAR NOT NULL,
+    "name" VARCHAR NOT NULL,
+    "fetched_at" TIMESTAMPTZ NOT NULL DEFAULT now()
+);
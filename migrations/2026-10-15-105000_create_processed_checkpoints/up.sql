@@ -0,0 +1,7 @@
+-- One row per checkpoint the indexer finished, including checkpoints without matching events,
+-- so skipped ranges can be told apart from quiet ones
+CREATE TABLE IF NOT EXISTS "processed_checkpoints" (
+    "checkpoint_seq" INT8 NOT NULL PRIMARY KEY,
+    "timestamp_ms" INT8 NOT NULL,
+    "processed_at" TIMESTAMPTZ NOT NULL DEFAULT now()
+);
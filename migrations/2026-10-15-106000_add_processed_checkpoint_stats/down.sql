@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "processed_checkpoints"
+    DROP COLUMN IF EXISTS "swap_events",
+    DROP COLUMN IF EXISTS "add_liquidity_events",
+    DROP COLUMN IF EXISTS "remove_liquidity_events",
+    DROP COLUMN IF EXISTS "dynamic_events",
+    DROP COLUMN IF EXISTS "duration_ms";
@@ -0,0 +1,8 @@
+-- Event counts and processing time per checkpoint, for throughput dashboards. Checkpoints
+-- recorded before these columns existed read as 0.
+ALTER TABLE "processed_checkpoints"
+    ADD COLUMN IF NOT EXISTS "swap_events" INT4 NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "add_liquidity_events" INT4 NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "remove_liquidity_events" INT4 NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "dynamic_events" INT4 NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS "duration_ms" INT8 NOT NULL DEFAULT 0;
@@ -0,0 +1,27 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "cetus_swap_events" RENAME TO "cetus_swap_events_partitioned";
+DROP INDEX IF EXISTS "cetus_swap_events_pool_id_timestamp_ms_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_tx_digest_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_price_impact_bps_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_partner_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_checkpoint_seq_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_event_seq_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_sender_event_seq_idx";
+ALTER TABLE "cetus_swap_events_partitioned" RENAME CONSTRAINT "cetus_swap_events_pkey" TO "cetus_swap_events_partitioned_pkey";
+
+CREATE TABLE "cetus_swap_events" (LIKE "cetus_swap_events_partitioned" INCLUDING DEFAULTS);
+-- Ids were only unique together with timestamp_ms; keep the latest row of any duplicate id
+INSERT INTO "cetus_swap_events"
+SELECT DISTINCT ON ("id") * FROM "cetus_swap_events_partitioned" ORDER BY "id", "timestamp_ms" DESC;
+ALTER TABLE "cetus_swap_events" ADD PRIMARY KEY ("id");
+
+DROP TABLE "cetus_swap_events_partitioned";
+DROP FUNCTION IF EXISTS create_cetus_swap_events_partition(DATE);
+
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_pool_id_timestamp_ms_idx" ON "cetus_swap_events" ("pool_id", "timestamp_ms");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_tx_digest_idx" ON "cetus_swap_events" ("tx_digest");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_price_impact_bps_idx" ON "cetus_swap_events" ("price_impact_bps");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_partner_idx" ON "cetus_swap_events" ("partner");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_checkpoint_seq_idx" ON "cetus_swap_events" ("checkpoint_seq");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_event_seq_idx" ON "cetus_swap_events" ("event_seq");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_sender_event_seq_idx" ON "cetus_swap_events" ("sender", "event_seq" DESC);
@@ -0,0 +1,106 @@
+-- Partition swaps by UTC month of timestamp_ms, so old months can be detached or dropped as a
+-- whole and time-bounded queries only scan the months they cover. Postgres requires the
+-- partition key in the primary key, so it becomes (id, timestamp_ms). Swaps indexed before
+-- timestamps were stored (timestamp_ms = 0) go to the default partition.
+--
+-- The existing rows are copied into the new table. Views on cetus_swap_events must be dropped
+-- before running this migration and recreated after it.
+
+ALTER TABLE "cetus_swap_events" RENAME TO "cetus_swap_events_unpartitioned";
+ALTER TABLE "cetus_swap_events_unpartitioned" RENAME CONSTRAINT "cetus_swap_events_pkey" TO "cetus_swap_events_unpartitioned_pkey";
+DROP INDEX IF EXISTS "cetus_swap_events_pool_id_timestamp_ms_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_tx_digest_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_price_impact_bps_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_partner_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_checkpoint_seq_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_event_seq_idx";
+DROP INDEX IF EXISTS "cetus_swap_events_sender_event_seq_idx";
+
+CREATE TABLE "cetus_swap_events" (
+    "id" VARCHAR NOT NULL,
+    "amount_in" INT8 NOT NULL,
+    "amount_out" INT8 NOT NULL,
+    "created_at" TIMESTAMPTZ DEFAULT now(),
+    "pool_id" VARCHAR NOT NULL DEFAULT '',
+    "timestamp_ms" INT8 NOT NULL DEFAULT 0,
+    "atob" BOOL NOT NULL DEFAULT FALSE,
+    "coin_type_a" VARCHAR NOT NULL DEFAULT '',
+    "coin_type_b" VARCHAR NOT NULL DEFAULT '',
+    "package_id" VARCHAR NOT NULL DEFAULT '',
+    "tx_digest" VARCHAR NOT NULL DEFAULT '',
+    "event_index" INT8 NOT NULL DEFAULT 0,
+    "before_sqrt_price" VARCHAR NOT NULL DEFAULT '0',
+    "after_sqrt_price" VARCHAR NOT NULL DEFAULT '0',
+    "price_impact_bps" INT8 NOT NULL DEFAULT 0,
+    "partner" VARCHAR NOT NULL DEFAULT '',
+    "fee_amount" INT8 NOT NULL DEFAULT 0,
+    "checkpoint_seq" INT8 NOT NULL DEFAULT 0,
+    "tx_index" INT8 NOT NULL DEFAULT 0,
+    "event_seq" INT8 NOT NULL DEFAULT 0,
+    "sender" VARCHAR NOT NULL DEFAULT '',
+    PRIMARY KEY ("id", "timestamp_ms")
+) PARTITION BY RANGE ("timestamp_ms");
+
+-- Rows without a monthly partition, i.e. timestamp_ms = 0 as long as partitions are created ahead
+CREATE TABLE "cetus_swap_events_default" PARTITION OF "cetus_swap_events" DEFAULT;
+
+-- Create the partition holding the given month, named cetus_swap_events_YYYY_MM, unless it exists
+CREATE OR REPLACE FUNCTION create_cetus_swap_events_partition(month DATE) RETURNS TEXT AS $$
+DECLARE
+    start_month TIMESTAMP := date_trunc('month', month::TIMESTAMP);
+    partition_name TEXT := format('cetus_swap_events_%s', to_char(start_month, 'YYYY_MM'));
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF cetus_swap_events FOR VALUES FROM (%s) TO (%s)',
+        partition_name,
+        (EXTRACT(EPOCH FROM start_month AT TIME ZONE 'UTC') * 1000)::INT8,
+        (EXTRACT(EPOCH FROM (start_month + INTERVAL '1 month') AT TIME ZONE 'UTC') * 1000)::INT8
+    );
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Partitions for every month with swaps so far, up to two months ahead
+DO $$
+DECLARE
+    first_month DATE;
+    month DATE;
+BEGIN
+    SELECT date_trunc('month', to_timestamp(MIN(timestamp_ms) / 1000.0) AT TIME ZONE 'UTC')::DATE
+    INTO first_month
+    FROM cetus_swap_events_unpartitioned
+    WHERE timestamp_ms > 0;
+
+    FOR month IN
+        SELECT generate_series(
+            COALESCE(first_month, date_trunc('month', now() AT TIME ZONE 'UTC')::DATE)::TIMESTAMP,
+            date_trunc('month', now() AT TIME ZONE 'UTC') + INTERVAL '2 months',
+            INTERVAL '1 month'
+        )::DATE
+    LOOP
+        PERFORM create_cetus_swap_events_partition(month);
+    END LOOP;
+END;
+$$;
+
+INSERT INTO "cetus_swap_events" (
+    "id", "amount_in", "amount_out", "created_at", "pool_id", "timestamp_ms", "atob", "coin_type_a",
+    "coin_type_b", "package_id", "tx_digest", "event_index", "before_sqrt_price", "after_sqrt_price",
+    "price_impact_bps", "partner", "fee_amount", "checkpoint_seq", "tx_index", "event_seq", "sender"
+)
+SELECT
+    "id", "amount_in", "amount_out", "created_at", "pool_id", "timestamp_ms", "atob", "coin_type_a",
+    "coin_type_b", "package_id", "tx_digest", "event_index", "before_sqrt_price", "after_sqrt_price",
+    "price_impact_bps", "partner", "fee_amount", "checkpoint_seq", "tx_index", "event_seq", "sender"
+FROM "cetus_swap_events_unpartitioned";
+
+DROP TABLE "cetus_swap_events_unpartitioned";
+
+-- Indexes on the parent are created on every partition, including future ones
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_pool_id_timestamp_ms_idx" ON "cetus_swap_events" ("pool_id", "timestamp_ms");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_tx_digest_idx" ON "cetus_swap_events" ("tx_digest");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_price_impact_bps_idx" ON "cetus_swap_events" ("price_impact_bps");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_partner_idx" ON "cetus_swap_events" ("partner");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_checkpoint_seq_idx" ON "cetus_swap_events" ("checkpoint_seq");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_event_seq_idx" ON "cetus_swap_events" ("event_seq");
+CREATE INDEX IF NOT EXISTS "cetus_swap_events_sender_event_seq_idx" ON "cetus_swap_events" ("sender", "event_seq" DESC);
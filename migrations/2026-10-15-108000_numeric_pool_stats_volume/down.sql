@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "pool_stats"
+    ALTER COLUMN "total_volume_in" TYPE INT8,
+    ALTER COLUMN "total_volume_out" TYPE INT8;
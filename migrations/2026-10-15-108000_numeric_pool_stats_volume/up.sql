@@ -0,0 +1,4 @@
+-- Per-pool volume totals can exceed INT8 even when every swap amount fits, so keep them as NUMERIC
+ALTER TABLE "pool_stats"
+    ALTER COLUMN "total_volume_in" TYPE NUMERIC,
+    ALTER COLUMN "total_volume_out" TYPE NUMERIC;
@@ -0,0 +1,8 @@
+-- Event type counts per checkpoint, so observed_event_types.count only grows by checkpoints
+-- it hasn't counted yet and reprocessing a checkpoint doesn't count its events twice
+CREATE TABLE IF NOT EXISTS "observed_event_type_checkpoints" (
+    "event_type" VARCHAR NOT NULL,
+    "checkpoint_seq" INT8 NOT NULL,
+    "count" INT8 NOT NULL,
+    PRIMARY KEY ("event_type", "checkpoint_seq")
+);
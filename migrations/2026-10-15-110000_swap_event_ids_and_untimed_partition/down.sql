@@ -0,0 +1,26 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER IF EXISTS "cetus_swap_events_release_id" ON "cetus_swap_events";
+DROP TRIGGER IF EXISTS "cetus_swap_events_claim_id" ON "cetus_swap_events";
+DROP FUNCTION IF EXISTS cetus_swap_events_release_id();
+DROP FUNCTION IF EXISTS cetus_swap_events_claim_id();
+DROP TABLE IF EXISTS "cetus_swap_event_ids";
+
+ALTER TABLE "cetus_swap_events" DETACH PARTITION "cetus_swap_events_untimed";
+CREATE TABLE "cetus_swap_events_default" PARTITION OF "cetus_swap_events" DEFAULT;
+INSERT INTO "cetus_swap_events" SELECT * FROM "cetus_swap_events_untimed";
+DROP TABLE "cetus_swap_events_untimed";
+
+CREATE OR REPLACE FUNCTION create_cetus_swap_events_partition(month DATE) RETURNS TEXT AS $$
+DECLARE
+    start_month TIMESTAMP := date_trunc('month', month::TIMESTAMP);
+    partition_name TEXT := format('cetus_swap_events_%s', to_char(start_month, 'YYYY_MM'));
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF cetus_swap_events FOR VALUES FROM (%s) TO (%s)',
+        partition_name,
+        (EXTRACT(EPOCH FROM start_month AT TIME ZONE 'UTC') * 1000)::INT8,
+        (EXTRACT(EPOCH FROM (start_month + INTERVAL '1 month') AT TIME ZONE 'UTC') * 1000)::INT8
+    );
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
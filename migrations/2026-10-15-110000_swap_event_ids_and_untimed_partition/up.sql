@@ -0,0 +1,102 @@
+-- Swaps without a monthly partition used to land in cetus_swap_events_default. Postgres then
+-- refuses to create that month's partition, and retention never expires the rows. Replace the
+-- default partition with one for swaps without a timestamp (timestamp_ms = 0); every other
+-- month gets its partition from the indexer before its swaps are inserted, so an insert into
+-- a missing month now fails instead of silently landing in the default partition.
+--
+-- The primary key is (id, timestamp_ms), so a swap first stored with timestamp_ms = 0 and then
+-- reprocessed with its timestamp was stored twice. cetus_swap_event_ids keeps one row per id,
+-- and a trigger replaces the stored swap when the same id arrives with another timestamp_ms.
+
+-- Serialize partition creation, so concurrent sink workers creating the same month don't race
+CREATE OR REPLACE FUNCTION create_cetus_swap_events_partition(month DATE) RETURNS TEXT AS $$
+DECLARE
+    start_month TIMESTAMP := date_trunc('month', month::TIMESTAMP);
+    partition_name TEXT := format('cetus_swap_events_%s', to_char(start_month, 'YYYY_MM'));
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('create_cetus_swap_events_partition'));
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF cetus_swap_events FOR VALUES FROM (%s) TO (%s)',
+        partition_name,
+        (EXTRACT(EPOCH FROM start_month AT TIME ZONE 'UTC') * 1000)::INT8,
+        (EXTRACT(EPOCH FROM (start_month + INTERVAL '1 month') AT TIME ZONE 'UTC') * 1000)::INT8
+    );
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE "cetus_swap_events" DETACH PARTITION "cetus_swap_events_default";
+
+-- Partitions for the months of the swaps that landed in the default partition
+DO $$
+DECLARE
+    month DATE;
+BEGIN
+    FOR month IN
+        SELECT DISTINCT date_trunc('month', to_timestamp(timestamp_ms / 1000.0) AT TIME ZONE 'UTC')::DATE
+        FROM cetus_swap_events_default
+        WHERE timestamp_ms > 0
+    LOOP
+        PERFORM create_cetus_swap_events_partition(month);
+    END LOOP;
+END;
+$$;
+
+CREATE TABLE "cetus_swap_events_untimed" PARTITION OF "cetus_swap_events" FOR VALUES FROM (MINVALUE) TO (1);
+
+INSERT INTO "cetus_swap_events" SELECT * FROM "cetus_swap_events_default";
+DROP TABLE "cetus_swap_events_default";
+
+CREATE TABLE "cetus_swap_event_ids" (
+    "id" VARCHAR NOT NULL PRIMARY KEY,
+    "timestamp_ms" INT8 NOT NULL
+);
+CREATE INDEX IF NOT EXISTS "cetus_swap_event_ids_timestamp_ms_idx" ON "cetus_swap_event_ids" ("timestamp_ms");
+
+-- Keep the latest row of every id stored more than once
+DELETE FROM "cetus_swap_events" s
+USING (
+    SELECT "id", MAX("timestamp_ms") AS "timestamp_ms"
+    FROM "cetus_swap_events"
+    GROUP BY "id"
+    HAVING COUNT(*) > 1
+) latest
+WHERE s."id" = latest."id" AND s."timestamp_ms" <> latest."timestamp_ms";
+
+INSERT INTO "cetus_swap_event_ids" ("id", "timestamp_ms")
+SELECT "id", "timestamp_ms" FROM "cetus_swap_events";
+
+-- Claim the id before the swap is inserted. When the id is stored with another timestamp_ms,
+-- the stored row is deleted so the insert replaces it. Locking the id row serializes
+-- concurrent inserts of the same id.
+CREATE OR REPLACE FUNCTION cetus_swap_events_claim_id() RETURNS TRIGGER AS $$
+DECLARE
+    stored_timestamp_ms INT8;
+BEGIN
+    INSERT INTO cetus_swap_event_ids (id, timestamp_ms) VALUES (NEW.id, NEW.timestamp_ms)
+    ON CONFLICT (id) DO NOTHING;
+    SELECT timestamp_ms INTO stored_timestamp_ms FROM cetus_swap_event_ids WHERE id = NEW.id FOR UPDATE;
+    IF stored_timestamp_ms <> NEW.timestamp_ms THEN
+        DELETE FROM cetus_swap_events WHERE id = NEW.id AND timestamp_ms = stored_timestamp_ms;
+        INSERT INTO cetus_swap_event_ids (id, timestamp_ms) VALUES (NEW.id, NEW.timestamp_ms)
+        ON CONFLICT (id) DO UPDATE SET timestamp_ms = excluded.timestamp_ms;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Release the id when its swap is deleted (e.g. by the prune endpoint). Dropped or detached
+-- partitions don't fire this; the partition maintenance deletes their ids instead.
+CREATE OR REPLACE FUNCTION cetus_swap_events_release_id() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM cetus_swap_event_ids WHERE id = OLD.id AND timestamp_ms = OLD.timestamp_ms;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER "cetus_swap_events_claim_id"
+    BEFORE INSERT ON "cetus_swap_events"
+    FOR EACH ROW EXECUTE FUNCTION cetus_swap_events_claim_id();
+CREATE TRIGGER "cetus_swap_events_release_id"
+    AFTER DELETE ON "cetus_swap_events"
+    FOR EACH ROW EXECUTE FUNCTION cetus_swap_events_release_id();
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`. Archived partitions keep their _archived name.
+CREATE OR REPLACE FUNCTION create_cetus_swap_events_partition(month DATE) RETURNS TEXT AS $$
+DECLARE
+    start_month TIMESTAMP := date_trunc('month', month::TIMESTAMP);
+    partition_name TEXT := format('cetus_swap_events_%s', to_char(start_month, 'YYYY_MM'));
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('create_cetus_swap_events_partition'));
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF cetus_swap_events FOR VALUES FROM (%s) TO (%s)',
+        partition_name,
+        (EXTRACT(EPOCH FROM start_month AT TIME ZONE 'UTC') * 1000)::INT8,
+        (EXTRACT(EPOCH FROM (start_month + INTERVAL '1 month') AT TIME ZONE 'UTC') * 1000)::INT8
+    );
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
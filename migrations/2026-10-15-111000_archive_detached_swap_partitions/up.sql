@@ -0,0 +1,45 @@
+-- Expired swap partitions used to be detached under their monthly name. CREATE TABLE IF NOT
+-- EXISTS then did nothing for a later swap of that month, whose insert failed forever with no
+-- partition to land in. The partition maintainer now renames detached partitions with an
+-- _archived suffix; rename the ones detached before, and fail with a clear error if a plain
+-- table still has a month's partition name.
+DO $$
+DECLARE
+    detached TEXT;
+BEGIN
+    FOR detached IN
+        SELECT c.relname
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = current_schema()
+          AND c.relkind = 'r'
+          AND c.relname ~ '^cetus_swap_events_[0-9]{4}_[0-9]{2}$'
+          AND NOT c.relispartition
+    LOOP
+        EXECUTE format('ALTER TABLE %I RENAME TO %I', detached, detached || '_archived');
+    END LOOP;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION create_cetus_swap_events_partition(month DATE) RETURNS TEXT AS $$
+DECLARE
+    start_month TIMESTAMP := date_trunc('month', month::TIMESTAMP);
+    partition_name TEXT := format('cetus_swap_events_%s', to_char(start_month, 'YYYY_MM'));
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtext('create_cetus_swap_events_partition'));
+    IF EXISTS (
+        SELECT 1 FROM pg_class
+        WHERE oid = to_regclass(quote_ident(partition_name)) AND NOT relispartition
+    ) THEN
+        RAISE EXCEPTION '% exists but is not a partition of cetus_swap_events; rename it to archive it',
+            partition_name;
+    END IF;
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF cetus_swap_events FOR VALUES FROM (%s) TO (%s)',
+        partition_name,
+        (EXTRACT(EPOCH FROM start_month AT TIME ZONE 'UTC') * 1000)::INT8,
+        (EXTRACT(EPOCH FROM (start_month + INTERVAL '1 month') AT TIME ZONE 'UTC') * 1000)::INT8
+    );
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,118 @@
+#!/usr/bin/env python3
+"""Measures checkpoint download throughput for several CHECKPOINT_PREFETCH values against
+remote storage with a given latency.
+
+A local HTTP server stands in for REMOTE_STORAGE: it answers GET /{sequence}.chk after
+--latency-ms with --size-kb bytes, or with the files of --dir when given. The client fetches
+--checkpoints checkpoints in order with at most CHECKPOINT_PREFETCH requests in flight, the
+way the reader downloads with ReaderOptions::batch_size, and reports checkpoints per second.
+
+    checkpoint_prefetch.py --latency-ms 50 --prefetch 1 10 50
+
+To watch the indexer itself, serve a directory of real checkpoint files and point it at the
+server, then compare its checkpoint rate between CHECKPOINT_PREFETCH values:
+
+    checkpoint_prefetch.py --serve 8765 --dir /path/to/checkpoints --latency-ms 50
+    REMOTE_STORAGE=http://127.0.0.1:8765 CHECKPOINT_PREFETCH=50 cargo run --release --bin suins-indexer
+"""
+
+import argparse
+import collections
+import concurrent.futures
+import http.server
+import os
+import threading
+import time
+import urllib.request
+
+
+def make_handler(latency, payload, directory):
+    class CheckpointHandler(http.server.BaseHTTPRequestHandler):
+        protocol_version = "HTTP/1.1"
+
+        def do_GET(self):
+            time.sleep(latency)
+            name = self.path.lstrip("/")
+            if directory is not None:
+                path = os.path.join(directory, os.path.basename(name))
+                if not name.endswith(".chk") or not os.path.isfile(path):
+                    self.send_error(404)
+                    return
+                with open(path, "rb") as file:
+                    body = file.read()
+            else:
+                body = payload
+            self.send_response(200)
+            self.send_header("Content-Length", str(len(body)))
+            self.end_headers()
+            self.wfile.write(body)
+
+        def log_message(self, *args):
+            pass
+
+    return CheckpointHandler
+
+
+class CheckpointServer(http.server.ThreadingHTTPServer):
+    # The default backlog of 5 drops connections once more requests than that are in flight
+    request_queue_size = 1024
+
+
+def start_server(port, latency, payload, directory):
+    server = CheckpointServer(("127.0.0.1", port), make_handler(latency, payload, directory))
+    server.daemon_threads = True
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    return server
+
+
+def fetch(url):
+    with urllib.request.urlopen(url) as response:
+        return len(response.read())
+
+
+def measure(base_url, first, count, prefetch):
+    """Download checkpoints first..first+count in order with `prefetch` requests in flight"""
+    started = time.monotonic()
+    downloaded = 0
+    with concurrent.futures.ThreadPoolExecutor(max_workers=prefetch) as executor:
+        in_flight = collections.deque()
+        next_sequence = first
+        while next_sequence < first + count or in_flight:
+            while len(in_flight) < prefetch and next_sequence < first + count:
+                in_flight.append(executor.submit(fetch, f"{base_url}/{next_sequence}.chk"))
+                next_sequence += 1
+            downloaded += in_flight.popleft().result()
+    elapsed = time.monotonic() - started
+    return count / elapsed, downloaded / elapsed / 1e6
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--latency-ms", type=float, default=50, help="time before each response (default 50)")
+    parser.add_argument("--size-kb", type=int, default=256, help="size of a synthetic checkpoint (default 256)")
+    parser.add_argument("--dir", help="serve the .chk files in this directory instead of synthetic ones")
+    parser.add_argument("--checkpoints", type=int, default=500, help="checkpoints fetched per run (default 500)")
+    parser.add_argument("--first", type=int, default=0, help="first checkpoint sequence number (default 0)")
+    parser.add_argument("--prefetch", type=int, nargs="+", default=[1, 5, 10, 25, 50, 100],
+                        help="CHECKPOINT_PREFETCH values to measure (default 1 5 10 25 50 100)")
+    parser.add_argument("--serve", type=int, metavar="PORT", help="only serve checkpoints on this port")
+    args = parser.parse_args()
+
+    payload = os.urandom(args.size_kb * 1024)
+    server = start_server(args.serve or 0, args.latency_ms / 1000, payload, args.dir)
+    base_url = f"http://127.0.0.1:{server.server_address[1]}"
+    if args.serve:
+        print(f"Serving checkpoints on {base_url} with {args.latency_ms} ms latency")
+        threading.Event().wait()
+
+    source = args.dir or f"{args.size_kb} KB synthetic checkpoints"
+    print(f"Fetching {args.checkpoints} checkpoints of {source} with {args.latency_ms} ms latency")
+    print(f"{'prefetch':>8} {'checkpoints/s':>14} {'MB/s':>8}")
+    for prefetch in args.prefetch:
+        checkpoints_per_sec, mb_per_sec = measure(base_url, args.first, args.checkpoints, prefetch)
+        print(f"{prefetch:>8} {checkpoints_per_sec:>14.1f} {mb_per_sec:>8.1f}")
+    server.shutdown()
+
+
+if __name__ == "__main__":
+    main()
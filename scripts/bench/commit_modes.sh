@@ -0,0 +1,205 @@
+#!/usr/bin/env bash
+# Measures how long PgSink takes to commit one batch of events with PIPELINE_COMMITS off
+# (sequential) and on (pipelined), and with PARALLEL_TABLE_COMMITS (parallel), with the
+# database a given round trip away.
+#
+# Every commit replays the statements of PgSink::commit_transaction with pgbench: BEGIN,
+# SET CONSTRAINTS, the upserts of the four event tables (awaited one by one, or sent in one
+# pipeline), the observed event type counts and COMMIT. Each upsert writes BENCH_EVENTS new
+# rows. In parallel mode each table and the observed types commit in their own transaction,
+# on their own connection, all at the same time. latency_proxy.py sits between pgbench and
+# Postgres to add the round trip.
+#
+# Needs pgbench and psql 14+ and python3. Connects with the usual libpq variables (PGHOST,
+# PGPORT, PGUSER, PGPASSWORD) to a Postgres 14+ server, e.g. a throwaway container:
+#
+#     docker run -d --name cetus-bench -p 5432:5432 -e POSTGRES_PASSWORD=postgres postgres:15
+#     PGHOST=127.0.0.1 PGUSER=postgres PGPASSWORD=postgres scripts/bench/commit_modes.sh
+#
+# It creates the database BENCH_DATABASE (default cetus_commit_bench), applies the
+# migrations and drops it again at the end.
+#
+# BENCH_DELAYS_MS      one-way delays to measure, default "0 0.5 2.5" (RTT 0, 1 and 5 ms)
+# BENCH_EVENTS         rows per table in each commit, default 50
+# BENCH_TRANSACTIONS   commits per measurement, default 200
+set -euo pipefail
+
+cd "$(dirname "$0")/../.."
+
+database=${BENCH_DATABASE:-cetus_commit_bench}
+delays=${BENCH_DELAYS_MS:-0 0.5 2.5}
+events=${BENCH_EVENTS:-50}
+transactions=${BENCH_TRANSACTIONS:-200}
+modes="sequential pipelined parallel"
+proxy_port=${BENCH_PROXY_PORT:-6543}
+db_host=${PGHOST:-127.0.0.1}
+db_port=${PGPORT:-5432}
+# The proxy only speaks TCP, so a socket directory in PGHOST isn't reachable through it
+if [[ $db_host == /* ]]; then
+    echo "PGHOST must be a TCP host for the latency proxy, not a socket directory" >&2
+    exit 1
+fi
+
+work=$(mktemp -d)
+proxy_pid=
+cleanup() {
+    [[ -n $proxy_pid ]] && kill "$proxy_pid" 2>/dev/null || true
+    dropdb --if-exists "$database" 2>/dev/null || true
+    rm -rf "$work"
+}
+trap cleanup EXIT
+
+echo "Creating $database and applying the migrations"
+# Only report warnings and errors of the setup statements, not their notices
+export PGOPTIONS="${PGOPTIONS:-} -c client_min_messages=warning"
+dropdb --if-exists "$database"
+createdb "$database"
+for migration in $(ls -d migrations/*/ | sort); do
+    psql -q -v ON_ERROR_STOP=1 -d "$database" -f "$migration/up.sql" >/dev/null
+done
+# Swaps are stamped with the current time, so they land in this month's partition
+timestamp_ms=$(($(date +%s) * 1000))
+psql -q -v ON_ERROR_STOP=1 -d "$database" \
+    -c "SELECT create_cetus_swap_events_partition(date_trunc('month', now() AT TIME ZONE 'UTC')::DATE)" >/dev/null
+
+# Upserts as the sink sends them: :rows new rows per table, the same columns updated on conflict
+swap_upsert="INSERT INTO cetus_swap_events (id, amount_in, amount_out, pool_id, timestamp_ms, atob, coin_type_a,
+    coin_type_b, package_id, tx_digest, event_index, before_sqrt_price, after_sqrt_price, price_impact_bps,
+    partner, fee_amount, checkpoint_seq, tx_index, event_seq, sender)
+SELECT 'bench-swap-' || (:base::INT8 + n), 1000000, 999000, '0xpool', :ts, true, '0x2::sui::SUI', '0xusdc::usdc::USDC',
+    '0xcetus', 'digest-' || :base, n, '18446744073709551616', '18446744073709551615', 1, '', 2500, :base, 0, n, '0xsender'
+FROM generate_series(1, :rows) n
+ON CONFLICT (id, timestamp_ms) DO UPDATE SET amount_in = excluded.amount_in, amount_out = excluded.amount_out,
+    pool_id = excluded.pool_id, checkpoint_seq = excluded.checkpoint_seq, tx_index = excluded.tx_index,
+    event_seq = excluded.event_seq, atob = excluded.atob, coin_type_a = excluded.coin_type_a,
+    coin_type_b = excluded.coin_type_b, package_id = excluded.package_id, tx_digest = excluded.tx_digest,
+    event_index = excluded.event_index, before_sqrt_price = excluded.before_sqrt_price,
+    after_sqrt_price = excluded.after_sqrt_price, price_impact_bps = excluded.price_impact_bps,
+    partner = excluded.partner, fee_amount = excluded.fee_amount, sender = excluded.sender;"
+
+liquidity_upsert() {
+    echo "INSERT INTO $1 (id, liquidity, after_liquidity, package_id, tx_digest, event_index, pool, position,
+    timestamp_ms, checkpoint_seq, tx_index, event_seq)
+SELECT 'bench-$1-' || (:base::INT8 + n), '1000000', '2000000', '0xcetus', 'digest-' || :base, n, '0xpool',
+    '0xposition', :ts, :base, 0, n
+FROM generate_series(1, :rows) n
+ON CONFLICT (id) DO UPDATE SET liquidity = excluded.liquidity, after_liquidity = excluded.after_liquidity,
+    package_id = excluded.package_id, tx_digest = excluded.tx_digest, event_index = excluded.event_index,
+    pool = excluded.pool, position = excluded.position, timestamp_ms = excluded.timestamp_ms,
+    checkpoint_seq = excluded.checkpoint_seq, tx_index = excluded.tx_index, event_seq = excluded.event_seq;"
+}
+
+dynamic_upsert="INSERT INTO dynamic_events (id, event_name, event_type, package_id, tx_digest, event_index, timestamp_ms,
+    checkpoint_seq, tx_index, event_seq, data)
+SELECT 'bench-dynamic-' || (:base::INT8 + n), 'bench', '0xbench::bench::Event', '0xbench', 'digest-' || :base, n, :ts,
+    :base, 0, n, '{\"amount\": \"1000\"}'::JSONB
+FROM generate_series(1, :rows) n
+ON CONFLICT (id) DO UPDATE SET event_name = excluded.event_name, event_type = excluded.event_type,
+    package_id = excluded.package_id, tx_digest = excluded.tx_digest, event_index = excluded.event_index,
+    timestamp_ms = excluded.timestamp_ms, checkpoint_seq = excluded.checkpoint_seq, tx_index = excluded.tx_index,
+    event_seq = excluded.event_seq, data = excluded.data;"
+
+observed_types="INSERT INTO observed_event_type_checkpoints (event_type, checkpoint_seq, count)
+SELECT * FROM UNNEST(ARRAY['0xcetus::pool::SwapEvent'], ARRAY[:base::INT8], ARRAY[:rows::INT8])
+ON CONFLICT (event_type, checkpoint_seq) DO NOTHING
+RETURNING event_type, count;
+INSERT INTO observed_event_types (event_type, matched, count, last_seen_at)
+VALUES ('0xcetus::pool::SwapEvent', true, :rows, now())
+ON CONFLICT (event_type) DO UPDATE SET matched = excluded.matched,
+    count = observed_event_types.count + excluded.count, last_seen_at = now();"
+
+add_liquidity_upsert=$(liquidity_upsert cetus_add_liquidity_events)
+remove_liquidity_upsert=$(liquidity_upsert cetus_remove_liquidity_events)
+upserts="$swap_upsert
+$add_liquidity_upsert
+$remove_liquidity_upsert
+$dynamic_upsert"
+
+# Random ids keep every commit inserting new rows, like the indexer moving forward
+header="\\set base random(1, 1000000000) * 1000"
+
+cat >"$work/sequential.sql" <<EOF
+$header
+BEGIN;
+SET CONSTRAINTS ALL DEFERRED;
+$upserts
+$observed_types
+COMMIT;
+EOF
+
+cat >"$work/pipelined.sql" <<EOF
+$header
+BEGIN;
+SET CONSTRAINTS ALL DEFERRED;
+\\startpipeline
+$upserts
+\\endpipeline
+$observed_types
+COMMIT;
+EOF
+
+# The transactions of a parallel commit, one per table plus the observed types
+parts="swap add_liquidity remove_liquidity dynamic observed_types"
+for part in $parts; do
+    case $part in
+        swap) statements=$swap_upsert ;;
+        add_liquidity) statements=$add_liquidity_upsert ;;
+        remove_liquidity) statements=$remove_liquidity_upsert ;;
+        dynamic) statements=$dynamic_upsert ;;
+        observed_types) statements=$observed_types ;;
+    esac
+    cat >"$work/parallel_$part.sql" <<EOF
+$header
+BEGIN;
+SET CONSTRAINTS ALL DEFERRED;
+$statements
+COMMIT;
+EOF
+done
+
+# Average latency of one commit in ms, from pgbench's report
+run_pgbench() {
+    local script=$1
+    pgbench -n -M extended -c 1 -t "$transactions" -h 127.0.0.1 -p "$proxy_port" \
+        -D rows="$events" -D ts="$timestamp_ms" -f "$script" "$database" 2>&1 |
+        sed -n 's/^latency average = \([0-9.]*\) ms$/\1/p'
+}
+
+# Average commit latency in ms of one mode. The transactions of a parallel commit run as
+# concurrent pgbench clients that don't wait for each other, so the slowest one's average
+# stands in for the commit, which waits for all of them; it is a lower bound.
+measure() {
+    local mode=$1
+    if [[ $mode != parallel ]]; then
+        run_pgbench "$work/$mode.sql"
+        return
+    fi
+    local pids=()
+    for part in $parts; do
+        run_pgbench "$work/parallel_$part.sql" >"$work/parallel_$part.latency" &
+        pids+=($!)
+    done
+    wait "${pids[@]}"
+    cat "$work"/parallel_*.latency | sort -n | tail -n 1
+}
+
+echo "Committing $events rows per table, $transactions commits per run"
+printf '%-10s' "RTT (ms)"
+for mode in $modes; do
+    printf '%14s' "$mode"
+done
+echo
+for delay in $delays; do
+    python3 scripts/bench/latency_proxy.py --listen "127.0.0.1:$proxy_port" --target "$db_host:$db_port" \
+        --delay-ms "$delay" &
+    proxy_pid=$!
+    sleep 1
+    printf '%-10s' "$(python3 -c "print(round(2 * $delay, 2))")"
+    for mode in $modes; do
+        printf '%14s' "$(measure "$mode")"
+    done
+    echo
+    kill "$proxy_pid"
+    wait "$proxy_pid" 2>/dev/null || true
+    proxy_pid=
+done
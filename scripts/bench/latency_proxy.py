@@ -0,0 +1,79 @@
+#!/usr/bin/env python3
+"""TCP proxy that delays every chunk by a fixed time in each direction, to measure how the
+indexer behaves against a database or checkpoint store that is a network hop away.
+
+    latency_proxy.py --listen 127.0.0.1:6543 --target 127.0.0.1:5432 --delay-ms 1
+
+A round trip through the proxy takes at least twice --delay-ms. Chunks are forwarded in
+order; the delay is added to their arrival time, so pipelined requests overlap their delays
+like they would on a real link.
+"""
+
+import argparse
+import asyncio
+import time
+
+
+def parse_address(value):
+    host, _, port = value.rpartition(":")
+    return host, int(port)
+
+
+async def forward(reader, writer, delay):
+    queue = asyncio.Queue()
+
+    async def send():
+        while True:
+            due, data = await queue.get()
+            wait = due - time.monotonic()
+            if wait > 0:
+                await asyncio.sleep(wait)
+            if not data:
+                writer.close()
+                return
+            writer.write(data)
+            await writer.drain()
+
+    sender = asyncio.create_task(send())
+    try:
+        while True:
+            data = await reader.read(65536)
+            queue.put_nowait((time.monotonic() + delay, data))
+            if not data:
+                break
+        await sender
+    except (ConnectionError, asyncio.CancelledError):
+        sender.cancel()
+        writer.close()
+
+
+async def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--listen", type=parse_address, required=True, help="host:port to accept connections on")
+    parser.add_argument("--target", type=parse_address, required=True, help="host:port to forward connections to")
+    parser.add_argument("--delay-ms", type=float, required=True, help="one-way delay added in each direction")
+    args = parser.parse_args()
+    delay = args.delay_ms / 1000
+
+    async def handle(client_reader, client_writer):
+        try:
+            server_reader, server_writer = await asyncio.open_connection(*args.target)
+        except OSError:
+            client_writer.close()
+            return
+        await asyncio.gather(
+            forward(client_reader, server_writer, delay),
+            forward(server_reader, client_writer, delay),
+            return_exceptions=True,
+        )
+
+    server = await asyncio.start_server(handle, *args.listen)
+    async with server:
+        await server.serve_forever()
+
+
+if __name__ == "__main__":
+    try:
+        asyncio.run(main())
+    except KeyboardInterrupt:
+        pass
@@ -0,0 +1,16 @@
+#!/usr/bin/env bash
+# Checks run in CI: build, lint and test the indexer with and without the optional features,
+# so code behind `--features clickhouse` can't rot unnoticed.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+for features in "" "--features clickhouse"; do
+    echo "--- cargo checks ${features:-(default features)}"
+    # shellcheck disable=SC2086
+    cargo build --all-targets $features
+    # shellcheck disable=SC2086
+    cargo clippy --all-targets $features -- -D warnings
+    # shellcheck disable=SC2086
+    cargo test $features
+done
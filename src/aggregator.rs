@@ -0,0 +1,311 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use diesel::sql_types::{BigInt, Integer, Text};
+use diesel::QueryableByName;
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use tracing::{error, info};
+
+use crate::sink::SwapPartitionCache;
+use crate::PgConnectionPool;
+
+// Recompute every pool's totals from the swaps table. Rows are stamped with the transaction
+// start time, so pools without swaps left (e.g. after pruning) are the ones stamped earlier.
+const REFRESH_POOL_STATS_SQL: &str = r#"
+    INSERT INTO pool_stats (pool_id, total_volume_in, total_volume_out, swap_count, last_updated)
+    SELECT pool_id, SUM(amount_in), SUM(amount_out), COUNT(*), now()
+    FROM cetus_swap_events
+    GROUP BY pool_id
+    ON CONFLICT (pool_id) DO UPDATE SET
+        total_volume_in = excluded.total_volume_in,
+        total_volume_out = excluded.total_volume_out,
+        swap_count = excluded.swap_count,
+        last_updated = excluded.last_updated
+"#;
+
+// Postgres only allows CONCURRENTLY for materialized views with a unique index
+const HAS_UNIQUE_INDEX_SQL: &str = r#"
+    SELECT COUNT(*) AS unique_indexes
+    FROM pg_index i
+    JOIN pg_class c ON c.oid = i.indrelid
+    WHERE c.relname = $1 AND i.indisunique
+"#;
+
+const DELETE_STALE_POOL_STATS_SQL: &str = "DELETE FROM pool_stats WHERE last_updated < now()";
+
+/// Rebuild the `pool_stats` summary table in a single transaction, so readers never see
+/// a partially refreshed table. Returns the number of pools with swaps.
+pub async fn refresh_pool_stats(pool: &PgConnectionPool) -> Result<usize> {
+    let mut conn = pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+    let pools = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            async move {
+                let pools = diesel::sql_query(REFRESH_POOL_STATS_SQL).execute(conn).await?;
+                diesel::sql_query(DELETE_STALE_POOL_STATS_SQL).execute(conn).await?;
+                Ok(pools)
+            }
+            .scope_boxed()
+        })
+        .await?;
+    Ok(pools)
+}
+
+/// Refresh `pool_stats` every `interval` in the background. A failed refresh is logged and
+/// retried on the next tick; the table keeps its previous contents in the meantime.
+pub fn spawn_pool_stats_refresher(pool: PgConnectionPool, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let started = Instant::now();
+            match refresh_pool_stats(&pool).await {
+                Ok(pools) => info!(
+                    "Refreshed pool_stats for {} pools in {:?}",
+                    pools,
+                    started.elapsed()
+                ),
+                Err(e) => error!("Failed to refresh pool_stats: {}", e),
+            }
+        }
+    });
+}
+
+// Create the monthly partition of cetus_swap_events holding a date, see the partitioning migration
+const CREATE_SWAP_PARTITION_SQL: &str = r#"
+    SELECT create_cetus_swap_events_partition(
+        (date_trunc('month', now() AT TIME ZONE 'UTC') + make_interval(months => $1))::DATE
+    ) AS partition_name
+"#;
+
+#[derive(QueryableByName)]
+struct SwapPartition {
+    #[diesel(sql_type = Text)]
+    partition_name: String,
+}
+
+// Monthly partitions of cetus_swap_events, oldest first. The partition of swaps without a
+// timestamp doesn't match the name pattern, so it is never created or dropped here.
+const LIST_SWAP_PARTITIONS_SQL: &str = r#"
+    SELECT c.relname::TEXT AS partition_name
+    FROM pg_inherits i
+    JOIN pg_class c ON c.oid = i.inhrelid
+    JOIN pg_class p ON p.oid = i.inhparent
+    WHERE p.relname = 'cetus_swap_events' AND c.relname ~ '^cetus_swap_events_[0-9]{4}_[0-9]{2}$'
+    ORDER BY c.relname
+"#;
+
+// Name of the oldest monthly partition to keep when keeping `$1` months before the current one
+const OLDEST_KEPT_SWAP_PARTITION_SQL: &str = r#"
+    SELECT format(
+        'cetus_swap_events_%s',
+        to_char(date_trunc('month', now() AT TIME ZONE 'UTC') - make_interval(months => $1), 'YYYY_MM')
+    ) AS partition_name
+"#;
+
+// Release the ids of swaps older than the month `$1` months before the current one. Detaching
+// or dropping a partition doesn't fire the trigger that releases the ids of deleted swaps.
+const EXPIRE_SWAP_EVENT_IDS_SQL: &str = r#"
+    DELETE FROM cetus_swap_event_ids
+    WHERE timestamp_ms > 0
+      AND timestamp_ms < (EXTRACT(EPOCH FROM
+          (date_trunc('month', now() AT TIME ZONE 'UTC') - make_interval(months => $1)) AT TIME ZONE 'UTC'
+      ) * 1000)::INT8
+"#;
+
+// Appended to the name of a detached partition, see the archived partitions migration
+const ARCHIVED_SWAP_PARTITION_SUFFIX: &str = "_archived";
+
+/// What happens to monthly swap partitions older than the retention
+#[derive(Clone, Copy, Debug)]
+pub struct SwapPartitionRetention {
+    /// Full months kept before the current one
+    pub months: u32,
+    /// Drop expired partitions; otherwise they are only detached and kept as plain tables
+    pub drop: bool,
+}
+
+async fn swap_partition_names(conn: &mut AsyncPgConnection) -> Result<Vec<String>> {
+    let partitions = diesel::sql_query(LIST_SWAP_PARTITIONS_SQL)
+        .load::<SwapPartition>(conn)
+        .await?;
+    Ok(partitions.into_iter().map(|partition| partition.partition_name).collect())
+}
+
+/// Make sure the monthly swap partitions exist from the current month up to `months_ahead`
+/// months ahead. The sink also creates the partition of every month it writes swaps for, so
+/// this only keeps partition creation off the write path for live checkpoints. Returns the
+/// partitions that were created.
+pub async fn create_swap_partitions(pool: &PgConnectionPool, months_ahead: u32) -> Result<Vec<String>> {
+    let mut conn = pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+    let existing = swap_partition_names(&mut conn).await?;
+    let mut created = Vec::new();
+    for month in 0..=months_ahead {
+        let partition = diesel::sql_query(CREATE_SWAP_PARTITION_SQL)
+            .bind::<Integer, _>(month as i32)
+            .get_result::<SwapPartition>(&mut conn)
+            .await?;
+        if !existing.contains(&partition.partition_name) {
+            created.push(partition.partition_name);
+        }
+    }
+    Ok(created)
+}
+
+/// Detach (and optionally drop) the monthly swap partitions older than the retention, in one
+/// transaction with releasing their swaps' ids. Detached partitions are renamed with an
+/// `_archived` suffix, so a later swap of that month gets a new partition instead of hitting
+/// the detached table's name. Returns the partitions that were removed.
+pub async fn expire_swap_partitions(pool: &PgConnectionPool, retention: SwapPartitionRetention) -> Result<Vec<String>> {
+    let mut conn = pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+    let oldest_kept = diesel::sql_query(OLDEST_KEPT_SWAP_PARTITION_SQL)
+        .bind::<Integer, _>(retention.months as i32)
+        .get_result::<SwapPartition>(&mut conn)
+        .await?
+        .partition_name;
+    // Names end in a zero-padded YYYY_MM, so they sort by month
+    let expired = swap_partition_names(&mut conn)
+        .await?
+        .into_iter()
+        .filter(|partition| *partition < oldest_kept)
+        .collect::<Vec<_>>();
+    if expired.is_empty() {
+        return Ok(expired);
+    }
+
+    let partitions = &expired;
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        async move {
+            for partition in partitions {
+                // The names come from the catalog and match the monthly pattern, so they are safe to interpolate
+                diesel::sql_query(format!("ALTER TABLE cetus_swap_events DETACH PARTITION \"{}\"", partition))
+                    .execute(conn)
+                    .await?;
+                let statement = if retention.drop {
+                    format!("DROP TABLE \"{}\"", partition)
+                } else {
+                    format!("ALTER TABLE \"{}\" RENAME TO \"{}{}\"", partition, partition, ARCHIVED_SWAP_PARTITION_SUFFIX)
+                };
+                diesel::sql_query(statement).execute(conn).await?;
+            }
+            diesel::sql_query(EXPIRE_SWAP_EVENT_IDS_SQL)
+                .bind::<Integer, _>(retention.months as i32)
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await?;
+
+    for partition in &expired {
+        if retention.drop {
+            info!("Dropped expired swap partition {}", partition);
+        } else {
+            info!("Detached expired swap partition {} as {}{}", partition, partition, ARCHIVED_SWAP_PARTITION_SUFFIX);
+        }
+    }
+    Ok(expired)
+}
+
+/// Create upcoming swap partitions (unless `months_ahead` is 0) and expire old ones now and
+/// then once a day, so inserts always have a partition and old months don't pile up. The sink's
+/// `partition_cache` is cleared after partitions expire, so it no longer assumes they exist.
+pub fn spawn_swap_partition_maintainer(
+    pool: PgConnectionPool,
+    months_ahead: u32,
+    retention: Option<SwapPartitionRetention>,
+    partition_cache: SwapPartitionCache,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(24 * 60 * 60));
+        loop {
+            ticker.tick().await;
+            if months_ahead > 0 {
+                match create_swap_partitions(&pool, months_ahead).await {
+                    Ok(created) => {
+                        for partition in created {
+                            info!("Created swap partition {}", partition);
+                        }
+                    }
+                    Err(e) => error!("Failed to create swap partitions: {}", e),
+                }
+            }
+            if let Some(retention) = retention {
+                match expire_swap_partitions(&pool, retention).await {
+                    Ok(expired) if !expired.is_empty() => partition_cache.clear(),
+                    Ok(_) => {}
+                    Err(e) => error!("Failed to expire swap partitions: {}", e),
+                }
+            }
+        }
+    });
+}
+
+#[derive(QueryableByName)]
+struct UniqueIndexCount {
+    #[diesel(sql_type = BigInt)]
+    unique_indexes: i64,
+}
+
+/// Parse a comma-separated list of materialized view names, rejecting anything that is not a
+/// plain (optionally schema-qualified) identifier since the names are interpolated into SQL
+pub fn parse_view_names(value: &str) -> Result<Vec<String>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let valid = name.split('.').all(|part| {
+                !part.is_empty()
+                    && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    && !part.starts_with(|c: char| c.is_ascii_digit())
+            });
+            if valid {
+                Ok(name.to_string())
+            } else {
+                Err(anyhow!("Invalid materialized view name '{}'", name))
+            }
+        })
+        .collect()
+}
+
+/// Refresh one materialized view, concurrently when it has a unique index so readers are not
+/// blocked while it is rebuilt
+pub async fn refresh_materialized_view(pool: &PgConnectionPool, view: &str) -> Result<()> {
+    let mut conn = pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+    let relname = view.rsplit('.').next().unwrap_or(view);
+    let concurrently = diesel::sql_query(HAS_UNIQUE_INDEX_SQL)
+        .bind::<Text, _>(relname)
+        .get_result::<UniqueIndexCount>(&mut conn)
+        .await?
+        .unique_indexes
+        > 0;
+    let sql = if concurrently {
+        format!("REFRESH MATERIALIZED VIEW CONCURRENTLY {}", view)
+    } else {
+        format!("REFRESH MATERIALIZED VIEW {}", view)
+    };
+    diesel::sql_query(sql).execute(&mut conn).await?;
+    Ok(())
+}
+
+/// Refresh the given materialized views one after another every `interval`, logging how long
+/// each one took. Failures are logged and retried on the next tick.
+pub fn spawn_view_refresher(pool: PgConnectionPool, views: Vec<String>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for view in &views {
+                let started = Instant::now();
+                match refresh_materialized_view(&pool, view).await {
+                    Ok(()) => info!("Refreshed materialized view {} in {:?}", view, started.elapsed()),
+                    Err(e) => error!("Failed to refresh materialized view {}: {}", view, e),
+                }
+            }
+        }
+    });
+}
@@ -1,49 +1,750 @@
-use actix_web::{web, HttpResponse, Responder, Error};
+use actix_web::{http::{header, StatusCode}, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use diesel::dsl::sql;
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Bool, Double, Integer, Nullable, Numeric, SmallInt, Text, Timestamptz};
 use diesel_async::RunQueryDsl;
+use move_core_types::language_storage::TypeTag;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use std::{collections::HashMap, env, fmt};
+use subtle::ConstantTimeEq;
 
-use crate::models::{CetusSwapEvent, CetusAddLiquidityEvent, CetusRemoveLiquidityEvent};
-use crate::schema::{cetus_swap_events, cetus_add_liquidity_events, cetus_remove_liquidity_events};
-use crate::PgConnectionPool;
+use crate::dynamic::ProtocolsConfig;
+use crate::export::{swap_schema, swaps_to_record_batch};
+use crate::indexer::{normalize_object_id, CetusIndexer};
+use crate::price::{sqrt_price_to_price, time_weighted_average};
+use crate::registry::EventTable;
+use crate::models::{CetusSwapEvent, CetusAddLiquidityEvent, CetusRemoveLiquidityEvent, ObservedEventType};
+use crate::schema::{cetus_swap_events, cetus_add_liquidity_events, cetus_remove_liquidity_events, observed_event_types};
+use crate::{PgConnectionPool, PgPoolConnection};
+
+// Fallback page size when no per-endpoint default is configured
+const DEFAULT_PER_PAGE: i64 = 20;
+
+// Deepest row offset served by list endpoints unless MAX_OFFSET overrides it; high offsets
+// are slow to scan and allow extracting whole tables page by page
+const DEFAULT_MAX_OFFSET: i64 = 10_000;
+
+// Cached response bodies kept unless STATS_CACHE_MAX_ENTRIES overrides it
+const DEFAULT_CACHE_MAX_ENTRIES: usize = 1000;
+
+// API configuration loaded once at startup and shared with the handlers
+#[derive(Clone)]
+pub struct ApiConfig {
+    pub swaps_default_per_page: i64,
+    pub add_liquidity_default_per_page: i64,
+    pub remove_liquidity_default_per_page: i64,
+    pub volume_default_per_page: i64,
+    /// Bearer token required by the /api/admin endpoints; they are disabled when unset
+    pub admin_token: Option<String>,
+    /// Largest row offset list endpoints will page to; `None` allows any offset
+    pub max_offset: Option<i64>,
+    /// The indexer's SKIP_CHECKPOINTS_BELOW; those checkpoints are never recorded as processed
+    pub skip_checkpoints_below: i64,
+}
+
+impl ApiConfig {
+    /// Load per-endpoint settings from environment variables
+    pub fn from_env() -> Self {
+        Self {
+            swaps_default_per_page: default_per_page_from_env("SWAPS_DEFAULT_PER_PAGE"),
+            add_liquidity_default_per_page: default_per_page_from_env("ADD_LIQUIDITY_DEFAULT_PER_PAGE"),
+            remove_liquidity_default_per_page: default_per_page_from_env("REMOVE_LIQUIDITY_DEFAULT_PER_PAGE"),
+            volume_default_per_page: default_per_page_from_env("VOLUME_DEFAULT_PER_PAGE"),
+            admin_token: env::var("ADMIN_API_TOKEN").ok().filter(|token| !token.is_empty()),
+            max_offset: max_offset_from_env(),
+            skip_checkpoints_below: env::var("SKIP_CHECKPOINTS_BELOW")
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+                .filter(|value| *value >= 0)
+                .unwrap_or(0),
+        }
+    }
+}
+
+// Read a positive page size from the environment, falling back to DEFAULT_PER_PAGE
+fn default_per_page_from_env(var: &str) -> i64 {
+    env::var(var)
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_PER_PAGE)
+}
+
+// In-memory cache for the aggregate endpoints, keyed by endpoint and parsed query parameters.
+// Entries expire after STATS_CACHE_TTL_SECS; a TTL of 0 (the default) disables caching.
+// recent_candles has its own RECENT_CANDLES_CACHE_TTL_SECS, since its last candle moves every poll.
+// At most STATS_CACHE_MAX_ENTRIES bodies are kept; the oldest is evicted to make room.
+pub struct ResponseCache {
+    ttl: Duration,
+    recent_candles_ttl: Duration,
+    max_entries: usize,
+    entries: RwLock<HashMap<String, CacheEntry>>,
+}
+
+struct CacheEntry {
+    stored_at: Instant,
+    ttl: Duration,
+    body: serde_json::Value,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self) -> bool {
+        self.stored_at.elapsed() < self.ttl
+    }
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration, recent_candles_ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            recent_candles_ttl,
+            max_entries,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let secs_from_env = |var: &str| {
+            env::var(var)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+        let max_entries = env::var("STATS_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(DEFAULT_CACHE_MAX_ENTRIES);
+        Self::new(
+            Duration::from_secs(secs_from_env("STATS_CACHE_TTL_SECS")),
+            Duration::from_secs(secs_from_env("RECENT_CANDLES_CACHE_TTL_SECS")),
+            max_entries,
+        )
+    }
+
+    fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|entry| entry.is_fresh())
+            .map(|entry| entry.body.clone())
+    }
+
+    fn insert(&self, key: String, ttl: Duration, body: serde_json::Value) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        // Drop expired entries so the map only holds keys polled within their TTL
+        entries.retain(|_, entry| entry.is_fresh());
+        if !entries.contains_key(&key) && entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            CacheEntry {
+                stored_at: Instant::now(),
+                ttl,
+                body,
+            },
+        );
+    }
+}
+
+// Serve a fresh cached body for `key`, or run `load` and cache its result for STATS_CACHE_TTL_SECS.
+// `key` must be built from the parsed parameters, so unknown or reordered ones share an entry.
+async fn cached_json<T, F>(cache: &ResponseCache, key: String, load: F) -> Result<HttpResponse, ApiError>
+where
+    T: Serialize,
+    F: Future<Output = Result<T, ApiError>>,
+{
+    cached_json_for(cache, cache.ttl, key, load).await
+}
+
+// Like cached_json with an endpoint-specific TTL.
+// When caching is enabled the body gets a `cached` field telling clients whether it came from the cache.
+async fn cached_json_for<T, F>(
+    cache: &ResponseCache,
+    ttl: Duration,
+    key: String,
+    load: F,
+) -> Result<HttpResponse, ApiError>
+where
+    T: Serialize,
+    F: Future<Output = Result<T, ApiError>>,
+{
+    if ttl.is_zero() {
+        return Ok(HttpResponse::Ok().json(load.await?));
+    }
+
+    if let Some(body) = cache.get(&key) {
+        return Ok(with_cache_hint(body, true));
+    }
+
+    let body = serde_json::to_value(load.await?).map_err(|e| {
+        tracing::error!("Failed to serialize response: {}", e);
+        ApiError::Internal("Failed to serialize response".to_string())
+    })?;
+    cache.insert(key, ttl, body.clone());
+    Ok(with_cache_hint(body, false))
+}
+
+fn with_cache_hint(mut body: serde_json::Value, cached: bool) -> HttpResponse {
+    if let serde_json::Value::Object(fields) = &mut body {
+        fields.insert("cached".to_string(), serde_json::Value::Bool(cached));
+    }
+    HttpResponse::Ok().json(body)
+}
+
+// Read MAX_OFFSET, defaulting to DEFAULT_MAX_OFFSET; 0 disables the cap
+fn max_offset_from_env() -> Option<i64> {
+    let max_offset = env::var("MAX_OFFSET")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+        .unwrap_or(DEFAULT_MAX_OFFSET);
+    (max_offset > 0).then_some(max_offset)
+}
+
+// Supported bucket sizes for time series endpoints, in milliseconds
+const VOLUME_MA_INTERVALS: &[(&str, i64)] = &[("1h", 3_600_000), ("1d", 86_400_000)];
+
+// Buckets returned by GET /api/pools/{pool}/volume_ma without start_ms, and at most
+const VOLUME_MA_DEFAULT_BUCKETS: i64 = 90;
+const VOLUME_MA_MAX_BUCKETS: i64 = 1000;
+
+// Supported moving-average windows, in milliseconds
+const VOLUME_MA_WINDOWS: &[(&str, i64)] = &[
+    ("24h", 86_400_000),
+    ("7d", 604_800_000),
+    ("14d", 1_209_600_000),
+    ("30d", 2_592_000_000),
+];
+
+// Supported TWAP windows, in milliseconds
+const TWAP_WINDOWS: &[(&str, i64)] = &[
+    ("5m", 300_000),
+    ("15m", 900_000),
+    ("1h", 3_600_000),
+    ("4h", 14_400_000),
+    ("24h", 86_400_000),
+    ("7d", 604_800_000),
+];
+
+// Errors returned to API clients as JSON
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    /// The requested resource, e.g. a pool, is unknown to the indexer
+    NotFound(String),
+    /// The table behind the endpoint does not exist, e.g. its migration was not run
+    NotEnabled(String),
+    Internal(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::NotEnabled(message)
+            | ApiError::Internal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::NotEnabled(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            error: self.to_string(),
+        })
+    }
+}
+
+impl From<diesel::result::Error> for ApiError {
+    fn from(error: diesel::result::Error) -> Self {
+        match &error {
+            // Postgres reports a missing table as `relation "<table>" does not exist`
+            diesel::result::Error::DatabaseError(_, info)
+                if info.message().starts_with("relation ") && info.message().ends_with(" does not exist") =>
+            {
+                ApiError::NotEnabled(format!("This event type is not enabled: {}", info.message()))
+            }
+            _ => {
+                tracing::error!("Database error: {}", error);
+                ApiError::Internal("Database error".to_string())
+            }
+        }
+    }
+}
+
+// Check out a pooled connection, reporting pool errors as a 500
+async fn get_connection(pool: &PgConnectionPool) -> Result<PgPoolConnection<'_>, ApiError> {
+    let _waiter = ConnectionWaiter::new();
+    pool.get().await.map_err(|e| {
+        tracing::error!("Failed to get DB connection: {}", e);
+        ApiError::Internal("Failed to get DB connection".to_string())
+    })
+}
+
+// Requests waiting for a pooled connection, for GET /api/admin/pool_stats; bb8's pool state
+// only counts connections, not the requests queued for one
+static CONNECTION_WAITERS: AtomicUsize = AtomicUsize::new(0);
+
+// Counted in CONNECTION_WAITERS until dropped, so cancelled requests stop counting too
+struct ConnectionWaiter;
+
+impl ConnectionWaiter {
+    fn new() -> Self {
+        CONNECTION_WAITERS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for ConnectionWaiter {
+    fn drop(&mut self) {
+        CONNECTION_WAITERS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Whether a pool has any indexed event
+#[derive(QueryableByName)]
+struct PoolKnown {
+    #[diesel(sql_type = Bool)]
+    known: bool,
+}
+
+// Reject pools without any indexed event with a 404, so pool-scoped endpoints can tell a
+// bogus pool id apart from a known pool with no activity in the requested range
+async fn require_known_pool(conn: &mut PgPoolConnection<'_>, pool_id: &str) -> Result<(), ApiError> {
+    let row = diesel::sql_query(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM cetus_swap_events WHERE pool_id = $1)
+            OR EXISTS (SELECT 1 FROM cetus_add_liquidity_events WHERE pool = $1)
+            OR EXISTS (SELECT 1 FROM cetus_remove_liquidity_events WHERE pool = $1) AS known
+        "#,
+    )
+    .bind::<Text, _>(pool_id)
+    .get_result::<PoolKnown>(conn)
+    .await?;
+    if row.known {
+        Ok(())
+    } else {
+        Err(ApiError::NotFound(format!("Unknown pool {}", pool_id)))
+    }
+}
+
+// Error body for failed requests
+#[derive(Serialize)]
+pub struct ErrorResponse {
+    error: String,
+}
 
 // Query parameters for pagination
 #[derive(Deserialize)]
 pub struct PaginationParams {
-    page: Option<i64>,
-    per_page: Option<i64>,
+    page: Option<String>,
+    per_page: Option<String>,
+}
+
+// Query parameters for GET /api/swaps and the endpoints built on it. Every filter is
+// optional and they combine with AND.
+#[derive(Deserialize)]
+pub struct SwapQuery {
+    page: Option<String>,
+    per_page: Option<String>,
+    /// Only include swaps in this pool
+    pool: Option<String>,
+    /// Only include swaps at or after this checkpoint timestamp
+    start_ms: Option<i64>,
+    /// Only include swaps before this checkpoint timestamp
+    end_ms: Option<i64>,
+    /// Only include swaps with at least this amount_in
+    min_amount_in: Option<i64>,
+    /// Only include swaps with at most this amount_in
+    max_amount_in: Option<i64>,
+    /// Only include swaps selling coin A (`true`) or coin B (`false`)
+    atob: Option<bool>,
+    /// Only include swaps whose input side is worth at least this many USD
+    min_usd: Option<f64>,
+    /// Only include swaps that moved the pool's sqrt price by at least this many basis points
+    min_price_impact_bps: Option<i64>,
+    /// `event_seq` (default, newest first) or `id`
+    sort: Option<String>,
+    /// Comma-separated subset of SWAP_FIELDS to return; all fields when omitted
+    fields: Option<String>,
+    /// Only include swaps sent by this address
+    sender: Option<String>,
+    /// How amount_in and amount_out are written: raw (default), decimal or ui
+    amount_format: Option<String>,
+    /// `offset` (default, with `page`) or `keyset` (with `cursor`)
+    pagination: Option<String>,
+    /// `next_cursor` of the previous page, with `pagination=keyset`
+    cursor: Option<String>,
+}
+
+// Validated filters of a SwapQuery, with ids normalized
+struct SwapFilters {
+    pool: Option<String>,
+    sender: Option<String>,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    min_amount_in: Option<i64>,
+    max_amount_in: Option<i64>,
+    atob: Option<bool>,
+    min_usd: Option<f64>,
+    min_price_impact_bps: Option<i64>,
+}
+
+// Query parameters for GET /api/add_liquidity and GET /api/remove_liquidity
+#[derive(Deserialize)]
+pub struct LiquidityParams {
+    page: Option<String>,
+    per_page: Option<String>,
+    /// Only include events moving at least this much liquidity (a u128, passed as a string)
+    min_liquidity: Option<String>,
+    /// `event_seq` (default, newest first) or `id`
+    sort: Option<String>,
+    /// `offset` (default, with `page`) or `keyset` (with `cursor`)
+    pagination: Option<String>,
+    /// `next_cursor` of the previous page, with `pagination=keyset`
+    cursor: Option<String>,
+}
+
+// Query parameters for GET /api/swaps/distribution
+#[derive(Deserialize)]
+pub struct SwapDistributionParams {
+    pool: Option<String>,
+    buckets: Option<i32>,
 }
 
 // Query parameters for pool filtering
 #[derive(Deserialize)]
 pub struct PoolFilterParams {
     id_contains: String,
-    page: Option<i64>,
-    per_page: Option<i64>,
+    page: Option<String>,
+    per_page: Option<String>,
 }
 
-// Response format for GET /api/swaps
+// Response format for GET /api/swaps; swaps are projected to JSON objects when `fields` is given
 #[derive(Serialize)]
-pub struct SwapsResponse {
-    swaps: Vec<CetusSwapEvent>,
-    total: i64,
-    page: i64,
+pub struct SwapsResponse<T = CetusSwapEvent> {
+    swaps: Vec<T>,
+    /// Only set with offset pagination; keyset pages don't count the filtered rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<i64>,
+    /// Only set with offset pagination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<i64>,
     per_page: i64,
+    pagination: PaginationMetadata,
+}
+
+// Smallest and largest amount_in of the swaps being bucketed
+#[derive(QueryableByName)]
+struct AmountRange {
+    #[diesel(sql_type = Nullable<BigInt>)]
+    min_amount: Option<i64>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    max_amount: Option<i64>,
+}
+
+// Swap count of one width_bucket
+#[derive(QueryableByName)]
+struct BucketCount {
+    #[diesel(sql_type = Integer)]
+    bucket: i32,
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+// One histogram bar: swaps with lower <= amount_in < upper
+#[derive(Serialize)]
+pub struct SwapSizeBucket {
+    lower: f64,
+    upper: f64,
+    count: i64,
+}
+
+// Response format for GET /api/swaps/distribution
+#[derive(Serialize)]
+pub struct SwapDistributionResponse {
+    pool: Option<String>,
+    buckets: Vec<SwapSizeBucket>,
+}
+
+// Query parameters for GET /api/stats/swaps_per_tx
+#[derive(Deserialize)]
+pub struct SwapsPerTxParams {
+    pool: Option<String>,
+}
+
+// Transactions containing the same number of swaps
+#[derive(QueryableByName)]
+struct SwapsPerTxCount {
+    #[diesel(sql_type = BigInt)]
+    swaps: i64,
+    #[diesel(sql_type = BigInt)]
+    transactions: i64,
+}
+
+// One histogram bar: transactions with min_swaps <= swaps <= max_swaps; `max_swaps` is `None`
+// for the open-ended last bar
+#[derive(Serialize)]
+pub struct SwapsPerTxBucket {
+    min_swaps: i64,
+    max_swaps: Option<i64>,
+    transactions: i64,
+}
+
+// Response format for GET /api/stats/swaps_per_tx
+#[derive(Serialize)]
+pub struct SwapsPerTxResponse {
+    pool: Option<String>,
+    buckets: Vec<SwapsPerTxBucket>,
+    /// Transactions with at least one swap
+    transactions: i64,
+    /// Most swaps in a single transaction
+    max_swaps: i64,
 }
 
 // Response format for GET /api/add_liquidity
 #[derive(Serialize)]
 pub struct AddLiquidityResponse {
     events: Vec<CetusAddLiquidityEvent>,
-    total: i64,
-    page: i64,
+    /// Only set with offset pagination; keyset pages don't count the filtered rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<i64>,
+    /// Only set with offset pagination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<i64>,
     per_page: i64,
+    /// Only set on the endpoints that support `pagination`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pagination: Option<PaginationMetadata>,
 }
 
 // Response format for GET /api/remove_liquidity
 #[derive(Serialize)]
 pub struct RemoveLiquidityResponse {
     events: Vec<CetusRemoveLiquidityEvent>,
+    /// Only set with offset pagination; keyset pages don't count the filtered rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<i64>,
+    /// Only set with offset pagination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<i64>,
+    per_page: i64,
+    /// Only set on the endpoints that support `pagination`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pagination: Option<PaginationMetadata>,
+}
+
+// Query parameters for GET /api/positions
+#[derive(Deserialize)]
+pub struct PositionsParams {
+    pool: String,
+    page: Option<String>,
+    per_page: Option<String>,
+}
+
+// Latest state of a position, from its most recent add or remove liquidity event
+#[derive(QueryableByName, Serialize)]
+pub struct PositionSummary {
+    #[diesel(sql_type = Text)]
+    position: String,
+    #[diesel(sql_type = Text)]
+    after_liquidity: String,
+    #[diesel(sql_type = BigInt)]
+    last_activity_ms: i64,
+}
+
+// Response format for GET /api/positions
+#[derive(Serialize)]
+pub struct PositionsResponse {
+    pool: String,
+    positions: Vec<PositionSummary>,
+    total: i64,
+    page: i64,
+    per_page: i64,
+}
+
+// Add or remove liquidity event in the merged feed of a pool
+#[derive(QueryableByName, Serialize)]
+pub struct LiquidityFeedEvent {
+    /// `add` or `remove`
+    #[diesel(sql_type = Text)]
+    kind: String,
+    #[diesel(sql_type = Text)]
+    id: String,
+    #[diesel(sql_type = Text)]
+    position: String,
+    #[diesel(sql_type = Text)]
+    liquidity: String,
+    #[diesel(sql_type = Text)]
+    after_liquidity: String,
+    #[diesel(sql_type = BigInt)]
+    timestamp_ms: i64,
+    #[diesel(sql_type = Text)]
+    tx_digest: String,
+    #[diesel(sql_type = BigInt)]
+    event_index: i64,
+    #[diesel(sql_type = BigInt)]
+    checkpoint_seq: i64,
+    #[diesel(sql_type = BigInt)]
+    event_seq: i64,
+}
+
+// Response format for GET /api/pools/{pool}/liquidity_events
+#[derive(Serialize)]
+pub struct LiquidityFeedResponse {
+    pool: String,
+    events: Vec<LiquidityFeedEvent>,
+    total: i64,
+    page: i64,
+    per_page: i64,
+}
+
+// Query parameters for GET /api/pools/{pool}/recent
+#[derive(Deserialize)]
+pub struct RecentActivityParams {
+    limit: Option<String>,
+}
+
+// Swap, add or remove liquidity event in the recent activity of a pool. Fields that don't
+// apply to the event's kind are omitted.
+#[derive(QueryableByName, Serialize)]
+pub struct RecentPoolEvent {
+    /// `swap`, `add` or `remove`
+    #[diesel(sql_type = Text)]
+    kind: String,
+    #[diesel(sql_type = Text)]
+    id: String,
+    #[diesel(sql_type = Text)]
+    tx_digest: String,
+    #[diesel(sql_type = BigInt)]
+    timestamp_ms: i64,
+    #[diesel(sql_type = BigInt)]
+    event_seq: i64,
+    #[diesel(sql_type = Nullable<Text>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount_in: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount_out: Option<String>,
+    #[diesel(sql_type = Nullable<Bool>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    atob: Option<bool>,
+    #[diesel(sql_type = Nullable<Text>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    liquidity: Option<String>,
+}
+
+// Response format for GET /api/pools/{pool}/recent
+#[derive(Serialize)]
+pub struct RecentActivityResponse {
+    pool: String,
+    events: Vec<RecentPoolEvent>,
+}
+
+// Query parameters for GET /api/pools/{pool}/context
+#[derive(Deserialize)]
+pub struct EventContextParams {
+    around: Option<String>,
+    window: Option<String>,
+}
+
+// Response format for GET /api/pools/{pool}/context
+#[derive(Serialize)]
+pub struct EventContextResponse {
+    pool: String,
+    around: i64,
+    window: i64,
+    /// Up to `window` events before the requested one, the event itself and up to `window` after
+    /// it, oldest first
+    events: Vec<RecentPoolEvent>,
+}
+
+// A pool trading a token pair, with its swap count
+#[derive(QueryableByName, Serialize)]
+pub struct PairPool {
+    #[diesel(sql_type = Text)]
+    pool_id: String,
+    /// Coin types in the pool's own order, which may be the reverse of the requested pair
+    #[diesel(sql_type = Text)]
+    coin_type_a: String,
+    #[diesel(sql_type = Text)]
+    coin_type_b: String,
+    #[diesel(sql_type = BigInt)]
+    swap_count: i64,
+}
+
+// Response format for GET /api/pairs/{coin_a}/{coin_b}/pools
+#[derive(Serialize)]
+pub struct PairPoolsResponse {
+    coin_a: String,
+    coin_b: String,
+    pools: Vec<PairPool>,
+}
+
+// Row count of a raw SQL query
+#[derive(QueryableByName)]
+struct CountRow {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+// Query parameters for the DELETE /api/admin endpoints
+#[derive(Deserialize)]
+pub struct PruneParams {
+    before_checkpoint: i64,
+    /// Must be `true`, to guard against accidental deletes
+    confirm: Option<bool>,
+}
+
+// Response format for the DELETE /api/admin endpoints
+#[derive(Serialize)]
+pub struct PruneResponse {
+    table: String,
+    before_checkpoint: i64,
+    deleted: i64,
+}
+
+// Response format for GET /api/debug/event_types
+#[derive(Serialize)]
+pub struct EventTypesResponse {
+    event_types: Vec<ObservedEventType>,
     total: i64,
     page: i64,
     per_page: i64,
@@ -62,343 +763,2895 @@ pub struct StatsResponse {
 pub struct TimeRangeParams {
     start_date: Option<String>, // ISO 8601 format: YYYY-MM-DD
     end_date: Option<String>,   // ISO 8601 format: YYYY-MM-DD
-    page: Option<i64>,
-    per_page: Option<i64>,
+    page: Option<String>,
+    per_page: Option<String>,
+    /// Coin type to denominate volume in; only swaps involving it are counted
+    quote: Option<String>,
+    /// Read the pool_stats summary instead of aggregating the swaps table
+    approx: Option<bool>,
+    /// Leave out pools with fewer swaps, e.g. new or spam pools
+    min_swaps: Option<String>,
+}
+
+// Query parameters for GET /api/pairs/{coin_a}/{coin_b}/pools
+#[derive(Deserialize)]
+pub struct PairPoolsParams {
+    min_swaps: Option<String>,
 }
 
 // Volume statistics response
 #[derive(Serialize)]
 pub struct VolumeStatsResponse {
-    total_volume_in: i64,
-    total_volume_out: i64,
+    /// Volume totals are decimal strings, since they can exceed i64
+    total_volume_in: BigDecimal,
+    total_volume_out: BigDecimal,
     pool_stats: Vec<PoolVolumeStats>,
+    total_pools: i64,
+    /// True when served from the pool_stats summary, which may lag the swaps table
+    approximate: bool,
+    /// When the pool_stats summary was last refreshed; only set in approximate mode, after the first refresh
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_updated: Option<chrono::DateTime<chrono::Utc>>,
+    page: i64,
+    per_page: i64,
 }
 
-// Pool volume statistics
+// Response format for GET /api/volume?quote={coin_type}
 #[derive(Serialize)]
+pub struct QuoteVolumeResponse {
+    quote: String,
+    /// Decimals of the quote coin from coin_prices or coin_metadata, when known
+    decimals: Option<i16>,
+    /// Decimal string, since the total can exceed i64
+    total_volume: BigDecimal,
+    /// total_volume in whole tokens, when the decimals are known
+    total_volume_tokens: Option<f64>,
+    pool_stats: Vec<PoolQuoteVolume>,
+    total_pools: i64,
+    page: i64,
+    per_page: i64,
+}
+
+// Volume of a pool on its quote coin side
+#[derive(QueryableByName, Serialize)]
+pub struct PoolQuoteVolume {
+    #[diesel(sql_type = Text)]
+    pool_id: String,
+    #[diesel(sql_type = Numeric)]
+    volume: BigDecimal,
+    #[diesel(sql_type = BigInt)]
+    swap_count: i64,
+    #[diesel(sql_type = Nullable<Double>)]
+    volume_tokens: Option<f64>,
+}
+
+#[derive(QueryableByName)]
+struct QuoteVolumeTotals {
+    #[diesel(sql_type = Numeric)]
+    total_volume: BigDecimal,
+    #[diesel(sql_type = BigInt)]
+    total_pools: i64,
+    #[diesel(sql_type = Nullable<SmallInt>)]
+    decimals: Option<i16>,
+}
+
+// Volume totals, from the pool_stats summary table or the swaps table
+#[derive(QueryableByName)]
+struct PoolStatsTotals {
+    #[diesel(sql_type = Numeric)]
+    total_volume_in: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    total_volume_out: BigDecimal,
+    #[diesel(sql_type = BigInt)]
+    total_pools: i64,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    last_updated: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// Pool volume statistics; volumes are decimal strings, summed as NUMERIC so they can exceed i64
+#[derive(QueryableByName, Serialize)]
 pub struct PoolVolumeStats {
+    #[diesel(sql_type = Text)]
     pool_id: String,
-    volume_in: i64,
-    volume_out: i64,
+    #[diesel(sql_type = Numeric)]
+    volume_in: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    volume_out: BigDecimal,
+    #[diesel(sql_type = BigInt)]
     swap_count: i64,
 }
 
-// GET /api/swaps - Get all swap events with pagination
-pub async fn get_swaps(
-    pool: web::Data<PgConnectionPool>,
-    query: web::Query<PaginationParams>,
-) -> Result<impl Responder, Error> {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    let offset = (page - 1) * per_page;
+// Query parameters for GET /api/compare_pools
+#[derive(Deserialize)]
+pub struct ComparePoolsParams {
+    /// Comma-separated pool ids
+    ids: String,
+}
 
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
+// Swap and liquidity totals of a single pool
+#[derive(QueryableByName, Serialize)]
+pub struct PoolTotals {
+    #[diesel(sql_type = Text)]
+    pool_id: String,
+    #[diesel(sql_type = Numeric)]
+    volume_in: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    volume_out: BigDecimal,
+    #[diesel(sql_type = BigInt)]
+    swap_count: i64,
+    #[diesel(sql_type = BigInt)]
+    add_liquidity_count: i64,
+    #[diesel(sql_type = BigInt)]
+    remove_liquidity_count: i64,
+    /// Liquidity added minus liquidity removed, as a decimal string
+    #[diesel(sql_type = Numeric)]
+    net_liquidity: BigDecimal,
+}
 
-    // Get total count
-    let total = cetus_swap_events::table
-        .count()
-        .get_result::<i64>(&mut conn)
-        .await
-        .expect("Error counting swap events");
+// Pool totals plus the change in volume over the last day
+#[derive(Serialize)]
+pub struct PoolSummary {
+    #[serde(flatten)]
+    totals: PoolTotals,
+    /// Change of the last 24h volume relative to the 24h before it, in percent;
+    /// null when the prior 24h had no volume
+    volume_change_24h_pct: Option<f64>,
+}
 
-    // Get paginated swaps
-    let swaps = cetus_swap_events::table
-        .order_by(cetus_swap_events::id.desc())
-        .limit(per_page)
-        .offset(offset)
-        .load::<CetusSwapEvent>(&mut conn)
-        .await
-        .expect("Error loading swap events");
+// Swap volume of a pool within a time window
+#[derive(QueryableByName)]
+struct PoolWindowVolume {
+    #[diesel(sql_type = Text)]
+    pool_id: String,
+    #[diesel(sql_type = Numeric)]
+    volume: BigDecimal,
+}
 
-    Ok(HttpResponse::Ok().json(SwapsResponse {
-        swaps,
-        total,
-        page,
-        per_page,
-    }))
+// Response format for GET /api/compare_pools
+#[derive(Serialize)]
+pub struct ComparePoolsResponse {
+    pools: Vec<PoolSummary>,
+}
+
+// Swap totals of a referral partner
+#[derive(QueryableByName, Serialize)]
+pub struct PartnerStats {
+    #[diesel(sql_type = Text)]
+    partner: String,
+    #[diesel(sql_type = BigInt)]
+    swap_count: i64,
+    #[diesel(sql_type = Numeric)]
+    volume_in: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    volume_out: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    fee_amount: BigDecimal,
+}
+
+// Response format for GET /api/partners
+#[derive(Serialize)]
+pub struct PartnersResponse {
+    partners: Vec<PartnerStats>,
+    total: i64,
+    page: i64,
+    per_page: i64,
+}
+
+// Swap volume over a time range
+#[derive(QueryableByName, Serialize)]
+pub struct VolumeTotals {
+    #[diesel(sql_type = Numeric)]
+    volume_in: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    volume_out: BigDecimal,
+    #[diesel(sql_type = BigInt)]
+    swap_count: i64,
+}
+
+// Response format for GET /api/overview
+#[derive(Serialize)]
+pub struct OverviewResponse {
+    stats: StatsResponse,
+    volume_24h: VolumeTotals,
+    top_pools: Vec<PoolVolumeStats>,
+    latest_swaps: Vec<CetusSwapEvent>,
+}
+
+// Query parameters for moving-average volume
+#[derive(Deserialize)]
+pub struct VolumeMaParams {
+    window: Option<String>,
+    interval: Option<String>,
+    /// First bucket to return; defaults to VOLUME_MA_DEFAULT_BUCKETS intervals before end_ms
+    start_ms: Option<i64>,
+    /// End of the range, exclusive; defaults to now
+    end_ms: Option<i64>,
+}
+
+// Volume of a single time bucket and its trailing moving average
+#[derive(QueryableByName, Serialize)]
+pub struct VolumeMaPoint {
+    #[diesel(sql_type = BigInt)]
+    bucket_start_ms: i64,
+    /// Decimal string, summed as NUMERIC like the other volumes
+    #[diesel(sql_type = Numeric)]
+    volume: BigDecimal,
+    #[diesel(sql_type = Double)]
+    moving_average: f64,
+}
+
+// Response format for GET /api/pools/{pool}/volume_ma
+#[derive(Serialize)]
+pub struct VolumeMaResponse {
+    pool_id: String,
+    window: String,
+    interval: String,
+    start_ms: i64,
+    end_ms: i64,
+    points: Vec<VolumeMaPoint>,
+}
+
+// Query parameters for GET /api/pools/{pool}/twap
+#[derive(Deserialize)]
+pub struct TwapParams {
+    window: Option<String>,
+}
+
+// Pool price after a swap, as a point of the TWAP price series
+#[derive(QueryableByName)]
+struct SwapPricePoint {
+    #[diesel(sql_type = BigInt)]
+    timestamp_ms: i64,
+    #[diesel(sql_type = Text)]
+    after_sqrt_price: String,
+}
+
+// Response format for GET /api/pools/{pool}/twap
+#[derive(Serialize)]
+pub struct TwapResponse {
+    pool_id: String,
+    window: String,
+    start_ms: i64,
+    end_ms: i64,
+    /// Time-weighted price of coin A in coin B, in raw units; `None` when the pool has no swaps
+    twap: Option<f64>,
+    /// Prices averaged: the swaps in the window, plus the last swap before it when there is one
+    samples: usize,
+}
+
+// Bucket size and count of GET /api/pools/{pool}/recent_candles
+const RECENT_CANDLE_MS: i64 = 60_000;
+const RECENT_CANDLES: i64 = 60;
+
+// Swap fields needed to build candles
+#[derive(QueryableByName)]
+struct CandleSwap {
+    #[diesel(sql_type = BigInt)]
+    timestamp_ms: i64,
+    #[diesel(sql_type = Bool)]
+    atob: bool,
+    #[diesel(sql_type = BigInt)]
+    amount_in: i64,
+    #[diesel(sql_type = BigInt)]
+    amount_out: i64,
+    #[diesel(sql_type = Text)]
+    before_sqrt_price: String,
+    #[diesel(sql_type = Text)]
+    after_sqrt_price: String,
+}
+
+// One-minute OHLC bucket of GET /api/pools/{pool}/recent_candles. Prices are coin A in coin B in
+// raw units; a minute without swaps repeats the previous close, and prices are `None` until the
+// pool's first known price.
+#[derive(Serialize)]
+pub struct Candle {
+    start_ms: i64,
+    open: Option<f64>,
+    high: Option<f64>,
+    low: Option<f64>,
+    close: Option<f64>,
+    swaps: i64,
+    /// Volumes are decimal strings, since a minute's sum can exceed i64
+    volume_a: BigDecimal,
+    volume_b: BigDecimal,
+}
+
+// Response format for GET /api/pools/{pool}/recent_candles
+#[derive(Serialize)]
+pub struct RecentCandlesResponse {
+    pool_id: String,
+    start_ms: i64,
+    end_ms: i64,
+    /// Oldest first; the last candle is the minute in progress
+    candles: Vec<Candle>,
+}
+
+// Swaps in one day-of-week and hour-of-day cell of the heatmap
+#[derive(QueryableByName)]
+struct HeatmapCell {
+    #[diesel(sql_type = Integer)]
+    day_of_week: i32,
+    #[diesel(sql_type = Integer)]
+    hour: i32,
+    #[diesel(sql_type = BigInt)]
+    swaps: i64,
+}
+
+// Response format for GET /api/pools/{pool}/activity_heatmap
+#[derive(Serialize)]
+pub struct ActivityHeatmapResponse {
+    pool_id: String,
+    /// Swap counts indexed as `swaps[day_of_week][hour]` in UTC, with day 0 being Sunday
+    swaps: Vec<Vec<i64>>,
+    total: i64,
+}
+
+// Row order for list endpoints
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortOrder {
+    /// Newest first by checkpoint, transaction and event position
+    EventSeq,
+    /// Descending by the string id
+    Id,
+}
+
+// Parse the `sort` query parameter, defaulting to event_seq
+fn parse_sort_param(sort: &Option<String>) -> Result<SortOrder, ApiError> {
+    match sort.as_deref() {
+        None | Some("event_seq") => Ok(SortOrder::EventSeq),
+        Some("id") => Ok(SortOrder::Id),
+        Some(other) => Err(ApiError::BadRequest(format!(
+            "Invalid sort '{}', expected one of: event_seq, id",
+            other
+        ))),
+    }
+}
+
+// How swap amounts are written in responses
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AmountFormat {
+    /// Integer string in the coin's smallest unit
+    Raw,
+    /// Exact decimal string with the coin's decimals applied, e.g. "1234.5"
+    Decimal,
+    /// Rounded for display with thousands separators, e.g. "1,234.5678"
+    Ui,
+}
+
+// Parse the `amount_format` query parameter, defaulting to raw
+fn parse_amount_format_param(amount_format: &Option<String>) -> Result<AmountFormat, ApiError> {
+    match amount_format.as_deref() {
+        None | Some("raw") => Ok(AmountFormat::Raw),
+        Some("decimal") => Ok(AmountFormat::Decimal),
+        Some("ui") => Ok(AmountFormat::Ui),
+        Some(other) => Err(ApiError::BadRequest(format!(
+            "Invalid amount_format '{}', expected one of: raw, decimal, ui",
+            other
+        ))),
+    }
+}
+
+// Write a raw amount in a decimal or UI format. UI amounts keep 4 fractional digits, or
+// 4 significant digits below 1, truncated rather than rounded.
+fn format_amount(amount: i64, decimals: u32, format: AmountFormat) -> String {
+    let digits = amount.unsigned_abs().to_string();
+    let sign = if amount < 0 { "-" } else { "" };
+    if format == AmountFormat::Raw || decimals == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let decimals = decimals as usize;
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = padded.split_at(padded.len() - decimals);
+
+    let (integer, fraction) = match format {
+        AmountFormat::Ui => {
+            let kept = if integer == "0" {
+                fraction.find(|digit: char| digit != '0').map_or(0, |first| first + 4)
+            } else {
+                4
+            };
+            let grouped = integer
+                .as_bytes()
+                .rchunks(3)
+                .rev()
+                .map(|group| std::str::from_utf8(group).unwrap())
+                .collect::<Vec<_>>()
+                .join(",");
+            (grouped, &fraction[..kept.min(fraction.len())])
+        }
+        _ => (integer.to_string(), fraction),
+    };
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+}
+
+// Decimals of a coin type, from coin_prices or the fetched coin_metadata
+#[derive(QueryableByName)]
+struct CoinDecimals {
+    #[diesel(sql_type = Text)]
+    coin_type: String,
+    #[diesel(sql_type = SmallInt)]
+    decimals: i16,
+}
+
+// Decimals of the given coin types, preferring coin_prices like the quote volume does.
+// Coins with unknown decimals are missing from the map.
+async fn load_coin_decimals(
+    conn: &mut PgPoolConnection<'_>,
+    coin_types: Vec<String>,
+) -> Result<HashMap<String, u32>, ApiError> {
+    let rows = diesel::sql_query(
+        r#"
+        SELECT coin_type, decimals FROM coin_metadata WHERE coin_type = ANY($1)
+        UNION ALL
+        SELECT coin_type, decimals FROM coin_prices WHERE coin_type = ANY($1)
+        "#,
+    )
+    .bind::<Array<Text>, _>(coin_types)
+    .load::<CoinDecimals>(conn)
+    .await?;
+    // coin_prices rows come last and override the fetched metadata
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.coin_type, row.decimals.max(0) as u32))
+        .collect())
+}
+
+// A swap as JSON with amount_in and amount_out in the requested format. Amounts of coins with
+// unknown decimals are null rather than silently raw.
+fn format_swap_amounts(
+    swap: &CetusSwapEvent,
+    decimals: &HashMap<String, u32>,
+    format: AmountFormat,
+) -> Result<serde_json::Value, ApiError> {
+    let mut value = serde_json::to_value(swap).map_err(|e| {
+        tracing::error!("Failed to serialize response: {}", e);
+        ApiError::Internal("Failed to serialize response".to_string())
+    })?;
+    let (coin_in, coin_out) = if swap.atob {
+        (&swap.coin_type_a, &swap.coin_type_b)
+    } else {
+        (&swap.coin_type_b, &swap.coin_type_a)
+    };
+    let format_side = |amount: i64, coin_type: &String| match decimals.get(coin_type) {
+        Some(decimals) => serde_json::Value::String(format_amount(amount, *decimals, format)),
+        None => serde_json::Value::Null,
+    };
+    value["amount_in"] = format_side(swap.amount_in, coin_in);
+    value["amount_out"] = format_side(swap.amount_out, coin_out);
+    Ok(value)
+}
+
+// Parse `page` and `per_page`, which must be positive integers when given, and compute the
+// row offset, rejecting pages starting beyond `max_offset` or whose offset overflows an i64
+fn parse_pagination(
+    page: &Option<String>,
+    per_page: &Option<String>,
+    default_per_page: i64,
+    max_offset: Option<i64>,
+) -> Result<(i64, i64, i64), ApiError> {
+    let page = parse_positive_param("page", page)?.unwrap_or(1);
+    let per_page = parse_positive_param("per_page", per_page)?.unwrap_or(default_per_page);
+    let offset = (page - 1).checked_mul(per_page).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "page {} with per_page {} is out of range; the row offset must fit in a 64-bit integer",
+            page, per_page
+        ))
+    })?;
+    if let Some(max_offset) = max_offset {
+        if offset > max_offset {
+            return Err(ApiError::BadRequest(format!(
+                "page {} with per_page {} starts beyond the maximum offset of {} rows; \
+                 narrow the query with filters (e.g. by pool) instead of paginating this deep",
+                page, per_page, max_offset
+            )));
+        }
+    }
+    Ok((page, per_page, offset))
+}
+
+// How the page was selected, returned by the list endpoints that take `pagination`
+#[derive(Serialize)]
+pub struct PaginationMetadata {
+    /// `offset` or `keyset`
+    strategy: &'static str,
+    /// Pass as `cursor` to get the next page; only set with keyset pagination when more rows may follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+    /// Cost and consistency of the strategy, to help clients choose
+    tradeoff: &'static str,
+}
+
+// Selects a page of rows ordered newest first by (event_seq, id)
+trait PaginationStrategy {
+    /// Only return rows after this (event_seq, id) in that order
+    fn after(&self) -> Option<&(i64, String)>;
+
+    /// Rows to skip, after applying `after`
+    fn offset(&self) -> i64;
+
+    fn per_page(&self) -> i64;
+
+    /// Page number for the response, if the strategy has one
+    fn page(&self) -> Option<i64>;
+
+    /// Whether the response reports the total of the filtered rows, which costs counting them all
+    fn counts_total(&self) -> bool;
+
+    /// Response metadata, given the (event_seq, id) of the page's last row and whether the page was full
+    fn metadata(&self, last: Option<(i64, &str)>, full_page: bool) -> PaginationMetadata;
+}
+
+// page/per_page over OFFSET, the default
+struct OffsetPagination {
+    page: i64,
+    per_page: i64,
+    offset: i64,
+}
+
+impl PaginationStrategy for OffsetPagination {
+    fn after(&self) -> Option<&(i64, String)> {
+        None
+    }
+
+    fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    fn per_page(&self) -> i64 {
+        self.per_page
+    }
+
+    fn page(&self) -> Option<i64> {
+        Some(self.page)
+    }
+
+    fn counts_total(&self) -> bool {
+        true
+    }
+
+    fn metadata(&self, _last: Option<(i64, &str)>, _full_page: bool) -> PaginationMetadata {
+        PaginationMetadata {
+            strategy: "offset",
+            next_cursor: None,
+            tradeoff: "Allows jumping to any page, but the database still reads every skipped row, so \
+                       deep pages get slower, and pages shift while new events are indexed",
+        }
+    }
+}
+
+// Continue after the last row of the previous page, `cursor=<event_seq>:<id>`
+struct KeysetPagination {
+    per_page: i64,
+    after: Option<(i64, String)>,
+}
+
+impl PaginationStrategy for KeysetPagination {
+    fn after(&self) -> Option<&(i64, String)> {
+        self.after.as_ref()
+    }
+
+    fn offset(&self) -> i64 {
+        0
+    }
+
+    fn per_page(&self) -> i64 {
+        self.per_page
+    }
+
+    fn page(&self) -> Option<i64> {
+        None
+    }
+
+    fn counts_total(&self) -> bool {
+        false
+    }
+
+    fn metadata(&self, last: Option<(i64, &str)>, full_page: bool) -> PaginationMetadata {
+        PaginationMetadata {
+            strategy: "keyset",
+            next_cursor: last
+                .filter(|_| full_page)
+                .map(|(event_seq, id)| format!("{}:{}", event_seq, id)),
+            tradeoff: "Every page costs the same however deep, and pages don't shift while new events \
+                       are indexed, but pages can only be walked in order with next_cursor and the \
+                       total is not counted",
+        }
+    }
+}
+
+// Parse `pagination` and the parameters of the chosen strategy. Keyset pagination follows the
+// event_seq order, so it can't be combined with another sort.
+fn parse_pagination_strategy(
+    pagination: &Option<String>,
+    cursor: &Option<String>,
+    page: &Option<String>,
+    per_page: &Option<String>,
+    default_per_page: i64,
+    max_offset: Option<i64>,
+    sort: SortOrder,
+) -> Result<Box<dyn PaginationStrategy>, ApiError> {
+    match pagination.as_deref() {
+        None | Some("offset") => {
+            if cursor.is_some() {
+                return Err(ApiError::BadRequest("cursor requires pagination=keyset".to_string()));
+            }
+            let (page, per_page, offset) = parse_pagination(page, per_page, default_per_page, None)?;
+            if let Some(max_offset) = max_offset.filter(|max_offset| offset > *max_offset) {
+                return Err(ApiError::BadRequest(format!(
+                    "page {} with per_page {} starts beyond the maximum offset of {} rows; \
+                     use pagination=keyset and follow next_cursor to page this deep",
+                    page, per_page, max_offset
+                )));
+            }
+            Ok(Box::new(OffsetPagination { page, per_page, offset }))
+        }
+        Some("keyset") => {
+            if page.is_some() {
+                return Err(ApiError::BadRequest(
+                    "page is not supported with pagination=keyset; pass the previous page's next_cursor as cursor".to_string(),
+                ));
+            }
+            if sort != SortOrder::EventSeq {
+                return Err(ApiError::BadRequest("pagination=keyset requires sort=event_seq".to_string()));
+            }
+            let per_page = parse_positive_param("per_page", per_page)?.unwrap_or(default_per_page);
+            let after = cursor
+                .as_deref()
+                .map(|raw| {
+                    raw.split_once(':')
+                        .and_then(|(event_seq, id)| Some((event_seq.parse::<i64>().ok()?, id.to_string())))
+                        .filter(|(_, id)| !id.is_empty())
+                        .ok_or_else(|| ApiError::BadRequest(format!("Invalid cursor '{}', expected <event_seq>:<id>", raw)))
+                })
+                .transpose()?;
+            Ok(Box::new(KeysetPagination { per_page, after }))
+        }
+        Some(other) => Err(ApiError::BadRequest(format!(
+            "Invalid pagination '{}', expected one of: offset, keyset",
+            other
+        ))),
+    }
+}
+
+// Parse `min_swaps`, a non-negative swap count defaulting to 0 (every pool)
+fn parse_min_swaps(value: &Option<String>) -> Result<i64, ApiError> {
+    match value.as_deref() {
+        None => Ok(0),
+        Some(raw) => match raw.parse::<i64>() {
+            Ok(parsed) if parsed >= 0 => Ok(parsed),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid min_swaps '{}', expected a non-negative integer",
+                raw
+            ))),
+        },
+    }
+}
+
+fn parse_positive_param(name: &str, value: &Option<String>) -> Result<Option<i64>, ApiError> {
+    match value.as_deref() {
+        None => Ok(None),
+        Some(raw) => match raw.parse::<i64>() {
+            Ok(parsed) if parsed > 0 => Ok(Some(parsed)),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid {} '{}', expected a positive integer",
+                name, raw
+            ))),
+        },
+    }
+}
+
+// Fields of CetusSwapEvent that can be requested with `fields`
+const SWAP_FIELDS: &[&str] = &[
+    "id",
+    "amount_in",
+    "amount_out",
+    "created_at",
+    "pool_id",
+    "timestamp_ms",
+    "atob",
+    "coin_type_a",
+    "coin_type_b",
+    "package_id",
+    "tx_digest",
+    "event_index",
+    "before_sqrt_price",
+    "after_sqrt_price",
+    "price_impact_bps",
+    "partner",
+    "fee_amount",
+    "checkpoint_seq",
+    "tx_index",
+    "event_seq",
+    "sender",
+];
+
+// Parse a comma-separated `fields` parameter, rejecting names outside the allowlist
+fn parse_fields_param(fields: &Option<String>, allowed: &[&str]) -> Result<Option<Vec<String>>, ApiError> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+    let fields = fields
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            if allowed.contains(&field) {
+                Ok(field.to_string())
+            } else {
+                Err(ApiError::BadRequest(format!(
+                    "Unknown field '{}', expected any of: {}",
+                    field,
+                    allowed.join(", ")
+                )))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if fields.is_empty() {
+        return Err(ApiError::BadRequest("fields must name at least one field".to_string()));
+    }
+    Ok(Some(fields))
+}
+
+// Serialize a row and keep only the requested fields
+fn project_fields<T: Serialize>(row: &T, fields: &[String]) -> Result<serde_json::Value, ApiError> {
+    let value = serde_json::to_value(row).map_err(|e| {
+        tracing::error!("Failed to serialize response: {}", e);
+        ApiError::Internal("Failed to serialize response".to_string())
+    })?;
+    let serde_json::Value::Object(mut object) = value else {
+        return Ok(value);
+    };
+    object.retain(|key, _| fields.contains(key));
+    Ok(serde_json::Value::Object(object))
+}
+
+// Normalize a coin type query parameter to the canonical form stored in coin_type_a/coin_type_b
+fn parse_coin_type_param(name: &str, value: &str) -> Result<String, ApiError> {
+    TypeTag::from_str(value.trim())
+        .map(|type_tag| type_tag.to_canonical_string(true))
+        .map_err(|_| ApiError::BadRequest(format!("Invalid {} '{}', expected a coin type", name, value)))
+}
+
+// Normalize an object id query parameter to the stored 0x-prefixed 64 hex character form
+fn parse_object_id_param(name: &str, value: &str) -> Result<String, ApiError> {
+    normalize_object_id(value).map_err(|_| ApiError::BadRequest(format!("Invalid {} '{}'", name, value)))
+}
+
+// Look up a duration in one of the allowlists above
+fn parse_duration_param(name: &str, value: &str, allowed: &[(&str, i64)]) -> Result<i64, ApiError> {
+    allowed
+        .iter()
+        .find(|(label, _)| *label == value)
+        .map(|(_, millis)| *millis)
+        .ok_or_else(|| {
+            let labels = allowed.iter().map(|(label, _)| *label).collect::<Vec<_>>();
+            ApiError::BadRequest(format!(
+                "Invalid {} '{}', expected one of: {}",
+                name,
+                value,
+                labels.join(", ")
+            ))
+        })
+}
+
+// Build the filtered swaps query shared by the count and page queries
+fn filtered_swaps(filters: &SwapFilters) -> cetus_swap_events::BoxedQuery<'static, Pg> {
+    let mut query = cetus_swap_events::table.into_boxed();
+
+    if let Some(pool_id) = &filters.pool {
+        query = query.filter(cetus_swap_events::pool_id.eq(pool_id.clone()));
+    }
+
+    if let Some(sender) = &filters.sender {
+        query = query.filter(cetus_swap_events::sender.eq(sender.clone()));
+    }
+
+    if let Some(start_ms) = filters.start_ms {
+        query = query.filter(cetus_swap_events::timestamp_ms.ge(start_ms));
+    }
+
+    if let Some(end_ms) = filters.end_ms {
+        query = query.filter(cetus_swap_events::timestamp_ms.lt(end_ms));
+    }
+
+    if let Some(min_amount_in) = filters.min_amount_in {
+        query = query.filter(cetus_swap_events::amount_in.ge(min_amount_in));
+    }
+
+    if let Some(max_amount_in) = filters.max_amount_in {
+        query = query.filter(cetus_swap_events::amount_in.le(max_amount_in));
+    }
+
+    if let Some(atob) = filters.atob {
+        query = query.filter(cetus_swap_events::atob.eq(atob));
+    }
+
+    if let Some(min_usd) = filters.min_usd {
+        // Value the input coin with its reference price; swaps whose input coin
+        // has no price make the subquery NULL and are skipped
+        query = query.filter(
+            sql::<Bool>(
+                "(SELECT cetus_swap_events.amount_in / POWER(10, coin_prices.decimals) * coin_prices.usd_price \
+                 FROM coin_prices \
+                 WHERE coin_prices.coin_type = CASE WHEN cetus_swap_events.atob \
+                     THEN cetus_swap_events.coin_type_a ELSE cetus_swap_events.coin_type_b END) >= ",
+            )
+            .bind::<Double, _>(min_usd),
+        );
+    }
+
+    if let Some(min_price_impact_bps) = filters.min_price_impact_bps {
+        query = query.filter(cetus_swap_events::price_impact_bps.ge(min_price_impact_bps));
+    }
+
+    query
+}
+
+// Validate the filters of a swap query and normalize its ids. `sender` overrides the query's
+// sender, for endpoints that take it from the path.
+fn swap_filters(params: &SwapQuery, sender: Option<String>) -> Result<SwapFilters, ApiError> {
+    if let Some(min_usd) = params.min_usd {
+        if !min_usd.is_finite() || min_usd < 0.0 {
+            return Err(ApiError::BadRequest("min_usd must be a non-negative number".to_string()));
+        }
+    }
+
+    if params.min_price_impact_bps.is_some_and(|bps| bps < 0) {
+        return Err(ApiError::BadRequest("min_price_impact_bps must be a non-negative integer".to_string()));
+    }
+
+    if let (Some(start_ms), Some(end_ms)) = (params.start_ms, params.end_ms) {
+        if start_ms >= end_ms {
+            return Err(ApiError::BadRequest("start_ms must be before end_ms".to_string()));
+        }
+    }
+
+    if let (Some(min_amount_in), Some(max_amount_in)) = (params.min_amount_in, params.max_amount_in) {
+        if min_amount_in > max_amount_in {
+            return Err(ApiError::BadRequest("min_amount_in must not exceed max_amount_in".to_string()));
+        }
+    }
+
+    let sender = match sender {
+        Some(sender) => Some(sender),
+        None => params
+            .sender
+            .as_deref()
+            .map(|sender| parse_object_id_param("sender", sender))
+            .transpose()?,
+    };
+
+    Ok(SwapFilters {
+        pool: params
+            .pool
+            .as_deref()
+            .map(|pool_id| parse_object_id_param("pool", pool_id))
+            .transpose()?,
+        sender,
+        start_ms: params.start_ms,
+        end_ms: params.end_ms,
+        min_amount_in: params.min_amount_in,
+        max_amount_in: params.max_amount_in,
+        atob: params.atob,
+        min_usd: params.min_usd,
+        min_price_impact_bps: params.min_price_impact_bps,
+    })
+}
+
+// Reject min_liquidity values that are not a non-negative integer
+fn validate_min_liquidity(min_liquidity: &Option<String>) -> Result<(), ApiError> {
+    match min_liquidity {
+        Some(value) if value.parse::<u128>().is_err() => Err(ApiError::BadRequest(
+            "min_liquidity must be a non-negative integer".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Build the filtered add liquidity query shared by the count and page queries
+fn filtered_add_liquidity(params: &LiquidityParams) -> cetus_add_liquidity_events::BoxedQuery<'static, Pg> {
+    let mut query = cetus_add_liquidity_events::table.into_boxed();
+
+    if let Some(min_liquidity) = &params.min_liquidity {
+        // Liquidity is stored as a u128 string, so compare it as NUMERIC
+        query = query.filter(
+            sql::<Bool>("CAST(cetus_add_liquidity_events.liquidity AS NUMERIC) >= CAST(")
+                .bind::<Text, _>(min_liquidity.clone())
+                .sql(" AS NUMERIC)"),
+        );
+    }
+
+    query
+}
+
+// Build the filtered remove liquidity query shared by the count and page queries
+fn filtered_remove_liquidity(params: &LiquidityParams) -> cetus_remove_liquidity_events::BoxedQuery<'static, Pg> {
+    let mut query = cetus_remove_liquidity_events::table.into_boxed();
+
+    if let Some(min_liquidity) = &params.min_liquidity {
+        // Liquidity is stored as a u128 string, so compare it as NUMERIC
+        query = query.filter(
+            sql::<Bool>("CAST(cetus_remove_liquidity_events.liquidity AS NUMERIC) >= CAST(")
+                .bind::<Text, _>(min_liquidity.clone())
+                .sql(" AS NUMERIC)"),
+        );
+    }
+
+    query
+}
+
+// GET /api/swaps - Get all swap events with pagination
+pub async fn get_swaps(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    query: web::Query<SwapQuery>,
+) -> Result<impl Responder, ApiError> {
+    let filters = swap_filters(&query, None)?;
+    list_swaps(&pool, &config, &query, &filters).await
+}
+
+// Swaps per Parquet row group in GET /api/swaps/export.parquet; bounds the memory of an export
+const EXPORT_BATCH_SIZE: i64 = 10_000;
+
+// GET /api/swaps/export.parquet - Stream the swaps matching the /api/swaps filters as a Parquet file
+pub async fn export_swaps_parquet(
+    pool: web::Data<PgConnectionPool>,
+    query: web::Query<SwapQuery>,
+) -> Result<impl Responder, ApiError> {
+    let filters = swap_filters(&query, None)?;
+
+    // Batches are read and encoded in a separate task and handed to the response as they
+    // are ready; the small channel keeps a slow client from buffering the whole table
+    let pool = pool.get_ref().clone();
+    let (sender, receiver) = tokio::sync::mpsc::channel::<Result<web::Bytes, std::io::Error>>(2);
+    tokio::spawn(async move {
+        if let Err(e) = write_swaps_parquet(&pool, &filters, &sender).await {
+            tracing::error!("Failed to export swaps as Parquet: {:#}", e);
+            let _ = sender.send(Err(std::io::Error::other(e.to_string()))).await;
+        }
+    });
+    let body = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/vnd.apache.parquet")
+        .insert_header((header::CONTENT_DISPOSITION, "attachment; filename=\"swaps.parquet\""))
+        .streaming(body))
+}
+
+// Write the filtered swaps newest first, one row group per batch, sending the encoded bytes
+// after each row group. Stops early when the client disconnects.
+async fn write_swaps_parquet(
+    pool: &PgConnectionPool,
+    filters: &SwapFilters,
+    output: &tokio::sync::mpsc::Sender<Result<web::Bytes, std::io::Error>>,
+) -> anyhow::Result<()> {
+    let mut conn = {
+        let _waiter = ConnectionWaiter::new();
+        pool.get().await?
+    };
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(Vec::new(), swap_schema(), Some(properties))?;
+
+    // Keyset pagination on (event_seq, id), so later batches don't rescan earlier ones
+    let mut after: Option<(i64, String)> = None;
+    loop {
+        let mut batch_query = filtered_swaps(filters);
+        if let Some((last_seq, last_id)) = after.take() {
+            batch_query = batch_query.filter(
+                cetus_swap_events::event_seq
+                    .lt(last_seq)
+                    .or(cetus_swap_events::event_seq.eq(last_seq).and(cetus_swap_events::id.lt(last_id))),
+            );
+        }
+        let swaps = batch_query
+            .order_by((cetus_swap_events::event_seq.desc(), cetus_swap_events::id.desc()))
+            .limit(EXPORT_BATCH_SIZE)
+            .load::<CetusSwapEvent>(&mut conn)
+            .await?;
+        if swaps.is_empty() {
+            break;
+        }
+
+        writer.write(&swaps_to_record_batch(&swaps)?)?;
+        // Flushing closes the row group; the writer tracks offsets itself, so the encoded
+        // bytes can be taken out of its buffer
+        writer.flush()?;
+        let chunk = std::mem::take(writer.inner_mut());
+        if output.send(Ok(web::Bytes::from(chunk))).await.is_err() {
+            return Ok(());
+        }
+
+        if (swaps.len() as i64) < EXPORT_BATCH_SIZE {
+            break;
+        }
+        let last = &swaps[swaps.len() - 1];
+        after = Some((last.event_seq, last.id.clone()));
+    }
+
+    let footer = writer.into_inner()?;
+    let _ = output.send(Ok(web::Bytes::from(footer))).await;
+    Ok(())
+}
+
+// GET /api/traders/{sender}/swaps - Get the swaps sent by an address, accepting the /api/swaps filters
+pub async fn get_trader_swaps(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    path: web::Path<String>,
+    query: web::Query<SwapQuery>,
+) -> Result<impl Responder, ApiError> {
+    let sender = parse_object_id_param("sender", &path.into_inner())?;
+    let filters = swap_filters(&query, Some(sender))?;
+    list_swaps(&pool, &config, &query, &filters).await
+}
+
+// Shared by the swap list endpoints
+async fn list_swaps(
+    pool: &PgConnectionPool,
+    config: &ApiConfig,
+    query: &SwapQuery,
+    filters: &SwapFilters,
+) -> Result<HttpResponse, ApiError> {
+    let sort = parse_sort_param(&query.sort)?;
+    let strategy = parse_pagination_strategy(
+        &query.pagination,
+        &query.cursor,
+        &query.page,
+        &query.per_page,
+        config.swaps_default_per_page,
+        config.max_offset,
+        sort,
+    )?;
+    let fields = parse_fields_param(&query.fields, SWAP_FIELDS)?;
+    let amount_format = parse_amount_format_param(&query.amount_format)?;
+
+    let mut conn = get_connection(pool).await?;
+    if let Some(pool_id) = &filters.pool {
+        require_known_pool(&mut conn, pool_id).await?;
+    }
+
+    // Get total count, unless the strategy skips it
+    let total = if strategy.counts_total() {
+        Some(filtered_swaps(filters).count().get_result::<i64>(&mut conn).await?)
+    } else {
+        None
+    };
+
+    // Get paginated swaps
+    let mut page_query = match sort {
+        SortOrder::EventSeq => filtered_swaps(filters).order_by((cetus_swap_events::event_seq.desc(), cetus_swap_events::id.desc())),
+        SortOrder::Id => filtered_swaps(filters).order_by(cetus_swap_events::id.desc()),
+    };
+    if let Some((last_seq, last_id)) = strategy.after() {
+        page_query = page_query.filter(
+            cetus_swap_events::event_seq
+                .lt(*last_seq)
+                .or(cetus_swap_events::event_seq.eq(*last_seq).and(cetus_swap_events::id.lt(last_id.clone()))),
+        );
+    }
+    let per_page = strategy.per_page();
+    let swaps = page_query
+        .limit(per_page)
+        .offset(strategy.offset())
+        .load::<CetusSwapEvent>(&mut conn)
+        .await?;
+    let page = strategy.page();
+    let pagination = strategy.metadata(
+        swaps.last().map(|swap| (swap.event_seq, swap.id.as_str())),
+        swaps.len() as i64 == per_page,
+    );
+
+    if amount_format != AmountFormat::Raw {
+        let coin_types = swaps
+            .iter()
+            .flat_map(|swap| [swap.coin_type_a.clone(), swap.coin_type_b.clone()])
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        let decimals = load_coin_decimals(&mut conn, coin_types).await?;
+        let swaps = swaps
+            .iter()
+            .map(|swap| {
+                let value = format_swap_amounts(swap, &decimals, amount_format)?;
+                match &fields {
+                    Some(fields) => project_fields(&value, fields),
+                    None => Ok(value),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(HttpResponse::Ok().json(SwapsResponse {
+            swaps,
+            total,
+            page,
+            per_page,
+            pagination,
+        }));
+    }
+
+    let Some(fields) = fields else {
+        return Ok(HttpResponse::Ok().json(SwapsResponse {
+            swaps,
+            total,
+            page,
+            per_page,
+            pagination,
+        }));
+    };
+
+    let swaps = swaps
+        .iter()
+        .map(|swap| project_fields(swap, &fields))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(HttpResponse::Ok().json(SwapsResponse {
+        swaps,
+        total,
+        page,
+        per_page,
+        pagination,
+    }))
+}
+
+// GET /api/swaps/distribution - Get a log-scale histogram of swap amount_in
+pub async fn get_swap_distribution(
+    pool: web::Data<PgConnectionPool>,
+    query: web::Query<SwapDistributionParams>,
+) -> Result<impl Responder, ApiError> {
+    let bucket_count = query.buckets.unwrap_or(DEFAULT_DISTRIBUTION_BUCKETS);
+    if !(1..=MAX_DISTRIBUTION_BUCKETS).contains(&bucket_count) {
+        return Err(ApiError::BadRequest(format!(
+            "buckets must be between 1 and {}",
+            MAX_DISTRIBUTION_BUCKETS
+        )));
+    }
+    let pool_id = query
+        .pool
+        .as_deref()
+        .map(|pool_id| parse_object_id_param("pool", pool_id))
+        .transpose()?;
+
+    let mut conn = get_connection(&pool).await?;
+    if let Some(pool_id) = &pool_id {
+        require_known_pool(&mut conn, pool_id).await?;
+    }
+
+    // Zero-sized swaps have no logarithm and are left out
+    let range = diesel::sql_query(
+        r#"
+        SELECT MIN(amount_in) AS min_amount, MAX(amount_in) AS max_amount
+        FROM cetus_swap_events
+        WHERE amount_in > 0 AND ($1 IS NULL OR pool_id = $1)
+        "#,
+    )
+    .bind::<Nullable<Text>, _>(&pool_id)
+    .get_result::<AmountRange>(&mut conn)
+    .await?;
+
+    let (Some(min_amount), Some(max_amount)) = (range.min_amount, range.max_amount) else {
+        return Ok(HttpResponse::Ok().json(SwapDistributionResponse {
+            pool: pool_id,
+            buckets: Vec::new(),
+        }));
+    };
+
+    // Equal-width buckets over ln(amount_in); nudge the upper bound so the largest swap
+    // falls in the last bucket rather than the overflow bucket
+    let low = (min_amount as f64).ln();
+    let high = (max_amount as f64).ln() + 1e-9;
+    let width = (high - low) / bucket_count as f64;
+
+    let counts = diesel::sql_query(
+        r#"
+        SELECT width_bucket(LN(amount_in), $2, $3, $4) AS bucket, COUNT(*) AS count
+        FROM cetus_swap_events
+        WHERE amount_in > 0 AND ($1 IS NULL OR pool_id = $1)
+        GROUP BY bucket
+        "#,
+    )
+    .bind::<Nullable<Text>, _>(&pool_id)
+    .bind::<Double, _>(low)
+    .bind::<Double, _>(high)
+    .bind::<Integer, _>(bucket_count)
+    .load::<BucketCount>(&mut conn)
+    .await?;
+
+    // width_bucket numbers buckets from 1; fill in the empty ones
+    let mut buckets = (0..bucket_count)
+        .map(|i| SwapSizeBucket {
+            lower: (low + width * i as f64).exp(),
+            upper: (low + width * (i + 1) as f64).exp(),
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+    for row in counts {
+        let index = (row.bucket - 1).clamp(0, bucket_count - 1) as usize;
+        buckets[index].count += row.count;
+    }
+
+    Ok(HttpResponse::Ok().json(SwapDistributionResponse {
+        pool: pool_id,
+        buckets,
+    }))
+}
+
+// GET /api/add_liquidity - Get all add liquidity events with pagination
+pub async fn get_add_liquidity(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    query: web::Query<LiquidityParams>,
+) -> Result<impl Responder, ApiError> {
+    validate_min_liquidity(&query.min_liquidity)?;
+    let sort = parse_sort_param(&query.sort)?;
+    let strategy = parse_pagination_strategy(
+        &query.pagination,
+        &query.cursor,
+        &query.page,
+        &query.per_page,
+        config.add_liquidity_default_per_page,
+        config.max_offset,
+        sort,
+    )?;
+
+    let mut conn = get_connection(&pool).await?;
+
+    // Get total count, unless the strategy skips it
+    let total = if strategy.counts_total() {
+        Some(filtered_add_liquidity(&query).count().get_result::<i64>(&mut conn).await?)
+    } else {
+        None
+    };
+
+    // Get paginated events
+    let mut page_query = match sort {
+        SortOrder::EventSeq => filtered_add_liquidity(&query).order_by((cetus_add_liquidity_events::event_seq.desc(), cetus_add_liquidity_events::id.desc())),
+        SortOrder::Id => filtered_add_liquidity(&query).order_by(cetus_add_liquidity_events::id.desc()),
+    };
+    if let Some((last_seq, last_id)) = strategy.after() {
+        page_query = page_query.filter(
+            cetus_add_liquidity_events::event_seq
+                .lt(*last_seq)
+                .or(cetus_add_liquidity_events::event_seq.eq(*last_seq).and(cetus_add_liquidity_events::id.lt(last_id.clone()))),
+        );
+    }
+    let per_page = strategy.per_page();
+    let events = page_query
+        .limit(per_page)
+        .offset(strategy.offset())
+        .load::<CetusAddLiquidityEvent>(&mut conn)
+        .await?;
+    let pagination = strategy.metadata(
+        events.last().map(|event| (event.event_seq, event.id.as_str())),
+        events.len() as i64 == per_page,
+    );
+
+    Ok(HttpResponse::Ok().json(AddLiquidityResponse {
+        events,
+        total,
+        page: strategy.page(),
+        per_page,
+        pagination: Some(pagination),
+    }))
+}
+
+// GET /api/remove_liquidity - Get all remove liquidity events with pagination
+pub async fn get_remove_liquidity(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    query: web::Query<LiquidityParams>,
+) -> Result<impl Responder, ApiError> {
+    validate_min_liquidity(&query.min_liquidity)?;
+    let sort = parse_sort_param(&query.sort)?;
+    let strategy = parse_pagination_strategy(
+        &query.pagination,
+        &query.cursor,
+        &query.page,
+        &query.per_page,
+        config.remove_liquidity_default_per_page,
+        config.max_offset,
+        sort,
+    )?;
+
+    let mut conn = get_connection(&pool).await?;
+
+    // Get total count, unless the strategy skips it
+    let total = if strategy.counts_total() {
+        Some(filtered_remove_liquidity(&query).count().get_result::<i64>(&mut conn).await?)
+    } else {
+        None
+    };
+
+    // Get paginated events
+    let mut page_query = match sort {
+        SortOrder::EventSeq => filtered_remove_liquidity(&query).order_by((cetus_remove_liquidity_events::event_seq.desc(), cetus_remove_liquidity_events::id.desc())),
+        SortOrder::Id => filtered_remove_liquidity(&query).order_by(cetus_remove_liquidity_events::id.desc()),
+    };
+    if let Some((last_seq, last_id)) = strategy.after() {
+        page_query = page_query.filter(
+            cetus_remove_liquidity_events::event_seq
+                .lt(*last_seq)
+                .or(cetus_remove_liquidity_events::event_seq.eq(*last_seq).and(cetus_remove_liquidity_events::id.lt(last_id.clone()))),
+        );
+    }
+    let per_page = strategy.per_page();
+    let events = page_query
+        .limit(per_page)
+        .offset(strategy.offset())
+        .load::<CetusRemoveLiquidityEvent>(&mut conn)
+        .await?;
+    let pagination = strategy.metadata(
+        events.last().map(|event| (event.event_seq, event.id.as_str())),
+        events.len() as i64 == per_page,
+    );
+
+    Ok(HttpResponse::Ok().json(RemoveLiquidityResponse {
+        events,
+        total,
+        page: strategy.page(),
+        per_page,
+        pagination: Some(pagination),
+    }))
+}
+
+// GET /api/add_liquidity/by_pool - Get add liquidity events for a specific pool
+pub async fn get_add_liquidity_by_pool(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    query: web::Query<PoolFilterParams>,
+) -> Result<impl Responder, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, config.add_liquidity_default_per_page, config.max_offset)?;
+    let id_filter = &query.id_contains;
+
+    let mut conn = get_connection(&pool).await?;
+
+    // Get total count for this filter
+    let total = diesel::QueryDsl::filter(
+        cetus_add_liquidity_events::table,
+        cetus_add_liquidity_events::id.like(format!("%{}%", id_filter))
+    )
+    .count()
+    .get_result::<i64>(&mut conn)
+    .await?;
+
+    // Get paginated events for this filter
+    let events = diesel::QueryDsl::filter(
+        cetus_add_liquidity_events::table,
+        cetus_add_liquidity_events::id.like(format!("%{}%", id_filter))
+    )
+    .order_by((cetus_add_liquidity_events::event_seq.desc(), cetus_add_liquidity_events::id.desc()))
+    .limit(per_page)
+    .offset(offset)
+    .load::<CetusAddLiquidityEvent>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(AddLiquidityResponse {
+        events,
+        total: Some(total),
+        page: Some(page),
+        per_page,
+        pagination: None,
+    }))
+}
+
+// GET /api/remove_liquidity/by_pool - Get remove liquidity events for a specific pool
+pub async fn get_remove_liquidity_by_pool(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    query: web::Query<PoolFilterParams>,
+) -> Result<impl Responder, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, config.remove_liquidity_default_per_page, config.max_offset)?;
+    let id_filter = &query.id_contains;
+
+    let mut conn = get_connection(&pool).await?;
+
+    // Get total count for this filter
+    let total = diesel::QueryDsl::filter(
+        cetus_remove_liquidity_events::table,
+        cetus_remove_liquidity_events::id.like(format!("%{}%", id_filter))
+    )
+    .count()
+    .get_result::<i64>(&mut conn)
+    .await?;
+
+    // Get paginated events for this filter
+    let events = diesel::QueryDsl::filter(
+        cetus_remove_liquidity_events::table,
+        cetus_remove_liquidity_events::id.like(format!("%{}%", id_filter))
+    )
+    .order_by((cetus_remove_liquidity_events::event_seq.desc(), cetus_remove_liquidity_events::id.desc()))
+    .limit(per_page)
+    .offset(offset)
+    .load::<CetusRemoveLiquidityEvent>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(RemoveLiquidityResponse {
+        events,
+        total: Some(total),
+        page: Some(page),
+        per_page,
+        pagination: None,
+    }))
+}
+
+// GET /api/stats - Get overall statistics
+pub async fn get_stats(
+    pool: web::Data<PgConnectionPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<impl Responder, ApiError> {
+    cached_json(&cache, "stats".to_string(), load_event_counts(&pool)).await
+}
+
+// GET /api/stats/swaps_per_tx - Get a histogram of the number of swaps per transaction
+pub async fn get_swaps_per_tx(
+    pool: web::Data<PgConnectionPool>,
+    cache: web::Data<ResponseCache>,
+    query: web::Query<SwapsPerTxParams>,
+) -> Result<impl Responder, ApiError> {
+    let pool_id = query
+        .pool
+        .as_deref()
+        .map(|pool_id| parse_object_id_param("pool", pool_id))
+        .transpose()?;
+    let key = format!("swaps_per_tx?pool={}", pool_id.as_deref().unwrap_or(""));
+    cached_json(&cache, key, load_swaps_per_tx(&pool, pool_id)).await
+}
+
+async fn load_swaps_per_tx(pool: &PgConnectionPool, pool_id: Option<String>) -> Result<SwapsPerTxResponse, ApiError> {
+    let mut conn = get_connection(pool).await?;
+    if let Some(pool_id) = &pool_id {
+        require_known_pool(&mut conn, pool_id).await?;
+    }
+
+    // Count swaps per transaction, then transactions per swap count; there are only a few
+    // distinct swap counts, so the bucketing happens here
+    let counts = diesel::sql_query(
+        r#"
+        SELECT swaps, COUNT(*) AS transactions
+        FROM (
+            SELECT COUNT(*) AS swaps
+            FROM cetus_swap_events
+            WHERE $1 IS NULL OR pool_id = $1
+            GROUP BY tx_digest
+        ) per_tx
+        GROUP BY swaps
+        "#,
+    )
+    .bind::<Nullable<Text>, _>(&pool_id)
+    .load::<SwapsPerTxCount>(&mut conn)
+    .await?;
+
+    let buckets = SWAPS_PER_TX_BUCKETS
+        .iter()
+        .map(|&(min_swaps, max_swaps)| SwapsPerTxBucket {
+            min_swaps,
+            max_swaps,
+            transactions: counts
+                .iter()
+                .filter(|count| count.swaps >= min_swaps && max_swaps.map_or(true, |max| count.swaps <= max))
+                .map(|count| count.transactions)
+                .sum(),
+        })
+        .collect();
+
+    Ok(SwapsPerTxResponse {
+        pool: pool_id,
+        buckets,
+        transactions: counts.iter().map(|count| count.transactions).sum(),
+        max_swaps: counts.iter().map(|count| count.swaps).max().unwrap_or(0),
+    })
+}
+
+// Bucket count bounds for GET /api/swaps/distribution
+const DEFAULT_DISTRIBUTION_BUCKETS: i32 = 20;
+const MAX_DISTRIBUTION_BUCKETS: i32 = 100;
+
+// Bars of GET /api/stats/swaps_per_tx as inclusive swap count ranges; single swaps are plain
+// trades, long chains are typically arbitrage or aggregator routes
+const SWAPS_PER_TX_BUCKETS: &[(i64, Option<i64>)] = &[
+    (1, Some(1)),
+    (2, Some(2)),
+    (3, Some(3)),
+    (4, Some(4)),
+    (5, Some(5)),
+    (6, Some(10)),
+    (11, Some(20)),
+    (21, None),
+];
+
+// Maximum number of pools accepted by GET /api/compare_pools
+const MAX_COMPARE_POOLS: usize = 10;
+
+// Summarize swaps and liquidity for the given pools in one query; pools without activity get zeros
+async fn load_pool_summaries(conn: &mut PgPoolConnection<'_>, pool_ids: &[String]) -> Result<Vec<PoolSummary>, ApiError> {
+    let totals = diesel::sql_query(
+        r#"
+        WITH ids AS (
+            SELECT UNNEST($1::TEXT[]) AS pool_id
+        ),
+        swaps AS (
+            SELECT pool_id,
+                   SUM(amount_in) AS volume_in,
+                   SUM(amount_out) AS volume_out,
+                   COUNT(*) AS swap_count
+            FROM cetus_swap_events
+            WHERE pool_id = ANY($1)
+            GROUP BY pool_id
+        ),
+        adds AS (
+            SELECT pool, COUNT(*) AS event_count, SUM(CAST(liquidity AS NUMERIC)) AS liquidity
+            FROM cetus_add_liquidity_events
+            WHERE pool = ANY($1)
+            GROUP BY pool
+        ),
+        removes AS (
+            SELECT pool, COUNT(*) AS event_count, SUM(CAST(liquidity AS NUMERIC)) AS liquidity
+            FROM cetus_remove_liquidity_events
+            WHERE pool = ANY($1)
+            GROUP BY pool
+        )
+        SELECT ids.pool_id,
+               COALESCE(swaps.volume_in, 0) AS volume_in,
+               COALESCE(swaps.volume_out, 0) AS volume_out,
+               COALESCE(swaps.swap_count, 0) AS swap_count,
+               COALESCE(adds.event_count, 0) AS add_liquidity_count,
+               COALESCE(removes.event_count, 0) AS remove_liquidity_count,
+               COALESCE(adds.liquidity, 0) - COALESCE(removes.liquidity, 0) AS net_liquidity
+        FROM ids
+        LEFT JOIN swaps ON swaps.pool_id = ids.pool_id
+        LEFT JOIN adds ON adds.pool = ids.pool_id
+        LEFT JOIN removes ON removes.pool = ids.pool_id
+        "#,
+    )
+    .bind::<Array<Text>, _>(pool_ids)
+    .load::<PoolTotals>(conn)
+    .await?;
+
+    // Compare the last 24h with the 24h before it
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let last_24h = load_pool_volume_between(conn, pool_ids, now_ms - 86_400_000, now_ms).await?;
+    let prior_24h = load_pool_volume_between(conn, pool_ids, now_ms - 2 * 86_400_000, now_ms - 86_400_000).await?;
+
+    Ok(totals
+        .into_iter()
+        .map(|totals| {
+            let zero = BigDecimal::zero();
+            let current = last_24h.get(&totals.pool_id).unwrap_or(&zero);
+            let previous = prior_24h.get(&totals.pool_id).unwrap_or(&zero);
+            PoolSummary {
+                volume_change_24h_pct: percent_change(previous, current),
+                totals,
+            }
+        })
+        .collect())
+}
+
+// Input volume per pool for swaps in [start_ms, end_ms)
+async fn load_pool_volume_between(
+    conn: &mut PgPoolConnection<'_>,
+    pool_ids: &[String],
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<HashMap<String, BigDecimal>, ApiError> {
+    let volumes = diesel::sql_query(
+        r#"
+        SELECT pool_id, SUM(amount_in) AS volume
+        FROM cetus_swap_events
+        WHERE pool_id = ANY($1) AND timestamp_ms >= $2 AND timestamp_ms < $3
+        GROUP BY pool_id
+        "#,
+    )
+    .bind::<Array<Text>, _>(pool_ids)
+    .bind::<BigInt, _>(start_ms)
+    .bind::<BigInt, _>(end_ms)
+    .load::<PoolWindowVolume>(conn)
+    .await?
+    .into_iter()
+    .map(|row| (row.pool_id, row.volume))
+    .collect();
+
+    Ok(volumes)
+}
+
+// Relative change from `previous` to `current` in percent, or None when `previous` is zero
+fn percent_change(previous: &BigDecimal, current: &BigDecimal) -> Option<f64> {
+    if previous.is_zero() {
+        return None;
+    }
+    ((current - previous) * BigDecimal::from(100) / previous).to_f64()
+}
+
+// Number of pools and swaps returned by GET /api/overview
+const OVERVIEW_TOP_POOLS: i64 = 5;
+const OVERVIEW_LATEST_SWAPS: i64 = 10;
+
+// Count rows in all event tables
+async fn load_event_counts(pool: &PgConnectionPool) -> Result<StatsResponse, ApiError> {
+    let mut conn = get_connection(&pool).await?;
+
+    let total_swaps = cetus_swap_events::table
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+
+    let total_add_liquidity = cetus_add_liquidity_events::table
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+
+    let total_remove_liquidity = cetus_remove_liquidity_events::table
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+
+    Ok(StatsResponse {
+        total_swaps,
+        total_add_liquidity,
+        total_remove_liquidity,
+    })
+}
+
+// Sum swap volume since the given timestamp
+async fn load_volume_since(pool: &PgConnectionPool, since_ms: i64) -> Result<VolumeTotals, ApiError> {
+    let mut conn = get_connection(&pool).await?;
+
+    diesel::sql_query(
+        r#"
+        SELECT COALESCE(SUM(amount_in), 0) AS volume_in,
+               COALESCE(SUM(amount_out), 0) AS volume_out,
+               COUNT(*) AS swap_count
+        FROM cetus_swap_events
+        WHERE timestamp_ms >= $1
+        "#,
+    )
+    .bind::<BigInt, _>(since_ms)
+    .get_result::<VolumeTotals>(&mut conn)
+    .await
+    .map_err(ApiError::from)
+}
+
+// Pools with the highest input volume
+async fn load_top_pools(pool: &PgConnectionPool, limit: i64) -> Result<Vec<PoolVolumeStats>, ApiError> {
+    let mut conn = get_connection(&pool).await?;
+
+    diesel::sql_query(
+        r#"
+        SELECT pool_id,
+               SUM(amount_in) AS volume_in,
+               SUM(amount_out) AS volume_out,
+               COUNT(*) AS swap_count
+        FROM cetus_swap_events
+        GROUP BY pool_id
+        ORDER BY SUM(amount_in) DESC
+        LIMIT $1
+        "#,
+    )
+    .bind::<BigInt, _>(limit)
+    .load::<PoolVolumeStats>(&mut conn)
+    .await
+    .map_err(ApiError::from)
+}
+
+// Most recent swaps by event order
+async fn load_latest_swaps(pool: &PgConnectionPool, limit: i64) -> Result<Vec<CetusSwapEvent>, ApiError> {
+    let mut conn = get_connection(&pool).await?;
+
+    cetus_swap_events::table
+        .order_by((cetus_swap_events::event_seq.desc(), cetus_swap_events::id.desc()))
+        .limit(limit)
+        .load::<CetusSwapEvent>(&mut conn)
+        .await
+        .map_err(ApiError::from)
+}
+
+// GET /api/overview - Counts, 24h volume, top pools and latest swaps for the dashboard in one call
+pub async fn get_overview(
+    pool: web::Data<PgConnectionPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<impl Responder, ApiError> {
+    cached_json(&cache, "overview".to_string(), load_overview(&pool)).await
+}
+
+async fn load_overview(pool: &PgConnectionPool) -> Result<OverviewResponse, ApiError> {
+    let since_ms = chrono::Utc::now().timestamp_millis() - 86_400_000;
+
+    // Each query runs on its own pooled connection so they execute concurrently
+    let (stats, volume_24h, top_pools, latest_swaps) = tokio::join!(
+        load_event_counts(pool),
+        load_volume_since(pool, since_ms),
+        load_top_pools(pool, OVERVIEW_TOP_POOLS),
+        load_latest_swaps(pool, OVERVIEW_LATEST_SWAPS),
+    );
+
+    Ok(OverviewResponse {
+        stats: stats?,
+        volume_24h: volume_24h?,
+        top_pools: top_pools?,
+        latest_swaps: latest_swaps?,
+    })
+}
+
+// GET /api/volume - Get volume statistics
+pub async fn get_volume_stats(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    cache: web::Data<ResponseCache>,
+    query: web::Query<TimeRangeParams>,
+) -> Result<impl Responder, ApiError> {
+    match &query.quote {
+        Some(_) if query.approx == Some(true) => Err(ApiError::BadRequest(
+            "approx is not supported together with quote".to_string(),
+        )),
+        Some(quote) => {
+            let quote = parse_coin_type_param("quote", quote)?;
+            let key = volume_cache_key(&config, &query, Some(&quote))?;
+            cached_json(&cache, key, load_quote_volume(&pool, &config, &query, quote)).await
+        }
+        None => {
+            let key = volume_cache_key(&config, &query, None)?;
+            cached_json(&cache, key, load_volume_stats(&pool, &config, &query)).await
+        }
+    }
+}
+
+// Cache key of GET /api/volume from the parameters as the loaders parse them
+fn volume_cache_key(config: &ApiConfig, query: &TimeRangeParams, quote: Option<&str>) -> Result<String, ApiError> {
+    let (page, per_page, _) = parse_pagination(&query.page, &query.per_page, config.volume_default_per_page, config.max_offset)?;
+    let min_swaps = parse_min_swaps(&query.min_swaps)?;
+    Ok(format!(
+        "volume?quote={}&approx={}&page={}&per_page={}&min_swaps={}",
+        quote.unwrap_or(""),
+        query.approx.unwrap_or(false),
+        page,
+        per_page,
+        min_swaps
+    ))
+}
+
+// Each swap's amount on the quote coin side: the input amount when the quote coin was sold
+// (coin A with atob, or coin B without), the output amount otherwise. Swaps not involving
+// the quote coin are skipped.
+const QUOTE_VOLUME_SWAPS: &str = r#"
+    SELECT pool_id,
+           CASE WHEN (coin_type_a = $1) = atob THEN amount_in ELSE amount_out END AS quote_amount
+    FROM cetus_swap_events
+    WHERE coin_type_a = $1 OR coin_type_b = $1
+"#;
+
+// Decimals of the quote coin: the hand-maintained coin_prices entry, else the on-chain metadata
+const QUOTE_DECIMALS: &str = r#"COALESCE(
+    (SELECT decimals FROM coin_prices WHERE coin_type = $1),
+    (SELECT decimals FROM coin_metadata WHERE coin_type = $1)
+)"#;
+
+async fn load_quote_volume(
+    pool: &PgConnectionPool,
+    config: &ApiConfig,
+    query: &TimeRangeParams,
+    quote: String,
+) -> Result<QuoteVolumeResponse, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, config.volume_default_per_page, config.max_offset)?;
+    let min_swaps = parse_min_swaps(&query.min_swaps)?;
+
+    let mut conn = get_connection(pool).await?;
+
+    // total_pools only counts the pools listed with min_swaps, so it matches the pagination
+    let totals = diesel::sql_query(format!(
+        r#"
+        SELECT COALESCE(SUM(quote_amount), 0) AS total_volume,
+               (SELECT COUNT(*) FROM (
+                    SELECT pool_id FROM ({swaps}) pool_swaps GROUP BY pool_id HAVING COUNT(*) >= $2
+                ) listed_pools) AS total_pools,
+               {decimals} AS decimals
+        FROM ({swaps}) swaps
+        "#,
+        decimals = QUOTE_DECIMALS,
+        swaps = QUOTE_VOLUME_SWAPS
+    ))
+    .bind::<Text, _>(&quote)
+    .bind::<BigInt, _>(min_swaps)
+    .get_result::<QuoteVolumeTotals>(&mut conn)
+    .await?;
+
+    let pool_stats = diesel::sql_query(format!(
+        r#"
+        SELECT pool_id,
+               SUM(quote_amount) AS volume,
+               COUNT(*) AS swap_count,
+               SUM(quote_amount)::FLOAT8 / POWER(10, {}) AS volume_tokens
+        FROM ({}) swaps
+        GROUP BY pool_id
+        HAVING COUNT(*) >= $4
+        ORDER BY SUM(quote_amount) DESC, pool_id
+        LIMIT $2 OFFSET $3
+        "#,
+        QUOTE_DECIMALS, QUOTE_VOLUME_SWAPS
+    ))
+    .bind::<Text, _>(&quote)
+    .bind::<BigInt, _>(per_page)
+    .bind::<BigInt, _>(offset)
+    .bind::<BigInt, _>(min_swaps)
+    .load::<PoolQuoteVolume>(&mut conn)
+    .await?;
+
+    Ok(QuoteVolumeResponse {
+        total_volume_tokens: totals
+            .decimals
+            .zip(totals.total_volume.to_f64())
+            .map(|(decimals, total_volume)| total_volume / 10f64.powi(decimals as i32)),
+        quote,
+        decimals: totals.decimals,
+        total_volume: totals.total_volume,
+        pool_stats,
+        total_pools: totals.total_pools,
+        page,
+        per_page,
+    })
+}
+
+async fn load_volume_stats(
+    pool: &PgConnectionPool,
+    config: &ApiConfig,
+    query: &TimeRangeParams,
+) -> Result<VolumeStatsResponse, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, config.volume_default_per_page, config.max_offset)?;
+
+    let approximate = query.approx.unwrap_or(false);
+    let min_swaps = parse_min_swaps(&query.min_swaps)?;
+
+    let mut conn = get_connection(pool).await?;
+
+    let (totals, pool_stats) = if approximate {
+        // Totals come from the pool_stats summary, which the indexer refreshes in the background
+        let totals = diesel::sql_query(
+            r#"
+            SELECT COALESCE(SUM(total_volume_in), 0) AS total_volume_in,
+                   COALESCE(SUM(total_volume_out), 0) AS total_volume_out,
+                   COUNT(*) FILTER (WHERE swap_count >= $1) AS total_pools,
+                   MAX(last_updated) AS last_updated
+            FROM pool_stats
+            "#,
+        )
+        .bind::<BigInt, _>(min_swaps)
+        .get_result::<PoolStatsTotals>(&mut conn)
+        .await?;
+
+        let pool_stats = diesel::sql_query(
+            r#"
+            SELECT pool_id, total_volume_in AS volume_in, total_volume_out AS volume_out, swap_count
+            FROM pool_stats
+            WHERE swap_count >= $3
+            ORDER BY total_volume_in DESC, pool_id
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind::<BigInt, _>(per_page)
+        .bind::<BigInt, _>(offset)
+        .bind::<BigInt, _>(min_swaps)
+        .load::<PoolVolumeStats>(&mut conn)
+        .await?;
+
+        (totals, pool_stats)
+    } else {
+        let totals = diesel::sql_query(
+            r#"
+            SELECT COALESCE(SUM(amount_in), 0) AS total_volume_in,
+                   COALESCE(SUM(amount_out), 0) AS total_volume_out,
+                   (SELECT COUNT(*) FROM (
+                        SELECT pool_id FROM cetus_swap_events GROUP BY pool_id HAVING COUNT(*) >= $1
+                    ) listed_pools) AS total_pools,
+                   NULL::TIMESTAMPTZ AS last_updated
+            FROM cetus_swap_events
+            "#,
+        )
+        .bind::<BigInt, _>(min_swaps)
+        .get_result::<PoolStatsTotals>(&mut conn)
+        .await?;
+
+        let pool_stats = diesel::sql_query(
+            r#"
+            SELECT pool_id,
+                   SUM(amount_in) AS volume_in,
+                   SUM(amount_out) AS volume_out,
+                   COUNT(*) AS swap_count
+            FROM cetus_swap_events
+            GROUP BY pool_id
+            HAVING COUNT(*) >= $3
+            ORDER BY SUM(amount_in) DESC, pool_id
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind::<BigInt, _>(per_page)
+        .bind::<BigInt, _>(offset)
+        .bind::<BigInt, _>(min_swaps)
+        .load::<PoolVolumeStats>(&mut conn)
+        .await?;
+
+        (totals, pool_stats)
+    };
+
+    Ok(VolumeStatsResponse {
+        total_volume_in: totals.total_volume_in,
+        total_volume_out: totals.total_volume_out,
+        pool_stats,
+        total_pools: totals.total_pools,
+        approximate,
+        last_updated: totals.last_updated,
+        page,
+        per_page,
+    })
+}
+
+// GET /api/compare_pools?ids={pool_id},{pool_id} - Get summary stats for several pools side by side
+pub async fn get_compare_pools(
+    pool: web::Data<PgConnectionPool>,
+    query: web::Query<ComparePoolsParams>,
+) -> Result<impl Responder, ApiError> {
+    let mut pool_ids = Vec::new();
+    for id in query.ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let pool_id = parse_object_id_param("pool id", id)?;
+        if !pool_ids.contains(&pool_id) {
+            pool_ids.push(pool_id);
+        }
+    }
+    if pool_ids.is_empty() {
+        return Err(ApiError::BadRequest("ids must list at least one pool".to_string()));
+    }
+    if pool_ids.len() > MAX_COMPARE_POOLS {
+        return Err(ApiError::BadRequest(format!(
+            "At most {} pools can be compared at once",
+            MAX_COMPARE_POOLS
+        )));
+    }
+
+    let mut conn = get_connection(&pool).await?;
+
+    // Return pools in the order they were requested
+    let mut pools = load_pool_summaries(&mut conn, &pool_ids).await?;
+    pools.sort_by_key(|summary| pool_ids.iter().position(|id| *id == summary.totals.pool_id));
+
+    Ok(HttpResponse::Ok().json(ComparePoolsResponse { pools }))
+}
+
+// GET /api/partners - Get swap count, volume and fees per referral partner
+pub async fn get_partners(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    query: web::Query<PaginationParams>,
+) -> Result<impl Responder, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, DEFAULT_PER_PAGE, config.max_offset)?;
+
+    let mut conn = get_connection(&pool).await?;
+
+    // Get total count
+    let total = cetus_swap_events::table
+        .select(diesel::dsl::count_distinct(cetus_swap_events::partner))
+        .get_result::<i64>(&mut conn)
+        .await?;
+
+    // Highest volume first
+    let partners = diesel::sql_query(
+        r#"
+        SELECT partner,
+               COUNT(*) AS swap_count,
+               SUM(amount_in) AS volume_in,
+               SUM(amount_out) AS volume_out,
+               SUM(fee_amount) AS fee_amount
+        FROM cetus_swap_events
+        GROUP BY partner
+        ORDER BY SUM(amount_in) DESC, partner
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind::<BigInt, _>(per_page)
+    .bind::<BigInt, _>(offset)
+    .load::<PartnerStats>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(PartnersResponse {
+        partners,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+// GET /api/pools/{pool}/volume_ma - Get trailing moving average of a pool's swap volume
+pub async fn get_pool_volume_ma(
+    pool: web::Data<PgConnectionPool>,
+    path: web::Path<String>,
+    query: web::Query<VolumeMaParams>,
+) -> Result<impl Responder, ApiError> {
+    let pool_id = parse_object_id_param("pool", &path.into_inner())?;
+    let window = query.window.clone().unwrap_or_else(|| "7d".to_string());
+    let interval = query.interval.clone().unwrap_or_else(|| "1d".to_string());
+
+    let window_ms = parse_duration_param("window", &window, VOLUME_MA_WINDOWS)?;
+    let interval_ms = parse_duration_param("interval", &interval, VOLUME_MA_INTERVALS)?;
+    if window_ms < interval_ms || window_ms % interval_ms != 0 {
+        return Err(ApiError::BadRequest(format!(
+            "window '{}' must be a whole multiple of interval '{}'",
+            window, interval
+        )));
+    }
+
+    // Number of buckets before the current one that fall inside the window.
+    // Both values come from the allowlists, so it is safe to inline.
+    let preceding_buckets = window_ms / interval_ms - 1;
+
+    let end_ms = query.end_ms.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    let start_ms = match query.start_ms {
+        Some(start_ms) => start_ms,
+        None => end_ms.saturating_sub(VOLUME_MA_DEFAULT_BUCKETS * interval_ms),
+    };
+    if start_ms < 0 || start_ms >= end_ms {
+        return Err(ApiError::BadRequest("start_ms must be non-negative and before end_ms".to_string()));
+    }
+    // Buckets are aligned to the interval; the first one holds start_ms, the last one end_ms - 1
+    let first_bucket_ms = start_ms - start_ms % interval_ms;
+    let last_bucket_ms = (end_ms - 1) - (end_ms - 1) % interval_ms;
+    if (last_bucket_ms - first_bucket_ms) / interval_ms + 1 > VOLUME_MA_MAX_BUCKETS {
+        return Err(ApiError::BadRequest(format!(
+            "start_ms to end_ms spans more than {} {} buckets; narrow the range",
+            VOLUME_MA_MAX_BUCKETS, interval
+        )));
+    }
+    // The average of the first buckets also covers the window before start_ms
+    let lookback_ms = first_bucket_ms - preceding_buckets * interval_ms;
+
+    let mut conn = get_connection(&pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
+
+    // Bucket the pool's volume in the range, fill empty buckets with zero, then average over the trailing window
+    let points = diesel::sql_query(format!(
+        r#"
+        WITH buckets AS (
+            SELECT (timestamp_ms / $2) * $2 AS bucket_start_ms, SUM(amount_in) AS volume
+            FROM cetus_swap_events
+            WHERE pool_id = $1 AND timestamp_ms > 0 AND timestamp_ms >= $3 AND timestamp_ms < $4
+            GROUP BY 1
+        ),
+        series AS (
+            SELECT generate_series($3, $5, $2) AS bucket_start_ms
+        ),
+        averaged AS (
+            SELECT series.bucket_start_ms,
+                   COALESCE(buckets.volume, 0) AS volume,
+                   AVG(COALESCE(buckets.volume, 0)) OVER (
+                       ORDER BY series.bucket_start_ms
+                       ROWS BETWEEN {} PRECEDING AND CURRENT ROW
+                   ) AS moving_average
+            FROM series
+            LEFT JOIN buckets ON buckets.bucket_start_ms = series.bucket_start_ms
+        )
+        SELECT bucket_start_ms, volume, moving_average::DOUBLE PRECISION AS moving_average
+        FROM averaged
+        WHERE bucket_start_ms >= $6
+        ORDER BY bucket_start_ms
+        "#,
+        preceding_buckets
+    ))
+    .bind::<Text, _>(&pool_id)
+    .bind::<BigInt, _>(interval_ms)
+    .bind::<BigInt, _>(lookback_ms)
+    .bind::<BigInt, _>(end_ms)
+    .bind::<BigInt, _>(last_bucket_ms)
+    .bind::<BigInt, _>(first_bucket_ms)
+    .load::<VolumeMaPoint>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(VolumeMaResponse {
+        pool_id,
+        window,
+        interval,
+        start_ms,
+        end_ms,
+        points,
+    }))
+}
+
+// GET /api/pools/{pool}/twap - Get the time-weighted average price of a pool over a trailing window
+pub async fn get_pool_twap(
+    pool: web::Data<PgConnectionPool>,
+    path: web::Path<String>,
+    query: web::Query<TwapParams>,
+) -> Result<impl Responder, ApiError> {
+    let pool_id = parse_object_id_param("pool", &path.into_inner())?;
+    let window = query.window.clone().unwrap_or_else(|| "1h".to_string());
+    let window_ms = parse_duration_param("window", &window, TWAP_WINDOWS)?;
+
+    let end_ms = chrono::Utc::now().timestamp_millis();
+    let start_ms = end_ms - window_ms;
+
+    let mut conn = get_connection(&pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
+
+    // The last swap before the window sets the price in effect when the window opens
+    let points = diesel::sql_query(
+        r#"
+        (SELECT timestamp_ms, after_sqrt_price
+         FROM cetus_swap_events
+         WHERE pool_id = $1 AND timestamp_ms > 0 AND timestamp_ms < $2
+         ORDER BY timestamp_ms DESC, event_seq DESC
+         LIMIT 1)
+        UNION ALL
+        (SELECT timestamp_ms, after_sqrt_price
+         FROM cetus_swap_events
+         WHERE pool_id = $1 AND timestamp_ms >= $2
+         ORDER BY timestamp_ms, event_seq)
+        ORDER BY timestamp_ms
+        "#,
+    )
+    .bind::<Text, _>(&pool_id)
+    .bind::<BigInt, _>(start_ms)
+    .load::<SwapPricePoint>(&mut conn)
+    .await?;
+
+    let points = points
+        .iter()
+        .filter_map(|point| {
+            let sqrt_price = point.after_sqrt_price.parse::<u128>().ok()?;
+            Some((point.timestamp_ms, sqrt_price_to_price(sqrt_price)))
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(TwapResponse {
+        pool_id,
+        window,
+        start_ms,
+        end_ms,
+        twap: time_weighted_average(&points, start_ms, end_ms),
+        samples: points.len(),
+    }))
+}
+
+// GET /api/pools/{pool}/activity_heatmap - Get a pool's swap counts by day of week and hour of day
+pub async fn get_pool_activity_heatmap(
+    pool: web::Data<PgConnectionPool>,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let pool_id = parse_object_id_param("pool", &path.into_inner())?;
+
+    let mut conn = get_connection(&pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
+
+    // Swaps indexed before timestamps were stored have timestamp_ms 0 and are left out
+    let cells = diesel::sql_query(
+        r#"
+        SELECT
+            EXTRACT(DOW FROM to_timestamp(timestamp_ms / 1000.0) AT TIME ZONE 'UTC')::INT4 AS day_of_week,
+            EXTRACT(HOUR FROM to_timestamp(timestamp_ms / 1000.0) AT TIME ZONE 'UTC')::INT4 AS hour,
+            COUNT(*) AS swaps
+        FROM cetus_swap_events
+        WHERE pool_id = $1 AND timestamp_ms > 0
+        GROUP BY day_of_week, hour
+        "#,
+    )
+    .bind::<Text, _>(&pool_id)
+    .load::<HeatmapCell>(&mut conn)
+    .await?;
+
+    let mut swaps = vec![vec![0i64; 24]; 7];
+    for cell in &cells {
+        swaps[cell.day_of_week as usize][cell.hour as usize] = cell.swaps;
+    }
+    let total = cells.iter().map(|cell| cell.swaps).sum();
+
+    Ok(HttpResponse::Ok().json(ActivityHeatmapResponse {
+        pool_id,
+        swaps,
+        total,
+    }))
+}
+
+// GET /api/pools/{pool}/recent_candles - Get one-minute OHLC candles of a pool for the trailing hour
+pub async fn get_pool_recent_candles(
+    pool: web::Data<PgConnectionPool>,
+    cache: web::Data<ResponseCache>,
+    path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+    let pool_id = parse_object_id_param("pool", &path.into_inner())?;
+    let key = format!("recent_candles?pool={}", pool_id);
+    cached_json_for(&cache, cache.recent_candles_ttl, key, load_recent_candles(&pool, pool_id)).await
+}
+
+// Buckets are aligned to whole minutes, so every poll within a minute reads the same window
+async fn load_recent_candles(pool: &PgConnectionPool, pool_id: String) -> Result<RecentCandlesResponse, ApiError> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let end_ms = now_ms - now_ms.rem_euclid(RECENT_CANDLE_MS) + RECENT_CANDLE_MS;
+    let start_ms = end_ms - RECENT_CANDLES * RECENT_CANDLE_MS;
+
+    let mut conn = get_connection(pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
+
+    // Both parts only read the pool's newest rows through the (pool_id, timestamp_ms) index.
+    // The last swap before the window gives the price of the minutes before the first swap in it.
+    let swaps = diesel::sql_query(
+        r#"
+        (SELECT timestamp_ms, atob, amount_in, amount_out, before_sqrt_price, after_sqrt_price
+         FROM cetus_swap_events
+         WHERE pool_id = $1 AND timestamp_ms > 0 AND timestamp_ms < $2
+         ORDER BY timestamp_ms DESC, event_seq DESC
+         LIMIT 1)
+        UNION ALL
+        (SELECT timestamp_ms, atob, amount_in, amount_out, before_sqrt_price, after_sqrt_price
+         FROM cetus_swap_events
+         WHERE pool_id = $1 AND timestamp_ms >= $2 AND timestamp_ms < $3
+         ORDER BY timestamp_ms, event_seq)
+        "#,
+    )
+    .bind::<Text, _>(&pool_id)
+    .bind::<BigInt, _>(start_ms)
+    .bind::<BigInt, _>(end_ms)
+    .load::<CandleSwap>(&mut conn)
+    .await?;
+
+    let price = |sqrt_price: &str| sqrt_price.parse::<u128>().ok().map(sqrt_price_to_price);
+    let mut candles = (0..RECENT_CANDLES)
+        .map(|i| Candle {
+            start_ms: start_ms + i * RECENT_CANDLE_MS,
+            open: None,
+            high: None,
+            low: None,
+            close: None,
+            swaps: 0,
+            volume_a: BigDecimal::zero(),
+            volume_b: BigDecimal::zero(),
+        })
+        .collect::<Vec<_>>();
+    let mut last_close = None;
+    let mut swaps = swaps.iter().peekable();
+    if let Some(swap) = swaps.next_if(|swap| swap.timestamp_ms < start_ms) {
+        last_close = price(&swap.after_sqrt_price);
+    }
+    for candle in &mut candles {
+        while let Some(swap) = swaps.next_if(|swap| swap.timestamp_ms < candle.start_ms + RECENT_CANDLE_MS) {
+            if candle.swaps == 0 {
+                candle.open = price(&swap.before_sqrt_price).or(last_close);
+                candle.high = candle.open;
+                candle.low = candle.open;
+            }
+            if let Some(after) = price(&swap.after_sqrt_price) {
+                candle.high = Some(candle.high.map_or(after, |high| high.max(after)));
+                candle.low = Some(candle.low.map_or(after, |low| low.min(after)));
+                last_close = Some(after);
+            }
+            let (volume_a, volume_b) = if swap.atob {
+                (swap.amount_in, swap.amount_out)
+            } else {
+                (swap.amount_out, swap.amount_in)
+            };
+            candle.volume_a += BigDecimal::from(volume_a);
+            candle.volume_b += BigDecimal::from(volume_b);
+            candle.swaps += 1;
+        }
+        if candle.swaps == 0 {
+            candle.open = last_close;
+            candle.high = last_close;
+            candle.low = last_close;
+        }
+        candle.close = last_close;
+    }
+
+    Ok(RecentCandlesResponse {
+        pool_id,
+        start_ms,
+        end_ms,
+        candles,
+    })
+}
+
+// Add and remove liquidity activity of a pool, shared by the positions queries
+const POOL_LIQUIDITY_ACTIVITY: &str = r#"
+    SELECT position, after_liquidity, timestamp_ms, event_index
+    FROM cetus_add_liquidity_events
+    WHERE pool = $1
+    UNION ALL
+    SELECT position, after_liquidity, timestamp_ms, event_index
+    FROM cetus_remove_liquidity_events
+    WHERE pool = $1
+"#;
+
+// GET /api/positions?pool={pool_id} - Get distinct positions with liquidity activity in a pool
+pub async fn get_positions(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    query: web::Query<PositionsParams>,
+) -> Result<impl Responder, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, DEFAULT_PER_PAGE, config.max_offset)?;
+    let pool_id = parse_object_id_param("pool", &query.pool)?;
+
+    let mut conn = get_connection(&pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
+
+    // Get total count
+    let total = diesel::sql_query(format!(
+        "SELECT COUNT(DISTINCT position) AS count FROM ({}) activity",
+        POOL_LIQUIDITY_ACTIVITY
+    ))
+    .bind::<Text, _>(&pool_id)
+    .get_result::<CountRow>(&mut conn)
+    .await?
+    .count;
+
+    // Keep each position's latest event, then order positions by their latest activity
+    let positions = diesel::sql_query(format!(
+        r#"
+        SELECT position, after_liquidity, last_activity_ms
+        FROM (
+            SELECT DISTINCT ON (position)
+                   position, after_liquidity, timestamp_ms AS last_activity_ms
+            FROM ({}) activity
+            ORDER BY position, timestamp_ms DESC, event_index DESC
+        ) latest
+        ORDER BY last_activity_ms DESC, position
+        LIMIT $2 OFFSET $3
+        "#,
+        POOL_LIQUIDITY_ACTIVITY
+    ))
+    .bind::<Text, _>(&pool_id)
+    .bind::<BigInt, _>(per_page)
+    .bind::<BigInt, _>(offset)
+    .load::<PositionSummary>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(PositionsResponse {
+        pool: pool_id,
+        positions,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+// Add and remove liquidity events of a pool with a kind discriminator, for the merged feed
+const POOL_LIQUIDITY_FEED: &str = r#"
+    SELECT 'add' AS kind, id, position, liquidity, after_liquidity, timestamp_ms,
+           tx_digest, event_index, checkpoint_seq, event_seq
+    FROM cetus_add_liquidity_events
+    WHERE pool = $1
+    UNION ALL
+    SELECT 'remove' AS kind, id, position, liquidity, after_liquidity, timestamp_ms,
+           tx_digest, event_index, checkpoint_seq, event_seq
+    FROM cetus_remove_liquidity_events
+    WHERE pool = $1
+"#;
+
+// GET /api/pools/{pool}/liquidity_events - Get add and remove liquidity events of a pool in one feed, newest first
+pub async fn get_pool_liquidity_events(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    path: web::Path<String>,
+    query: web::Query<PaginationParams>,
+) -> Result<impl Responder, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, DEFAULT_PER_PAGE, config.max_offset)?;
+    let pool_id = parse_object_id_param("pool", &path.into_inner())?;
+
+    let mut conn = get_connection(&pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
+
+    // Get total count
+    let total = diesel::sql_query(format!("SELECT COUNT(*) AS count FROM ({}) feed", POOL_LIQUIDITY_FEED))
+        .bind::<Text, _>(&pool_id)
+        .get_result::<CountRow>(&mut conn)
+        .await?
+        .count;
+
+    let events = diesel::sql_query(format!(
+        r#"
+        SELECT * FROM ({}) feed
+        ORDER BY timestamp_ms DESC, event_seq DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        POOL_LIQUIDITY_FEED
+    ))
+    .bind::<Text, _>(&pool_id)
+    .bind::<BigInt, _>(per_page)
+    .bind::<BigInt, _>(offset)
+    .load::<LiquidityFeedEvent>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(LiquidityFeedResponse {
+        pool: pool_id,
+        events,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+// Event count bounds for GET /api/pools/{pool}/recent
+const DEFAULT_RECENT_LIMIT: i64 = 20;
+const MAX_RECENT_LIMIT: i64 = 100;
+
+// GET /api/pools/{pool}/recent - Get the latest events of any kind for a pool, newest first
+pub async fn get_pool_recent_activity(
+    pool: web::Data<PgConnectionPool>,
+    path: web::Path<String>,
+    query: web::Query<RecentActivityParams>,
+) -> Result<impl Responder, ApiError> {
+    let limit = parse_positive_param("limit", &query.limit)?.unwrap_or(DEFAULT_RECENT_LIMIT);
+    if limit > MAX_RECENT_LIMIT {
+        return Err(ApiError::BadRequest(format!("limit must be at most {}", MAX_RECENT_LIMIT)));
+    }
+    let pool_id = parse_object_id_param("pool", &path.into_inner())?;
+
+    let mut conn = get_connection(&pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
+
+    // Each branch only reads its own newest rows, so the merge stays cheap on busy pools
+    let events = diesel::sql_query(
+        r#"
+        (SELECT 'swap' AS kind, id, tx_digest, timestamp_ms, event_seq,
+                amount_in::TEXT AS amount_in, amount_out::TEXT AS amount_out, atob,
+                NULL::TEXT AS position, NULL::TEXT AS liquidity
+         FROM cetus_swap_events
+         WHERE pool_id = $1
+         ORDER BY event_seq DESC
+         LIMIT $2)
+        UNION ALL
+        (SELECT 'add' AS kind, id, tx_digest, timestamp_ms, event_seq,
+                NULL, NULL, NULL, position, liquidity
+         FROM cetus_add_liquidity_events
+         WHERE pool = $1
+         ORDER BY event_seq DESC
+         LIMIT $2)
+        UNION ALL
+        (SELECT 'remove' AS kind, id, tx_digest, timestamp_ms, event_seq,
+                NULL, NULL, NULL, position, liquidity
+         FROM cetus_remove_liquidity_events
+         WHERE pool = $1
+         ORDER BY event_seq DESC
+         LIMIT $2)
+        ORDER BY event_seq DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind::<Text, _>(&pool_id)
+    .bind::<BigInt, _>(limit)
+    .load::<RecentPoolEvent>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(RecentActivityResponse { pool: pool_id, events }))
+}
+
+// Event count bounds on each side of GET /api/pools/{pool}/context
+const DEFAULT_CONTEXT_WINDOW: i64 = 10;
+const MAX_CONTEXT_WINDOW: i64 = 100;
+
+// The swaps, adds and removes of pool $1 matching `condition`, each table read in `order` and cut
+// at $3 rows before merging, in the column layout of RecentPoolEvent
+fn pool_events_query(condition: &str, order: &str) -> String {
+    format!(
+        r#"
+        (SELECT 'swap' AS kind, id, tx_digest, timestamp_ms, event_seq,
+                amount_in::TEXT AS amount_in, amount_out::TEXT AS amount_out, atob,
+                NULL::TEXT AS position, NULL::TEXT AS liquidity
+         FROM cetus_swap_events
+         WHERE pool_id = $1 AND {condition}
+         ORDER BY event_seq {order}
+         LIMIT $3)
+        UNION ALL
+        (SELECT 'add' AS kind, id, tx_digest, timestamp_ms, event_seq,
+                NULL, NULL, NULL, position, liquidity
+         FROM cetus_add_liquidity_events
+         WHERE pool = $1 AND {condition}
+         ORDER BY event_seq {order}
+         LIMIT $3)
+        UNION ALL
+        (SELECT 'remove' AS kind, id, tx_digest, timestamp_ms, event_seq,
+                NULL, NULL, NULL, position, liquidity
+         FROM cetus_remove_liquidity_events
+         WHERE pool = $1 AND {condition}
+         ORDER BY event_seq {order}
+         LIMIT $3)
+        "#,
+        condition = condition,
+        order = order,
+    )
 }
 
-// GET /api/add_liquidity - Get all add liquidity events with pagination
-pub async fn get_add_liquidity(
+// GET /api/pools/{pool}/context - Get the events of a pool just before and after a given event
+pub async fn get_pool_event_context(
     pool: web::Data<PgConnectionPool>,
-    query: web::Query<PaginationParams>,
-) -> Result<impl Responder, Error> {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    let offset = (page - 1) * per_page;
+    path: web::Path<String>,
+    query: web::Query<EventContextParams>,
+) -> Result<impl Responder, ApiError> {
+    let around = match query.around.as_deref() {
+        Some(raw) => raw
+            .parse::<i64>()
+            .map_err(|_| ApiError::BadRequest(format!("Invalid around '{}', expected an event_seq", raw)))?,
+        None => return Err(ApiError::BadRequest("around is required".to_string())),
+    };
+    let window = parse_positive_param("window", &query.window)?.unwrap_or(DEFAULT_CONTEXT_WINDOW);
+    if window > MAX_CONTEXT_WINDOW {
+        return Err(ApiError::BadRequest(format!("window must be at most {}", MAX_CONTEXT_WINDOW)));
+    }
+    let pool_id = parse_object_id_param("pool", &path.into_inner())?;
 
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
+    let mut conn = get_connection(&pool).await?;
+    require_known_pool(&mut conn, &pool_id).await?;
 
-    // Get total count
-    let total = cetus_add_liquidity_events::table
-        .count()
-        .get_result::<i64>(&mut conn)
-        .await
-        .expect("Error counting add liquidity events");
+    // Each side takes the nearest `window` events of every table, then the nearest of those
+    let events = diesel::sql_query(format!(
+        r#"
+        WITH before AS (
+            {before}
+            ORDER BY event_seq DESC, id DESC
+            LIMIT $3
+        ),
+        target AS (
+            {target}
+        ),
+        after AS (
+            {after}
+            ORDER BY event_seq, id
+            LIMIT $3
+        )
+        SELECT * FROM before
+        UNION ALL
+        SELECT * FROM target
+        UNION ALL
+        SELECT * FROM after
+        ORDER BY event_seq, id
+        "#,
+        before = pool_events_query("event_seq < $2", "DESC"),
+        target = pool_events_query("event_seq = $2", "ASC"),
+        after = pool_events_query("event_seq > $2", "ASC"),
+    ))
+    .bind::<Text, _>(&pool_id)
+    .bind::<BigInt, _>(around)
+    .bind::<BigInt, _>(window)
+    .load::<RecentPoolEvent>(&mut conn)
+    .await?;
 
-    // Get paginated events
-    let events = cetus_add_liquidity_events::table
-        .order_by(cetus_add_liquidity_events::id.desc())
-        .limit(per_page)
-        .offset(offset)
-        .load::<CetusAddLiquidityEvent>(&mut conn)
-        .await
-        .expect("Error loading add liquidity events");
+    if !events.iter().any(|event| event.event_seq == around) {
+        return Err(ApiError::NotFound(format!("No event with event_seq {} in pool {}", around, pool_id)));
+    }
 
-    Ok(HttpResponse::Ok().json(AddLiquidityResponse {
+    Ok(HttpResponse::Ok().json(EventContextResponse {
+        pool: pool_id,
+        around,
+        window,
         events,
-        total,
-        page,
-        per_page,
     }))
 }
 
-// GET /api/remove_liquidity - Get all remove liquidity events with pagination
-pub async fn get_remove_liquidity(
+// GET /api/pairs/{coin_a}/{coin_b}/pools - Get the pools trading a token pair, in either order
+pub async fn get_pair_pools(
+    pool: web::Data<PgConnectionPool>,
+    path: web::Path<(String, String)>,
+    query: web::Query<PairPoolsParams>,
+) -> Result<impl Responder, ApiError> {
+    let (coin_a, coin_b) = path.into_inner();
+    let coin_a = parse_coin_type_param("coin_a", &coin_a)?;
+    let coin_b = parse_coin_type_param("coin_b", &coin_b)?;
+    let min_swaps = parse_min_swaps(&query.min_swaps)?;
+
+    let mut conn = get_connection(&pool).await?;
+
+    let pools = diesel::sql_query(
+        r#"
+        SELECT pool_id, coin_type_a, coin_type_b, COUNT(*) AS swap_count
+        FROM cetus_swap_events
+        WHERE (coin_type_a = $1 AND coin_type_b = $2) OR (coin_type_a = $2 AND coin_type_b = $1)
+        GROUP BY pool_id, coin_type_a, coin_type_b
+        HAVING COUNT(*) >= $3
+        ORDER BY swap_count DESC, pool_id
+        "#,
+    )
+    .bind::<Text, _>(&coin_a)
+    .bind::<Text, _>(&coin_b)
+    .bind::<BigInt, _>(min_swaps)
+    .load::<PairPool>(&mut conn)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(PairPoolsResponse { coin_a, coin_b, pools }))
+}
+
+// GET /api/debug/event_types - Get event types observed by the indexer in debug mode
+pub async fn get_observed_event_types(
     pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
     query: web::Query<PaginationParams>,
-) -> Result<impl Responder, Error> {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    let offset = (page - 1) * per_page;
+) -> Result<impl Responder, ApiError> {
+    let (page, per_page, offset) = parse_pagination(&query.page, &query.per_page, DEFAULT_PER_PAGE, config.max_offset)?;
 
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
+    let mut conn = get_connection(&pool).await?;
 
     // Get total count
-    let total = cetus_remove_liquidity_events::table
+    let total = observed_event_types::table
         .count()
         .get_result::<i64>(&mut conn)
-        .await
-        .expect("Error counting remove liquidity events");
+        .await?;
 
-    // Get paginated events
-    let events = cetus_remove_liquidity_events::table
-        .order_by(cetus_remove_liquidity_events::id.desc())
+    // Most frequent types first
+    let event_types = observed_event_types::table
+        .order_by((observed_event_types::count.desc(), observed_event_types::event_type))
         .limit(per_page)
         .offset(offset)
-        .load::<CetusRemoveLiquidityEvent>(&mut conn)
-        .await
-        .expect("Error loading remove liquidity events");
+        .load::<ObservedEventType>(&mut conn)
+        .await?;
 
-    Ok(HttpResponse::Ok().json(RemoveLiquidityResponse {
-        events,
+    Ok(HttpResponse::Ok().json(EventTypesResponse {
+        event_types,
         total,
         page,
         per_page,
     }))
 }
 
-// GET /api/swaps/by_pool - Get swap events for a specific pool
-pub async fn get_swaps_by_pool(
-    pool: web::Data<PgConnectionPool>,
-    query: web::Query<PoolFilterParams>,
-) -> Result<impl Responder, Error> {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    let offset = (page - 1) * per_page;
-    let id_filter = &query.id_contains;
+// Rows deleted per statement when pruning, to keep locks short
+const PRUNE_BATCH_SIZE: i64 = 10_000;
 
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
+// Check the request's bearer token against ADMIN_API_TOKEN, in constant time so the
+// response time doesn't reveal how much of the token matched
+fn require_admin(req: &HttpRequest, config: &ApiConfig) -> Result<(), ApiError> {
+    let Some(admin_token) = &config.admin_token else {
+        return Err(ApiError::Forbidden("Admin endpoints are disabled, set ADMIN_API_TOKEN to enable them".to_string()));
+    };
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if bool::from(token.as_bytes().ct_eq(admin_token.as_bytes())) => Ok(()),
+        _ => Err(ApiError::Unauthorized("Missing or invalid admin token".to_string())),
+    }
+}
 
-    // Get total count for this filter
-    let total = diesel::QueryDsl::filter(
-        cetus_swap_events::table,
-        cetus_swap_events::id.like(format!("%{}%", id_filter))
-    )
-    .count()
-    .get_result::<i64>(&mut conn)
-    .await
-    .expect("Error counting swap events for filter");
+// Delete rows of a table below a checkpoint, one batch at a time, returning the total deleted
+async fn prune_table(
+    pool: &PgConnectionPool,
+    table: EventTable,
+    req: &HttpRequest,
+    config: &ApiConfig,
+    params: &PruneParams,
+) -> Result<HttpResponse, ApiError> {
+    require_admin(req, config)?;
+    if params.confirm != Some(true) {
+        return Err(ApiError::BadRequest("Pass confirm=true to delete events".to_string()));
+    }
+    if params.before_checkpoint < 0 {
+        return Err(ApiError::BadRequest("before_checkpoint must be a non-negative integer".to_string()));
+    }
 
-    // Get paginated swaps for this filter
-    let swaps = diesel::QueryDsl::filter(
-        cetus_swap_events::table,
-        cetus_swap_events::id.like(format!("%{}%", id_filter))
-    )
-    .order_by(cetus_swap_events::id.desc())
-    .limit(per_page)
-    .offset(offset)
-    .load::<CetusSwapEvent>(&mut conn)
-    .await
-    .expect("Error loading swap events for filter");
+    let mut conn = get_connection(&pool).await?;
 
-    Ok(HttpResponse::Ok().json(SwapsResponse {
-        swaps,
-        total,
-        page,
-        per_page,
+    // Each batch is its own statement, so no lock is held across the whole prune
+    let mut deleted = 0;
+    loop {
+        let batch = diesel::sql_query(format!(
+            "DELETE FROM {table} WHERE id IN (SELECT id FROM {table} WHERE checkpoint_seq < $1 LIMIT $2)",
+            table = table.table_name()
+        ))
+        .bind::<BigInt, _>(params.before_checkpoint)
+        .bind::<BigInt, _>(PRUNE_BATCH_SIZE)
+        .execute(&mut conn)
+        .await?;
+        deleted += batch as i64;
+        if (batch as i64) < PRUNE_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(PruneResponse {
+        table: table.table_name().to_string(),
+        before_checkpoint: params.before_checkpoint,
+        deleted,
     }))
 }
 
-// GET /api/add_liquidity/by_pool - Get add liquidity events for a specific pool
-pub async fn get_add_liquidity_by_pool(
+// DELETE /api/admin/swaps - Delete swap events older than a checkpoint
+pub async fn prune_swaps(
     pool: web::Data<PgConnectionPool>,
-    query: web::Query<PoolFilterParams>,
-) -> Result<impl Responder, Error> {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    let offset = (page - 1) * per_page;
-    let id_filter = &query.id_contains;
-
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
+    config: web::Data<ApiConfig>,
+    req: HttpRequest,
+    query: web::Query<PruneParams>,
+) -> Result<impl Responder, ApiError> {
+    prune_table(&pool, EventTable::Swap, &req, &config, &query).await
+}
 
-    // Get total count for this filter
-    let total = diesel::QueryDsl::filter(
-        cetus_add_liquidity_events::table,
-        cetus_add_liquidity_events::id.like(format!("%{}%", id_filter))
-    )
-    .count()
-    .get_result::<i64>(&mut conn)
-    .await
-    .expect("Error counting add liquidity events for filter");
+// DELETE /api/admin/add_liquidity - Delete add liquidity events older than a checkpoint
+pub async fn prune_add_liquidity(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    req: HttpRequest,
+    query: web::Query<PruneParams>,
+) -> Result<impl Responder, ApiError> {
+    prune_table(&pool, EventTable::AddLiquidity, &req, &config, &query).await
+}
 
-    // Get paginated events for this filter
-    let events = diesel::QueryDsl::filter(
-        cetus_add_liquidity_events::table,
-        cetus_add_liquidity_events::id.like(format!("%{}%", id_filter))
-    )
-    .order_by(cetus_add_liquidity_events::id.desc())
-    .limit(per_page)
-    .offset(offset)
-    .load::<CetusAddLiquidityEvent>(&mut conn)
-    .await
-    .expect("Error loading add liquidity events for filter");
+// DELETE /api/admin/remove_liquidity - Delete remove liquidity events older than a checkpoint
+pub async fn prune_remove_liquidity(
+    pool: web::Data<PgConnectionPool>,
+    config: web::Data<ApiConfig>,
+    req: HttpRequest,
+    query: web::Query<PruneParams>,
+) -> Result<impl Responder, ApiError> {
+    prune_table(&pool, EventTable::RemoveLiquidity, &req, &config, &query).await
+}
 
-    Ok(HttpResponse::Ok().json(AddLiquidityResponse {
-        events,
-        total,
-        page,
-        per_page,
-    }))
+// Response format for GET /api/admin/pool_stats
+#[derive(Serialize)]
+pub struct ConnectionPoolStats {
+    /// Open connections, in use or idle
+    connections: u32,
+    in_use: u32,
+    idle: u32,
+    /// Requests of this API server waiting for a connection
+    waiters: usize,
 }
 
-// GET /api/remove_liquidity/by_pool - Get remove liquidity events for a specific pool
-pub async fn get_remove_liquidity_by_pool(
+// GET /api/admin/pool_stats - Current usage of the API server's database connection pool
+pub async fn get_connection_pool_stats(
     pool: web::Data<PgConnectionPool>,
-    query: web::Query<PoolFilterParams>,
-) -> Result<impl Responder, Error> {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    let offset = (page - 1) * per_page;
-    let id_filter = &query.id_contains;
-
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
+    config: web::Data<ApiConfig>,
+    req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&req, &config)?;
+    let state = pool.state();
+    Ok(HttpResponse::Ok().json(ConnectionPoolStats {
+        connections: state.connections,
+        in_use: state.connections.saturating_sub(state.idle_connections),
+        idle: state.idle_connections,
+        waiters: CONNECTION_WAITERS.load(Ordering::Relaxed),
+    }))
+}
 
-    // Get total count for this filter
-    let total = diesel::QueryDsl::filter(
-        cetus_remove_liquidity_events::table,
-        cetus_remove_liquidity_events::id.like(format!("%{}%", id_filter))
-    )
-    .count()
-    .get_result::<i64>(&mut conn)
-    .await
-    .expect("Error counting remove liquidity events for filter");
+// Query parameters for GET /api/admin/gaps
+#[derive(Deserialize)]
+pub struct GapsParams {
+    from: i64,
+    to: i64,
+}
 
-    // Get paginated events for this filter
-    let events = diesel::QueryDsl::filter(
-        cetus_remove_liquidity_events::table,
-        cetus_remove_liquidity_events::id.like(format!("%{}%", id_filter))
-    )
-    .order_by(cetus_remove_liquidity_events::id.desc())
-    .limit(per_page)
-    .offset(offset)
-    .load::<CetusRemoveLiquidityEvent>(&mut conn)
-    .await
-    .expect("Error loading remove liquidity events for filter");
+// An inclusive range of checkpoints missing from processed_checkpoints
+#[derive(QueryableByName, Serialize)]
+pub struct CheckpointGap {
+    #[diesel(sql_type = BigInt)]
+    start: i64,
+    #[diesel(sql_type = BigInt)]
+    end: i64,
+}
 
-    Ok(HttpResponse::Ok().json(RemoveLiquidityResponse {
-        events,
-        total,
-        page,
-        per_page,
-    }))
+// Response format for GET /api/admin/gaps
+#[derive(Serialize)]
+pub struct GapsResponse {
+    /// Start of the scanned range, raised to SKIP_CHECKPOINTS_BELOW
+    from: i64,
+    to: i64,
+    gaps: Vec<CheckpointGap>,
+    /// Total checkpoints in the gaps
+    missing: i64,
 }
 
-// GET /api/stats - Get overall statistics
-pub async fn get_stats(
+// GET /api/admin/gaps - Ranges of checkpoints between from and to (inclusive) that the indexer hasn't processed
+pub async fn get_checkpoint_gaps(
     pool: web::Data<PgConnectionPool>,
-) -> Result<impl Responder, Error> {
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
-
-    // Get counts for each event type
-    let total_swaps = cetus_swap_events::table
-        .count()
-        .get_result::<i64>(&mut conn)
-        .await
-        .expect("Error counting swap events");
+    config: web::Data<ApiConfig>,
+    req: HttpRequest,
+    query: web::Query<GapsParams>,
+) -> Result<impl Responder, ApiError> {
+    require_admin(&req, &config)?;
+    let from = gap_scan_start(query.from, query.to, config.skip_checkpoints_below)?;
+    if from > query.to {
+        // The whole range was skipped on purpose
+        return Ok(HttpResponse::Ok().json(GapsResponse {
+            from,
+            to: query.to,
+            gaps: Vec::new(),
+            missing: 0,
+        }));
+    }
 
-    let total_add_liquidity = cetus_add_liquidity_events::table
-        .count()
-        .get_result::<i64>(&mut conn)
-        .await
-        .expect("Error counting add liquidity events");
+    let mut conn = get_connection(&pool).await?;
 
-    let total_remove_liquidity = cetus_remove_liquidity_events::table
-        .count()
-        .get_result::<i64>(&mut conn)
-        .await
-        .expect("Error counting remove liquidity events");
+    // Sentinels just outside the range turn missing checkpoints at either end into gaps
+    // between neighbours like any other
+    let gaps = diesel::sql_query(
+        r#"
+        SELECT checkpoint_seq + 1 AS start, next_seq - 1 AS "end"
+        FROM (
+            SELECT checkpoint_seq, LEAD(checkpoint_seq) OVER (ORDER BY checkpoint_seq) AS next_seq
+            FROM (
+                SELECT $1 - 1 AS checkpoint_seq
+                UNION ALL
+                SELECT checkpoint_seq FROM processed_checkpoints WHERE checkpoint_seq BETWEEN $1 AND $2
+                UNION ALL
+                SELECT $2 + 1
+            ) AS bounded
+        ) AS neighbours
+        WHERE next_seq > checkpoint_seq + 1
+        ORDER BY start
+        "#,
+    )
+    .bind::<BigInt, _>(from)
+    .bind::<BigInt, _>(query.to)
+    .load::<CheckpointGap>(&mut conn)
+    .await?;
 
-    Ok(HttpResponse::Ok().json(StatsResponse {
-        total_swaps,
-        total_add_liquidity,
-        total_remove_liquidity,
+    let missing = gaps.iter().map(|gap| gap.end - gap.start + 1).sum();
+    Ok(HttpResponse::Ok().json(GapsResponse {
+        from,
+        to: query.to,
+        gaps,
+        missing,
     }))
 }
 
-// GET /api/volume - Get volume statistics
-pub async fn get_volume_stats(
-    pool: web::Data<PgConnectionPool>,
-    _query: web::Query<TimeRangeParams>,
-) -> Result<impl Responder, Error> {
-    let mut conn = pool.get().await.expect("Failed to get DB connection");
+// Validate a gap scan from `from` to `to` and return where it starts: checkpoints below
+// SKIP_CHECKPOINTS_BELOW are skipped by the indexer without being recorded, so they aren't gaps.
+// `to` stays below i64::MAX, since the scan's upper sentinel is `to + 1`.
+fn gap_scan_start(from: i64, to: i64, skip_checkpoints_below: i64) -> Result<i64, ApiError> {
+    if from < 0 || to < from {
+        return Err(ApiError::BadRequest("from must be a non-negative integer and to must not be below from".to_string()));
+    }
+    if to == i64::MAX {
+        return Err(ApiError::BadRequest(format!("to must be below {}", i64::MAX)));
+    }
+    Ok(from.max(skip_checkpoints_below))
+}
 
-    // Calculate total volume (simplified, in a production app you might want to use SQL aggregations)
-    let swaps = cetus_swap_events::table
-        .load::<CetusSwapEvent>(&mut conn)
-        .await
-        .expect("Error loading swap events");
+// An event type matched by the indexer and the table it is written to
+#[derive(Serialize)]
+pub struct ConfiguredEventType {
+    event_type: String,
+    table: String,
+}
 
-    let mut total_volume_in = 0;
-    let mut total_volume_out = 0;
-    let mut pool_volumes: std::collections::HashMap<String, (i64, i64, i64)> = std::collections::HashMap::new();
+// Response format for GET /api/config/event_types
+#[derive(Serialize)]
+pub struct ConfiguredEventTypesResponse {
+    event_types: Vec<ConfiguredEventType>,
+}
 
-    for swap in &swaps {
-        total_volume_in += swap.amount_in;
-        total_volume_out += swap.amount_out;
-        
-        // Try to extract pool ID from the event ID
-        // This assumes the pool ID is in the event ID
-        if let Some(pool_part) = swap.id.split('-').nth(1) {
-            let pool_id = pool_part.to_string();
-            let entry = pool_volumes.entry(pool_id).or_insert((0, 0, 0));
-            entry.0 += swap.amount_in;    // volume in
-            entry.1 += swap.amount_out;   // volume out
-            entry.2 += 1;                // swap count
+// GET /api/config/event_types - Get the event types matched with the current *_EVENT_TYPE and PROTOCOLS_CONFIG settings
+pub async fn get_configured_event_types() -> Result<impl Responder, ApiError> {
+    let mut indexer = CetusIndexer::from_env().map_err(|e| ApiError::Internal(format!("{:#}", e)))?;
+    if let Ok(path) = env::var("PROTOCOLS_CONFIG") {
+        let routes = ProtocolsConfig::load(path)
+            .and_then(|config| config.routes())
+            .map_err(|e| ApiError::Internal(format!("{:#}", e)))?;
+        for route in routes {
+            indexer.register(route);
         }
     }
 
-    // Convert pool volumes hashmap to a vector of PoolVolumeStats
-    let mut pool_stats = pool_volumes
-        .into_iter()
-        .map(|(pool_id, (vol_in, vol_out, count))| {
-            PoolVolumeStats {
-                pool_id,
-                volume_in: vol_in,
-                volume_out: vol_out,
-                swap_count: count,
-            }
+    let mut event_types = indexer
+        .registry()
+        .routes()
+        .map(|route| ConfiguredEventType {
+            event_type: route.struct_tag.to_canonical_string(true),
+            table: route.table.table_name().to_string(),
         })
         .collect::<Vec<_>>();
-    
-    // Sort by volume_in descending
-    pool_stats.sort_by(|a, b| b.volume_in.cmp(&a.volume_in));
+    event_types.sort_by(|a, b| (&a.table, &a.event_type).cmp(&(&b.table, &b.event_type)));
 
-    Ok(HttpResponse::Ok().json(VolumeStatsResponse {
-        total_volume_in,
-        total_volume_out,
-        pool_stats,
-    }))
+    Ok(HttpResponse::Ok().json(ConfiguredEventTypesResponse { event_types }))
+}
+
+// Build information captured by build.rs
+#[derive(Serialize)]
+pub struct VersionResponse {
+    version: String,
+    git_sha: String,
+    build_time: String,
+}
+
+impl VersionResponse {
+    fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("GIT_SHA").to_string(),
+            build_time: env!("BUILD_TIME").to_string(),
+        }
+    }
 }
 
 // GET /api/health - Simple health check endpoint
@@ -407,16 +3660,22 @@ pub async fn health_check() -> impl Responder {
     struct HealthResponse {
         status: String,
         message: String,
-        version: String,
+        #[serde(flatten)]
+        build: VersionResponse,
     }
     
     HttpResponse::Ok().json(HealthResponse {
         status: "ok".to_string(),
         message: "API server is running".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
+        build: VersionResponse::current(),
     })
 }
 
+// GET /api/version - Package version, git commit and build time of the running server
+pub async fn get_version() -> impl Responder {
+    HttpResponse::Ok().json(VersionResponse::current())
+}
+
 // GET / - Root route that shows available API endpoints
 pub async fn index() -> impl Responder {
     HttpResponse::Ok().content_type("text/html").body(r#"
@@ -440,27 +3699,55 @@ pub async fn index() -> impl Responder {
 
                 <h2>Basic Endpoints</h2>
                 <ul>
-                    <li><a href="/api/swaps" class="endpoint">GET /api/swaps</a> - Get all swap events with pagination</li>
-                    <li><a href="/api/add_liquidity" class="endpoint">GET /api/add_liquidity</a> - Get all add liquidity events with pagination</li>
-                    <li><a href="/api/remove_liquidity" class="endpoint">GET /api/remove_liquidity</a> - Get all remove liquidity events with pagination</li>
+                    <li><a href="/api/swaps" class="endpoint">GET /api/swaps</a> - Get all swap events with pagination (optional <code>pool</code>, <code>start_ms</code>, <code>end_ms</code>, <code>min_amount_in</code>, <code>max_amount_in</code>, <code>atob</code>, <code>min_usd</code>, <code>min_price_impact_bps</code> and <code>sender</code> filters)</li>
+                    <li><span class="endpoint">GET /api/swaps?pagination=keyset&amp;cursor={next_cursor}</span> - Page through swaps or liquidity events by cursor instead of page number</li>
+                    <li><a href="/api/swaps/distribution" class="endpoint">GET /api/swaps/distribution</a> - Get a log-scale histogram of swap sizes (optional <code>pool</code> and <code>buckets</code>)</li>
+                    <li><a href="/api/swaps/export.parquet" class="endpoint">GET /api/swaps/export.parquet</a> - Download swaps as a Parquet file (accepts the <code>/api/swaps</code> filters)</li>
+                    <li><a href="/api/add_liquidity" class="endpoint">GET /api/add_liquidity</a> - Get all add liquidity events with pagination (optional <code>min_liquidity</code> filter)</li>
+                    <li><a href="/api/remove_liquidity" class="endpoint">GET /api/remove_liquidity</a> - Get all remove liquidity events with pagination (optional <code>min_liquidity</code> filter)</li>
                 </ul>
 
                 <h2>Pool-Specific Endpoints</h2>
                 <ul>
-                    <li><span class="endpoint">GET /api/swaps/by_pool?id_contains={pool_id}</span> - Get swap events for a specific pool</li>
                     <li><span class="endpoint">GET /api/add_liquidity/by_pool?id_contains={pool_id}</span> - Get add liquidity events for a specific pool</li>
                     <li><span class="endpoint">GET /api/remove_liquidity/by_pool?id_contains={pool_id}</span> - Get remove liquidity events for a specific pool</li>
+                    <li><span class="endpoint">GET /api/positions?pool={pool_id}</span> - Get distinct positions in a pool with their latest liquidity</li>
+                    <li><span class="endpoint">GET /api/traders/{sender}/swaps</span> - Get the swaps sent by an address</li>
+                    <li><span class="endpoint">GET /api/pools/{pool_id}/liquidity_events</span> - Get add and remove liquidity events of a pool in one feed</li>
+                    <li><span class="endpoint">GET /api/pools/{pool_id}/recent?limit=20</span> - Get the latest events of any kind for a pool</li>
+                    <li><span class="endpoint">GET /api/pools/{pool_id}/context?around=&lt;event_seq&gt;&amp;window=10</span> - Get the events of a pool around a given event</li>
+                    <li><span class="endpoint">GET /api/pools/{pool_id}/twap?window=1h</span> - Get the time-weighted average price of a pool</li>
+                    <li><span class="endpoint">GET /api/pools/{pool_id}/activity_heatmap</span> - Get a pool's swap counts by day of week and hour of day</li>
+                    <li><span class="endpoint">GET /api/pools/{pool_id}/recent_candles</span> - Get one-minute OHLC candles of a pool for the last hour</li>
+                    <li><span class="endpoint">GET /api/pairs/{coin_a}/{coin_b}/pools</span> - Get the pools trading a token pair, in either order</li>
                 </ul>
 
                 <h2>Analytics Endpoints</h2>
                 <ul>
+                    <li><a href="/api/overview" class="endpoint">GET /api/overview</a> - Get counts, 24h volume, top pools and latest swaps in one call</li>
                     <li><a href="/api/stats" class="endpoint">GET /api/stats</a> - Get overall statistics</li>
-                    <li><a href="/api/volume" class="endpoint">GET /api/volume</a> - Get volume statistics</li>
+                    <li><a href="/api/stats/swaps_per_tx" class="endpoint">GET /api/stats/swaps_per_tx</a> - Get a histogram of swaps per transaction</li>
+                    <li><a href="/api/volume" class="endpoint">GET /api/volume</a> - Get volume statistics (pool stats are paginated)</li>
+                    <li><span class="endpoint">GET /api/compare_pools?ids={pool_id},{pool_id}</span> - Compare volume, swaps and liquidity of up to 10 pools</li>
+                    <li><a href="/api/partners" class="endpoint">GET /api/partners</a> - Get swap count, volume and fees per referral partner</li>
+                    <li><span class="endpoint">GET /api/pools/{pool_id}/volume_ma?window=7d&amp;interval=1d</span> - Get a pool's moving-average volume</li>
                 </ul>
 
                 <h2>Utility Endpoints</h2>
                 <ul>
                     <li><a href="/api/health" class="endpoint">GET /api/health</a> - API health check</li>
+                    <li><a href="/api/version" class="endpoint">GET /api/version</a> - Version, git commit and build time of the running server</li>
+                    <li><a href="/api/config/event_types" class="endpoint">GET /api/config/event_types</a> - Event types matched by the indexer configuration</li>
+                    <li><a href="/api/debug/event_types" class="endpoint">GET /api/debug/event_types</a> - Event types observed by the indexer (requires <code>RECORD_EVENT_TYPES=true</code>)</li>
+                </ul>
+
+                <h2>Admin Endpoints</h2>
+                <ul>
+                    <li><span class="endpoint">DELETE /api/admin/swaps?before_checkpoint={seq}&amp;confirm=true</span> - Delete swap events below a checkpoint (requires <code>ADMIN_API_TOKEN</code>)</li>
+                    <li><span class="endpoint">DELETE /api/admin/add_liquidity?before_checkpoint={seq}&amp;confirm=true</span> - Delete add liquidity events below a checkpoint</li>
+                    <li><span class="endpoint">DELETE /api/admin/remove_liquidity?before_checkpoint={seq}&amp;confirm=true</span> - Delete remove liquidity events below a checkpoint</li>
+                    <li><span class="endpoint">GET /api/admin/pool_stats</span> - Database connection pool usage</li>
+                    <li><span class="endpoint">GET /api/admin/gaps?from={seq}&amp;to={seq}</span> - Checkpoint ranges the indexer hasn't processed</li>
                 </ul>
 
                 <p>For all list endpoints, you can use <code>page</code> and <code>per_page</code> query parameters for pagination.</p>
@@ -472,25 +3759,460 @@ pub async fn index() -> impl Responder {
 // Configure API routes
 pub fn configure_api(cfg: &mut web::ServiceConfig) {
     cfg
+        // Report malformed query strings as JSON like every other client error
+        .app_data(web::QueryConfig::default().error_handler(|err, _req| {
+            ApiError::BadRequest(err.to_string()).into()
+        }))
         // Root route
         .route("/", web::get().to(index))
         .service(
             web::scope("/api")
                 // Basic event endpoints
                 .route("/swaps", web::get().to(get_swaps))
+                .route("/swaps/distribution", web::get().to(get_swap_distribution))
+                .route("/swaps/export.parquet", web::get().to(export_swaps_parquet))
                 .route("/add_liquidity", web::get().to(get_add_liquidity))
                 .route("/remove_liquidity", web::get().to(get_remove_liquidity))
                 
                 // Pool-specific endpoints
-                .route("/swaps/by_pool", web::get().to(get_swaps_by_pool))
                 .route("/add_liquidity/by_pool", web::get().to(get_add_liquidity_by_pool))
                 .route("/remove_liquidity/by_pool", web::get().to(get_remove_liquidity_by_pool))
+                .route("/positions", web::get().to(get_positions))
+                .route("/traders/{sender}/swaps", web::get().to(get_trader_swaps))
                 
                 // Statistics and volume endpoints
+                .route("/overview", web::get().to(get_overview))
                 .route("/stats", web::get().to(get_stats))
+                .route("/stats/swaps_per_tx", web::get().to(get_swaps_per_tx))
                 .route("/volume", web::get().to(get_volume_stats))
+                .route("/compare_pools", web::get().to(get_compare_pools))
+                .route("/partners", web::get().to(get_partners))
+                .route("/pools/{pool}/volume_ma", web::get().to(get_pool_volume_ma))
+                .route("/pools/{pool}/liquidity_events", web::get().to(get_pool_liquidity_events))
+                .route("/pools/{pool}/recent", web::get().to(get_pool_recent_activity))
+                .route("/pools/{pool}/context", web::get().to(get_pool_event_context))
+                .route("/pools/{pool}/twap", web::get().to(get_pool_twap))
+                .route("/pools/{pool}/activity_heatmap", web::get().to(get_pool_activity_heatmap))
+                .route("/pools/{pool}/recent_candles", web::get().to(get_pool_recent_candles))
+                .route("/pairs/{coin_a}/{coin_b}/pools", web::get().to(get_pair_pools))
                 
                 // Health check
                 .route("/health", web::get().to(health_check))
+                .route("/version", web::get().to(get_version))
+                .route("/config/event_types", web::get().to(get_configured_event_types))
+
+                // Debug endpoints
+                .route("/debug/event_types", web::get().to(get_observed_event_types))
+
+                // Admin endpoints, require ADMIN_API_TOKEN
+                .route("/admin/swaps", web::delete().to(prune_swaps))
+                .route("/admin/add_liquidity", web::delete().to(prune_add_liquidity))
+                .route("/admin/remove_liquidity", web::delete().to(prune_remove_liquidity))
+                .route("/admin/pool_stats", web::get().to(get_connection_pool_stats))
+                .route("/admin/gaps", web::get().to(get_checkpoint_gaps))
+        );
+} 
+#[cfg(test)]
+mod tests {
+    use diesel::{ConnectionError, ConnectionResult};
+    use diesel_async::pooled_connection::{bb8::Pool, AsyncDieselConnectionManager, ManagerConfig};
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+    use futures::future::{BoxFuture, FutureExt};
+
+    use super::*;
+
+    fn some(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn parse_positive_param_accepts_positive_integers() {
+        assert_eq!(parse_positive_param("page", &None).unwrap(), None);
+        assert_eq!(parse_positive_param("page", &some("1")).unwrap(), Some(1));
+        assert_eq!(parse_positive_param("page", &some("9223372036854775807")).unwrap(), Some(i64::MAX));
+    }
+
+    #[test]
+    fn parse_positive_param_rejects_other_input() {
+        for raw in ["0", "-1", "abc", "1.5", "", " 1", "9223372036854775808"] {
+            assert!(
+                matches!(parse_positive_param("page", &some(raw)), Err(ApiError::BadRequest(_))),
+                "accepted '{}'",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn parse_pagination_defaults_to_the_first_page() {
+        assert_eq!(parse_pagination(&None, &None, 50, Some(DEFAULT_MAX_OFFSET)).unwrap(), (1, 50, 0));
+        assert_eq!(parse_pagination(&some("3"), &some("20"), 50, None).unwrap(), (3, 20, 40));
+    }
+
+    #[test]
+    fn parse_pagination_rejects_invalid_pages() {
+        for (page, per_page) in [("0", "10"), ("-1", "10"), ("x", "10"), ("1", "0"), ("1", "-5"), ("1", "ten")] {
+            assert!(
+                matches!(
+                    parse_pagination(&some(page), &some(per_page), 50, None),
+                    Err(ApiError::BadRequest(_))
+                ),
+                "accepted page={} per_page={}",
+                page,
+                per_page
+            );
+        }
+    }
+
+    #[test]
+    fn gap_scan_starts_at_skip_checkpoints_below() {
+        assert_eq!(gap_scan_start(0, 100, 0).unwrap(), 0);
+        assert_eq!(gap_scan_start(10, 100, 50).unwrap(), 50);
+        assert_eq!(gap_scan_start(60, 100, 50).unwrap(), 60);
+        // Entirely below the threshold: the scan starts past `to` and finds nothing
+        assert_eq!(gap_scan_start(10, 20, 50).unwrap(), 50);
+    }
+
+    #[test]
+    fn gap_scan_rejects_invalid_ranges() {
+        for (from, to) in [(-1, 10), (10, 9), (0, i64::MAX), (i64::MAX, i64::MAX)] {
+            assert!(
+                matches!(gap_scan_start(from, to, 0), Err(ApiError::BadRequest(_))),
+                "accepted {}..={}",
+                from,
+                to
+            );
+        }
+        assert_eq!(gap_scan_start(0, i64::MAX - 1, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn response_cache_evicts_the_oldest_entry_when_full() {
+        let ttl = Duration::from_secs(60);
+        let cache = ResponseCache::new(ttl, ttl, 2);
+        cache.insert("a".to_string(), ttl, serde_json::json!(1));
+        cache.insert("b".to_string(), ttl, serde_json::json!(2));
+        // Replacing an entry doesn't evict another
+        cache.insert("b".to_string(), ttl, serde_json::json!(3));
+        assert_eq!(cache.get("a"), Some(serde_json::json!(1)));
+        cache.insert("c".to_string(), ttl, serde_json::json!(4));
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b"), Some(serde_json::json!(3)));
+        assert_eq!(cache.get("c"), Some(serde_json::json!(4)));
+    }
+
+    #[test]
+    fn response_cache_honors_the_entry_ttl() {
+        let cache = ResponseCache::new(Duration::from_secs(60), Duration::ZERO, 10);
+        cache.insert("fresh".to_string(), Duration::from_secs(60), serde_json::json!(1));
+        cache.insert("expired".to_string(), Duration::ZERO, serde_json::json!(2));
+        assert_eq!(cache.get("fresh"), Some(serde_json::json!(1)));
+        assert_eq!(cache.get("expired"), None);
+    }
+
+    #[test]
+    fn volume_cache_key_uses_the_parsed_parameters() {
+        let config = test_config();
+        let defaults = TimeRangeParams {
+            start_date: None,
+            end_date: None,
+            page: None,
+            per_page: None,
+            quote: None,
+            approx: None,
+            min_swaps: None,
+        };
+        // Omitted parameters and their defaults share an entry
+        assert_eq!(
+            volume_cache_key(&config, &defaults, None).unwrap(),
+            volume_cache_key(&config, &volume_query(1, DEFAULT_PER_PAGE, false, 0), None).unwrap()
+        );
+        assert_ne!(
+            volume_cache_key(&config, &volume_query(2, DEFAULT_PER_PAGE, false, 0), None).unwrap(),
+            volume_cache_key(&config, &volume_query(1, DEFAULT_PER_PAGE, false, 0), None).unwrap()
+        );
+        assert_ne!(
+            volume_cache_key(&config, &defaults, Some("0x2::sui::SUI")).unwrap(),
+            volume_cache_key(&config, &defaults, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_pagination_rejects_overflowing_offsets() {
+        let result = parse_pagination(&some("9223372036854775807"), &some("2"), 50, None);
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn parse_pagination_caps_the_offset_at_max_offset() {
+        // Page 101 of 100 rows starts exactly at the maximum offset
+        assert_eq!(
+            parse_pagination(&some("101"), &some("100"), 50, Some(10_000)).unwrap(),
+            (101, 100, 10_000)
+        );
+        assert!(matches!(
+            parse_pagination(&some("102"), &some("100"), 50, Some(10_000)),
+            Err(ApiError::BadRequest(_))
+        ));
+        // Without a cap any offset that fits in an i64 is served
+        assert_eq!(
+            parse_pagination(&some("102"), &some("100"), 50, None).unwrap(),
+            (102, 100, 10_100)
+        );
+    }
+
+    #[test]
+    fn pagination_strategy_points_deep_offset_pages_to_keyset() {
+        let strategy = |pagination: Option<&str>, page: Option<&str>| {
+            parse_pagination_strategy(
+                &pagination.map(str::to_string),
+                &None,
+                &page.map(str::to_string),
+                &some("100"),
+                50,
+                Some(10_000),
+                SortOrder::EventSeq,
+            )
+        };
+        match strategy(None, Some("102")) {
+            Err(ApiError::BadRequest(message)) => assert!(message.contains("pagination=keyset"), "{}", message),
+            _ => panic!("page 102 of 100 rows was accepted"),
+        }
+        assert!(strategy(Some("offset"), Some("101")).is_ok_and(|strategy| strategy.counts_total()));
+        // Keyset pages have no offset to cap, and skip counting the total
+        assert!(strategy(Some("keyset"), None).is_ok_and(|strategy| !strategy.counts_total()));
+    }
+
+    /// Open a connection inside a transaction that is never committed
+    fn establish_test_connection(database_url: &str) -> BoxFuture<ConnectionResult<AsyncPgConnection>> {
+        async move {
+            let mut conn = crate::establish_connection(database_url).await?;
+            conn.begin_test_transaction()
+                .await
+                .map_err(|e| ConnectionError::BadConnection(e.to_string()))?;
+            Ok(conn)
+        }
+        .boxed()
+    }
+
+    /// Pool over a single connection to TEST_DATABASE_URL, a migrated database, whose writes are
+    /// rolled back when the test ends. `None` skips the test when the variable is not set.
+    async fn test_pool() -> Option<PgConnectionPool> {
+        let Ok(database_url) = env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL is not set, skipping database test");
+            return None;
+        };
+        let mut config = ManagerConfig::default();
+        config.custom_setup = Box::new(establish_test_connection);
+        let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new_with_config(database_url, config);
+        Some(Pool::builder().max_size(1).build(manager).await.unwrap())
+    }
+
+    // Swaps per pool in the min_swaps dataset
+    const MIN_SWAPS_POOLS: &[(&str, i64)] = &[("min-swaps-a", 3), ("min-swaps-b", 2), ("min-swaps-c", 1)];
+
+    // `$2[i]` swaps in pool `$1[i]` at timestamp `$4`, each for `$3` in and out
+    const INSERT_POOL_SWAPS_SQL: &str = r#"
+        INSERT INTO cetus_swap_events (id, amount_in, amount_out, pool_id, timestamp_ms, atob, coin_type_a,
+            coin_type_b, package_id, tx_digest, event_index, before_sqrt_price, after_sqrt_price,
+            price_impact_bps, partner, fee_amount, checkpoint_seq, tx_index, event_seq, sender)
+        SELECT pool_id || '-' || $4 || '-' || n, $3, $3, pool_id, $4, true, '0x2::sui::SUI', '0x2::sui::SUI',
+               '0x1', 'digest', n, '0', '0', 0, '', 0, 0, 0, n, '0x1'
+        FROM UNNEST($1::TEXT[], $2::INT8[]) pools(pool_id, swaps), generate_series(1, swaps) n
+    "#;
+
+    // pool_stats rows for the inserted pools, as the background refresh would write them
+    const REFRESH_POOL_STATS_SQL: &str = r#"
+        INSERT INTO pool_stats (pool_id, total_volume_in, total_volume_out, swap_count, last_updated)
+        SELECT pool_id, SUM(amount_in), SUM(amount_out), COUNT(*), NOW()
+        FROM cetus_swap_events
+        WHERE pool_id = ANY($1)
+        GROUP BY pool_id
+    "#;
+
+    /// Insert `swaps` swaps of `amount` into each pool and summarize them in pool_stats
+    async fn insert_pool_swaps(pool: &PgConnectionPool, pools: &[(&str, i64)], amount: i64) {
+        insert_pool_swaps_at(pool, pools, amount, 0).await;
+    }
+
+    /// Like insert_pool_swaps, with the swaps at `timestamp_ms`
+    async fn insert_pool_swaps_at(pool: &PgConnectionPool, pools: &[(&str, i64)], amount: i64, timestamp_ms: i64) {
+        let pool_ids: Vec<String> = pools.iter().map(|(pool_id, _)| pool_id.to_string()).collect();
+        let swaps: Vec<i64> = pools.iter().map(|(_, swaps)| *swaps).collect();
+        let mut conn = pool.get().await.unwrap();
+        if timestamp_ms > 0 {
+            diesel::sql_query(
+                "SELECT create_cetus_swap_events_partition((to_timestamp($1 / 1000.0) AT TIME ZONE 'UTC')::DATE)",
+            )
+            .bind::<BigInt, _>(timestamp_ms)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+        diesel::sql_query(INSERT_POOL_SWAPS_SQL)
+            .bind::<Array<Text>, _>(&pool_ids)
+            .bind::<Array<BigInt>, _>(&swaps)
+            .bind::<BigInt, _>(amount)
+            .bind::<BigInt, _>(timestamp_ms)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        diesel::sql_query(REFRESH_POOL_STATS_SQL)
+            .bind::<Array<Text>, _>(&pool_ids)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+    }
+
+    fn test_config() -> ApiConfig {
+        ApiConfig {
+            swaps_default_per_page: DEFAULT_PER_PAGE,
+            add_liquidity_default_per_page: DEFAULT_PER_PAGE,
+            remove_liquidity_default_per_page: DEFAULT_PER_PAGE,
+            volume_default_per_page: DEFAULT_PER_PAGE,
+            admin_token: None,
+            max_offset: None,
+            skip_checkpoints_below: 0,
+        }
+    }
+
+    fn volume_query(page: i64, per_page: i64, approx: bool, min_swaps: i64) -> TimeRangeParams {
+        TimeRangeParams {
+            start_date: None,
+            end_date: None,
+            page: Some(page.to_string()),
+            per_page: Some(per_page.to_string()),
+            quote: None,
+            approx: Some(approx),
+            min_swaps: Some(min_swaps.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn min_swaps_total_pools_counts_the_listed_pools() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        insert_pool_swaps(&pool, MIN_SWAPS_POOLS, 100).await;
+        let config = test_config();
+
+        for approx in [false, true] {
+            for min_swaps in 0..=4 {
+                // Page through the listing two pools at a time, as a client would
+                let mut listed = Vec::new();
+                let mut total_pools = None;
+                for page in 1.. {
+                    let response = load_volume_stats(&pool, &config, &volume_query(page, 2, approx, min_swaps))
+                        .await
+                        .unwrap();
+                    assert_eq!(*total_pools.get_or_insert(response.total_pools), response.total_pools);
+                    if response.pool_stats.is_empty() {
+                        break;
+                    }
+                    for stats in response.pool_stats {
+                        assert!(stats.swap_count >= min_swaps, "{} listed with min_swaps {}", stats.pool_id, min_swaps);
+                        listed.push(stats.pool_id);
+                    }
+                }
+
+                assert_eq!(Some(listed.len() as i64), total_pools, "approx {} min_swaps {}", approx, min_swaps);
+                for (pool_id, swaps) in MIN_SWAPS_POOLS {
+                    assert_eq!(
+                        listed.iter().any(|listed_id| listed_id == pool_id),
+                        *swaps >= min_swaps,
+                        "{} with approx {} min_swaps {}",
+                        pool_id,
+                        approx,
+                        min_swaps
+                    );
+                }
+            }
+        }
+    }
+
+    // Two swaps of i64::MAX, a sum that only fits in NUMERIC
+    const TWICE_I64_MAX: &str = "18446744073709551614";
+
+    #[test]
+    fn volume_sums_above_i64_serialize_exactly() {
+        let volume = BigDecimal::from_str(TWICE_I64_MAX).unwrap();
+        assert_eq!(volume.to_i64(), None);
+        let stats = PoolVolumeStats {
+            pool_id: "0x1".to_string(),
+            volume_in: volume.clone(),
+            volume_out: volume,
+            swap_count: 2,
+        };
+        assert_eq!(
+            serde_json::to_value(&stats).unwrap(),
+            serde_json::json!({
+                "pool_id": "0x1",
+                "volume_in": TWICE_I64_MAX,
+                "volume_out": TWICE_I64_MAX,
+                "swap_count": 2,
+            })
         );
-} 
\ No newline at end of file
+    }
+
+    #[tokio::test]
+    async fn volume_sums_above_i64_are_exact() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        insert_pool_swaps(&pool, &[("overflow-a", 2)], i64::MAX).await;
+        let twice_i64_max = BigDecimal::from_str(TWICE_I64_MAX).unwrap();
+
+        for approx in [false, true] {
+            let response = load_volume_stats(&pool, &test_config(), &volume_query(1, 100, approx, 2))
+                .await
+                .unwrap();
+            // Other pools in the database only add to the totals
+            assert!(response.total_volume_in >= twice_i64_max, "approx {}", approx);
+            assert!(response.total_volume_out >= twice_i64_max, "approx {}", approx);
+            let stats = response
+                .pool_stats
+                .iter()
+                .find(|stats| stats.pool_id == "overflow-a")
+                .expect("the largest pool is on the first page");
+            assert_eq!(stats.volume_in, twice_i64_max);
+            assert_eq!(stats.volume_out, twice_i64_max);
+        }
+
+        let volume = load_volume_since(&pool, 0).await.unwrap();
+        assert!(volume.volume_in >= twice_i64_max);
+        assert!(volume.volume_out >= twice_i64_max);
+    }
+
+    #[test]
+    fn percent_change_is_exact_above_i64() {
+        let twice_i64_max = BigDecimal::from_str(TWICE_I64_MAX).unwrap();
+        let i64_max = BigDecimal::from(i64::MAX);
+        assert_eq!(percent_change(&i64_max, &twice_i64_max), Some(100.0));
+        assert_eq!(percent_change(&twice_i64_max, &i64_max), Some(-50.0));
+        assert_eq!(percent_change(&BigDecimal::zero(), &twice_i64_max), None);
+    }
+
+    #[tokio::test]
+    async fn window_volumes_above_i64_are_exact() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        // Two swaps of i64::MAX in the last 24h, one in the 24h before
+        insert_pool_swaps_at(&pool, &[("overflow-window", 2)], i64::MAX, now_ms - 3_600_000).await;
+        insert_pool_swaps_at(&pool, &[("overflow-window", 1)], i64::MAX, now_ms - 90_000_000).await;
+        let twice_i64_max = BigDecimal::from_str(TWICE_I64_MAX).unwrap();
+
+        let mut conn = pool.get().await.unwrap();
+        let summaries = load_pool_summaries(&mut conn, &["overflow-window".to_string()]).await.unwrap();
+        assert_eq!(summaries[0].volume_change_24h_pct, Some(100.0));
+        drop(conn);
+
+        insert_pool_swaps_at(&pool, &[("overflow-candle", 2)], i64::MAX, now_ms).await;
+        let response = load_recent_candles(&pool, "overflow-candle".to_string()).await.unwrap();
+        let candle = response.candles.last().unwrap();
+        assert_eq!(candle.swaps, 2);
+        assert_eq!(candle.volume_a, twice_i64_max);
+        assert_eq!(candle.volume_b, twice_i64_max);
+    }
+}
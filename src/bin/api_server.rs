@@ -1,12 +1,14 @@
 use actix_cors::Cors;
-use actix_web::{App, HttpServer, middleware, web, http::KeepAlive};
+use actix_web::{App, HttpResponse, HttpServer, middleware, web, http::KeepAlive};
 use dotenvy::dotenv;
-use std::{env, time::Duration};
+use std::{env, fs::File, io::BufReader, time::Duration};
 use rustls;
 use std::net::SocketAddr;
+use prometheus::{Encoder, Registry, TextEncoder};
 
-use suins_indexer::api::configure_api;
+use suins_indexer::api::{configure_api, ApiConfig, ResponseCache};
 use suins_indexer::get_connection_pool;
+use suins_indexer::metrics::{spawn_connection_pool_sampler, ConnectionPoolMetrics};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -21,6 +23,27 @@ async fn main() -> std::io::Result<()> {
     
     // Get the database connection pool
     let pool = get_connection_pool().await;
+
+    // Load per-endpoint API settings
+    let api_config = ApiConfig::from_env();
+
+    // Shared by all workers so every worker serves the same cached aggregates
+    let response_cache = web::Data::new(ResponseCache::from_env());
+
+    // Sample the connection pool for the gauges served on /metrics; 0 disables the sampler
+    let registry = Registry::new();
+    let db_pool_sample_secs = env::var("DB_POOL_SAMPLE_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>().expect("DB_POOL_SAMPLE_SECS must be a number of seconds"))
+        .unwrap_or(5);
+    if db_pool_sample_secs > 0 {
+        spawn_connection_pool_sampler(
+            pool.clone(),
+            "api",
+            ConnectionPoolMetrics::new(&registry),
+            Duration::from_secs(db_pool_sample_secs),
+        );
+    }
     
     // Get the host and port from environment variables or use default
     let host = env::var("API_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
@@ -31,10 +54,14 @@ async fn main() -> std::io::Result<()> {
     let socket_addr: SocketAddr = bind_address.parse()
         .expect("Invalid bind address format");
     
-    println!("Starting API server at http://{}", bind_address);
+    // Load the TLS configuration if a certificate and key were provided
+    let tls_config = load_tls_config()?;
+    let scheme = if tls_config.is_some() { "https" } else { "http" };
+
+    println!("Starting API server at {}://{}", scheme, bind_address);
     
-    // Create and start the HTTP server
-    HttpServer::new(move || {
+    // Create the HTTP server
+    let server = HttpServer::new(move || {
         // Configure CORS
         let cors = Cors::default()
             .allow_any_origin()
@@ -46,12 +73,68 @@ async fn main() -> std::io::Result<()> {
             .wrap(middleware::Logger::default())
             .wrap(cors)
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(api_config.clone()))
+            .app_data(response_cache.clone())
+            .app_data(web::Data::new(registry.clone()))
+            .route("/metrics", web::get().to(metrics))
             .configure(configure_api)
     })
     .workers(num_cpus::get()) // Set worker threads to number of available CPU cores
     .keep_alive(KeepAlive::Timeout(Duration::from_secs(75))) // Set keep-alive timeout to 75 seconds
-    .shutdown_timeout(30) // Allow 30 seconds for graceful shutdown
-    .bind(socket_addr)?
-    .run()
-    .await
-} 
\ No newline at end of file
+    .shutdown_timeout(30); // Allow 30 seconds for graceful shutdown
+
+    // Terminate TLS in-process when configured, otherwise serve plain HTTP
+    let server = match tls_config {
+        Some(tls_config) => server.bind_rustls_0_23(socket_addr, tls_config)?,
+        None => server.bind(socket_addr)?,
+    };
+
+    server.run().await
+}
+
+/// Prometheus text exposition of the API server's metrics
+async fn metrics(registry: web::Data<Registry>) -> HttpResponse {
+    let mut body = Vec::new();
+    match TextEncoder::new().encode(&registry.gather(), &mut body) {
+        Ok(()) => HttpResponse::Ok().content_type(TextEncoder::new().format_type()).body(body),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Build a rustls server config from `TLS_CERT_PATH` and `TLS_KEY_PATH`.
+/// Returns `None` when neither variable is set so the server falls back to plain HTTP, and an
+/// error when only one of them is.
+fn load_tls_config() -> std::io::Result<Option<rustls::ServerConfig>> {
+    let (cert_path, key_path) = match (env::var("TLS_CERT_PATH").ok(), env::var("TLS_KEY_PATH").ok()) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        (None, None) => return Ok(None),
+        // Serving plain HTTP when HTTPS was half configured would go unnoticed
+        (Some(_), None) => return Err(missing_tls_variable("TLS_KEY_PATH", "TLS_CERT_PATH")),
+        (None, Some(_)) => return Err(missing_tls_variable("TLS_CERT_PATH", "TLS_KEY_PATH")),
+    };
+
+    let mut cert_reader = BufReader::new(File::open(&cert_path)?);
+    let certs = rustls_pemfile::certs(&mut cert_reader).collect::<Result<Vec<_>, _>>()?;
+
+    let mut key_reader = BufReader::new(File::open(&key_path)?);
+    let key = rustls_pemfile::private_key(&mut key_reader)?.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("No private key found in {}", key_path),
+        )
+    })?;
+
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    Ok(Some(config))
+} 
+
+fn missing_tls_variable(missing: &str, set: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{} is set but {} is not; set both to serve HTTPS, or neither", set, missing),
+    )
+}
@@ -0,0 +1,89 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replays an event log written with EVENT_LOG_DIR into DATABASE_URL, e.g. to rebuild the event
+//! tables after losing the database. Events are upserted like the indexer does, so files can
+//! be replayed more than once and overlapping with data already in the database.
+
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use dotenvy::dotenv;
+use prometheus::Registry;
+
+use suins_indexer::event_log::{log_files, parse_log_line};
+use suins_indexer::get_connection_pool_for_url;
+use suins_indexer::metrics::IndexerMetrics;
+use suins_indexer::registry::IndexedEvents;
+use suins_indexer::sink::{EventSink, PgSink};
+
+// Events upserted per transaction
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+/// Import event log files into the database
+#[derive(Parser)]
+struct Args {
+    /// Event log files, or directories whose .ndjson files are imported in name order
+    paths: Vec<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .expect("Failed to install default crypto provider");
+    dotenv().ok();
+    let args = Args::parse();
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if path.is_dir() {
+            files.extend(log_files(path)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let database_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+    let sink = PgSink::new(
+        "import",
+        get_connection_pool_for_url(database_url).await,
+        IndexerMetrics::new(&Registry::new()),
+    );
+
+    let mut imported = 0;
+    let mut skipped = 0;
+    for file in &files {
+        let reader = BufReader::new(File::open(file).with_context(|| format!("Failed to open {}", file.display()))?);
+        let mut batch = IndexedEvents::default();
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line.with_context(|| format!("Failed to read {}", file.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A failed append can leave a partial last line behind
+            match parse_log_line(&line) {
+                Ok(event) => batch.push(event),
+                Err(e) => {
+                    eprintln!("Skipping line {} of {}: {:#}", line_number + 1, file.display(), e);
+                    skipped += 1;
+                    continue;
+                }
+            }
+            if batch.event_count() >= IMPORT_BATCH_SIZE {
+                imported += batch.event_count();
+                sink.write(&std::mem::take(&mut batch)).await?;
+            }
+        }
+        imported += batch.event_count();
+        sink.write(&batch).await?;
+        println!("Imported {}", file.display());
+    }
+
+    println!("Imported {} events from {} files, skipped {} unreadable lines", imported, files.len(), skipped);
+    Ok(())
+}
@@ -0,0 +1,64 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs the indexer's parse logic against one downloaded checkpoint file and prints the
+//! decoded events as JSON, without touching the database. Uses the same event type
+//! overrides, `ID_SCHEME`, `ID_PREFIX` and `PROTOCOLS_CONFIG` as the indexer.
+
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use dotenvy::dotenv;
+use serde_json::json;
+use sui_storage::blob::Blob;
+use sui_types::full_checkpoint_content::CheckpointData;
+
+use suins_indexer::dynamic::ProtocolsConfig;
+use suins_indexer::indexer::{CetusIndexer, IdScheme};
+
+/// Print the events the indexer would extract from a checkpoint file
+#[derive(Parser)]
+struct Args {
+    /// Path to a checkpoint file, e.g. ./checkpoints/12345.chk
+    path: PathBuf,
+}
+
+fn main() -> Result<()> {
+    dotenv().ok();
+    let args = Args::parse();
+
+    let id_scheme = env::var("ID_SCHEME")
+        .ok()
+        .map(|value| value.parse::<IdScheme>())
+        .transpose()?
+        .unwrap_or_default();
+    let mut indexer = CetusIndexer::from_env()?
+        .with_id_scheme(id_scheme)
+        .with_id_prefix(env::var("ID_PREFIX").unwrap_or_default());
+    if let Ok(path) = env::var("PROTOCOLS_CONFIG") {
+        for route in ProtocolsConfig::load(path)?.routes()? {
+            indexer.register(route);
+        }
+    }
+
+    let bytes = std::fs::read(&args.path)
+        .with_context(|| format!("Failed to read checkpoint file {}", args.path.display()))?;
+    let checkpoint = Blob::from_bytes::<CheckpointData>(&bytes)
+        .with_context(|| format!("Failed to decode checkpoint file {}", args.path.display()))?;
+
+    let events = indexer.process_checkpoint(&checkpoint);
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json!({
+            "checkpoint": checkpoint.checkpoint_summary.sequence_number,
+            "swap_events": events.swap_events,
+            "add_liquidity_events": events.add_liquidity_events,
+            "remove_liquidity_events": events.remove_liquidity_events,
+            "dynamic_events": events.dynamic_events,
+        }))?
+    );
+    Ok(())
+}
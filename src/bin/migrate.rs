@@ -0,0 +1,94 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Applies the pending migrations embedded from `migrations/` to DATABASE_URL and prints the
+//! status of every migration, so deploy pipelines can migrate as a separate step from starting
+//! the indexer. Exits non-zero when a migration fails.
+
+use std::collections::HashSet;
+use std::env;
+
+use anyhow::{anyhow, Context, Result};
+use diesel::pg::Pg;
+use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
+use diesel_async::AsyncPgConnection;
+use diesel_migrations::{MigrationHarness, MigrationSource};
+use dotenvy::dotenv;
+
+use suins_indexer::{establish_connection, MIGRATIONS};
+
+/// Outcome of running the pending migrations
+struct MigrationReport {
+    /// Versions applied before this run
+    applied_before: HashSet<String>,
+    /// Versions applied by this run
+    applied_now: HashSet<String>,
+    /// Error of the migration that failed, which stops the run
+    error: Option<anyhow::Error>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .expect("Failed to install default crypto provider");
+    dotenv().ok();
+
+    let database_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+    let conn = establish_connection(&database_url)
+        .await
+        .context("Could not connect to DATABASE_URL")?;
+    let mut conn: AsyncConnectionWrapper<AsyncPgConnection> = AsyncConnectionWrapper::from(conn);
+
+    // The migration harness is synchronous and blocks on the async connection internally
+    let report = tokio::task::spawn_blocking(move || -> Result<MigrationReport> {
+        let applied_versions = |conn: &mut AsyncConnectionWrapper<AsyncPgConnection>| {
+            conn.applied_migrations()
+                .map(|versions| versions.iter().map(|version| version.to_string()).collect::<HashSet<_>>())
+                .map_err(|e| anyhow!("Could not read applied migrations: {}", e))
+        };
+        let applied_before = applied_versions(&mut conn)?;
+        let error = conn.run_pending_migrations(MIGRATIONS).err().map(|e| anyhow!(e));
+        let applied_now = applied_versions(&mut conn)?
+            .difference(&applied_before)
+            .cloned()
+            .collect();
+        Ok(MigrationReport {
+            applied_before,
+            applied_now,
+            error,
+        })
+    })
+    .await??;
+
+    let embedded = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+        .map_err(|e| anyhow!("Could not load embedded migrations: {}", e))?;
+    // Migrations run in order, so the first one left unapplied after an error is the one that failed
+    let mut failure_reported = false;
+    println!("{:<8} MIGRATION", "STATUS");
+    for migration in &embedded {
+        let version = migration.name().version().to_string();
+        let status = if report.applied_before.contains(&version) {
+            "applied"
+        } else if report.applied_now.contains(&version) {
+            "ran"
+        } else if report.error.is_some() && !failure_reported {
+            failure_reported = true;
+            "FAILED"
+        } else {
+            "pending"
+        };
+        println!("{:<8} {}", status, migration.name());
+    }
+
+    if let Some(error) = report.error {
+        eprintln!("Migration failed: {:#}", error);
+        std::process::exit(1);
+    }
+    println!(
+        "{} migrations applied, {} by this run",
+        report.applied_before.len() + report.applied_now.len(),
+        report.applied_now.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,297 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Copy of the event tables in ClickHouse, for analytics (volume over months, large group-bys)
+//! that are slow on Postgres. Postgres stays the source of truth for the API and progress;
+//! with `SINK=clickhouse` the indexer writes every batch to both. The ClickHouse write is
+//! wrapped in a `BestEffortSink` unless `CLICKHOUSE_REQUIRED=true`, so an outage leaves gaps
+//! in ClickHouse instead of stalling Postgres.
+//!
+//! The tables mirror the Postgres columns. ClickHouse has no upserts, so they use
+//! `ReplacingMergeTree` ordered by id: a reprocessed checkpoint inserts its rows again and the
+//! duplicates are merged away in the background (query with `FINAL` for exact counts).
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use clickhouse::{Client, Row};
+use serde::Serialize;
+use tracing::info;
+
+use crate::registry::IndexedEvents;
+use crate::sink::EventSink;
+
+// Tables created on startup when missing. created_at is filled in by ClickHouse, like the
+// Postgres default.
+const CREATE_TABLES_SQL: &[&str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS cetus_swap_events (
+        id String,
+        amount_in Int64,
+        amount_out Int64,
+        created_at DateTime64(6, 'UTC') DEFAULT now64(6),
+        pool_id String,
+        timestamp_ms Int64,
+        atob Bool,
+        coin_type_a String,
+        coin_type_b String,
+        package_id String,
+        tx_digest String,
+        event_index Int64,
+        before_sqrt_price String,
+        after_sqrt_price String,
+        price_impact_bps Int64,
+        partner String,
+        fee_amount Int64,
+        checkpoint_seq Int64,
+        tx_index Int64,
+        event_seq Int64,
+        sender String
+    )
+    ENGINE = ReplacingMergeTree
+    PARTITION BY toYYYYMM(fromUnixTimestamp64Milli(timestamp_ms))
+    ORDER BY id
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS cetus_add_liquidity_events (
+        id String,
+        liquidity String,
+        after_liquidity String,
+        created_at DateTime64(6, 'UTC') DEFAULT now64(6),
+        package_id String,
+        tx_digest String,
+        event_index Int64,
+        pool String,
+        position String,
+        timestamp_ms Int64,
+        checkpoint_seq Int64,
+        tx_index Int64,
+        event_seq Int64
+    )
+    ENGINE = ReplacingMergeTree
+    ORDER BY id
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS cetus_remove_liquidity_events (
+        id String,
+        liquidity String,
+        after_liquidity String,
+        created_at DateTime64(6, 'UTC') DEFAULT now64(6),
+        package_id String,
+        tx_digest String,
+        event_index Int64,
+        pool String,
+        position String,
+        timestamp_ms Int64,
+        checkpoint_seq Int64,
+        tx_index Int64,
+        event_seq Int64
+    )
+    ENGINE = ReplacingMergeTree
+    ORDER BY id
+    "#,
+    r#"
+    CREATE TABLE IF NOT EXISTS dynamic_events (
+        id String,
+        event_name String,
+        event_type String,
+        package_id String,
+        tx_digest String,
+        event_index Int64,
+        timestamp_ms Int64,
+        checkpoint_seq Int64,
+        tx_index Int64,
+        event_seq Int64,
+        data String,
+        created_at DateTime64(6, 'UTC') DEFAULT now64(6)
+    )
+    ENGINE = ReplacingMergeTree
+    ORDER BY id
+    "#,
+];
+
+#[derive(Row, Serialize)]
+struct SwapRow<'a> {
+    id: &'a str,
+    amount_in: i64,
+    amount_out: i64,
+    pool_id: &'a str,
+    timestamp_ms: i64,
+    atob: bool,
+    coin_type_a: &'a str,
+    coin_type_b: &'a str,
+    package_id: &'a str,
+    tx_digest: &'a str,
+    event_index: i64,
+    before_sqrt_price: &'a str,
+    after_sqrt_price: &'a str,
+    price_impact_bps: i64,
+    partner: &'a str,
+    fee_amount: i64,
+    checkpoint_seq: i64,
+    tx_index: i64,
+    event_seq: i64,
+    sender: &'a str,
+}
+
+/// Row of either liquidity table, which share their columns
+#[derive(Row, Serialize)]
+struct LiquidityRow<'a> {
+    id: &'a str,
+    liquidity: &'a str,
+    after_liquidity: &'a str,
+    package_id: &'a str,
+    tx_digest: &'a str,
+    event_index: i64,
+    pool: &'a str,
+    position: &'a str,
+    timestamp_ms: i64,
+    checkpoint_seq: i64,
+    tx_index: i64,
+    event_seq: i64,
+}
+
+#[derive(Row, Serialize)]
+struct DynamicRow<'a> {
+    id: &'a str,
+    event_name: &'a str,
+    event_type: &'a str,
+    package_id: &'a str,
+    tx_digest: &'a str,
+    event_index: i64,
+    timestamp_ms: i64,
+    checkpoint_seq: i64,
+    tx_index: i64,
+    event_seq: i64,
+    /// The decoded fields as JSON text
+    data: String,
+}
+
+/// Inserts events into ClickHouse, one `INSERT` per table and write. Wrap it in a
+/// `BatchingSink` (BUFFER_MAX_EVENTS) to get the large inserts ClickHouse prefers.
+pub struct ClickHouseSink {
+    client: Client,
+}
+
+impl ClickHouseSink {
+    /// Connect to ClickHouse and create the event tables when they don't exist yet
+    pub async fn connect(url: &str, database: &str, user: Option<String>, password: Option<String>) -> Result<Self> {
+        let mut client = Client::default().with_url(url).with_database(database);
+        if let Some(user) = user {
+            client = client.with_user(user);
+        }
+        if let Some(password) = password {
+            client = client.with_password(password);
+        }
+        for sql in CREATE_TABLES_SQL {
+            client
+                .query(sql)
+                .execute()
+                .await
+                .with_context(|| format!("Failed to create ClickHouse tables in {} at {}", database, url))?;
+        }
+        info!("Writing events to ClickHouse database {} at {}", database, url);
+        Ok(Self { client })
+    }
+
+    async fn insert<T: Row + Serialize>(&self, table: &str, rows: impl IntoIterator<Item = T>) -> Result<()> {
+        let mut rows = rows.into_iter().peekable();
+        if rows.peek().is_none() {
+            return Ok(());
+        }
+        let mut insert = self.client.insert(table)?;
+        for row in rows {
+            insert.write(&row).await?;
+        }
+        insert.end().await.with_context(|| format!("Failed to insert into ClickHouse table {}", table))
+    }
+}
+
+#[async_trait]
+impl EventSink for ClickHouseSink {
+    fn name(&self) -> &str {
+        "clickhouse"
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        self.insert(
+            "cetus_swap_events",
+            events.swap_events.iter().map(|swap| SwapRow {
+                id: &swap.id,
+                amount_in: swap.amount_in,
+                amount_out: swap.amount_out,
+                pool_id: &swap.pool_id,
+                timestamp_ms: swap.timestamp_ms,
+                atob: swap.atob,
+                coin_type_a: &swap.coin_type_a,
+                coin_type_b: &swap.coin_type_b,
+                package_id: &swap.package_id,
+                tx_digest: &swap.tx_digest,
+                event_index: swap.event_index,
+                before_sqrt_price: &swap.before_sqrt_price,
+                after_sqrt_price: &swap.after_sqrt_price,
+                price_impact_bps: swap.price_impact_bps,
+                partner: &swap.partner,
+                fee_amount: swap.fee_amount,
+                checkpoint_seq: swap.checkpoint_seq,
+                tx_index: swap.tx_index,
+                event_seq: swap.event_seq,
+                sender: &swap.sender,
+            }),
+        )
+        .await?;
+        self.insert(
+            "cetus_add_liquidity_events",
+            events.add_liquidity_events.iter().map(|event| LiquidityRow {
+                id: &event.id,
+                liquidity: &event.liquidity,
+                after_liquidity: &event.after_liquidity,
+                package_id: &event.package_id,
+                tx_digest: &event.tx_digest,
+                event_index: event.event_index,
+                pool: &event.pool,
+                position: &event.position,
+                timestamp_ms: event.timestamp_ms,
+                checkpoint_seq: event.checkpoint_seq,
+                tx_index: event.tx_index,
+                event_seq: event.event_seq,
+            }),
+        )
+        .await?;
+        self.insert(
+            "cetus_remove_liquidity_events",
+            events.remove_liquidity_events.iter().map(|event| LiquidityRow {
+                id: &event.id,
+                liquidity: &event.liquidity,
+                after_liquidity: &event.after_liquidity,
+                package_id: &event.package_id,
+                tx_digest: &event.tx_digest,
+                event_index: event.event_index,
+                pool: &event.pool,
+                position: &event.position,
+                timestamp_ms: event.timestamp_ms,
+                checkpoint_seq: event.checkpoint_seq,
+                tx_index: event.tx_index,
+                event_seq: event.event_seq,
+            }),
+        )
+        .await?;
+        self.insert(
+            "dynamic_events",
+            events.dynamic_events.iter().map(|event| DynamicRow {
+                id: &event.id,
+                event_name: &event.event_name,
+                event_type: &event.event_type,
+                package_id: &event.package_id,
+                tx_digest: &event.tx_digest,
+                event_index: event.event_index,
+                timestamp_ms: event.timestamp_ms,
+                checkpoint_seq: event.checkpoint_seq,
+                tx_index: event.tx_index,
+                event_seq: event.event_seq,
+                data: event.data.to_string(),
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+}
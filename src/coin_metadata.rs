@@ -0,0 +1,163 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Coin decimals and symbols fetched from a Sui fullnode. When `SUI_RPC_URL` is set, the
+//! indexer looks up `suix_getCoinMetadata` for every coin type the first time a swap involving
+//! it is committed and stores the result in `coin_metadata`, so decimal-adjusted amounts work
+//! for any token without maintaining the decimals by hand.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use diesel::ExpressionMethods;
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::models::CoinMetadata;
+use crate::registry::IndexedEvents;
+use crate::schema::coin_metadata;
+use crate::sink::EventSink;
+use crate::PgConnectionPool;
+
+// Coin types waiting to be looked up; more are picked up again from later swaps
+const LOOKUP_QUEUE_SIZE: usize = 1000;
+
+/// `result` of a `suix_getCoinMetadata` response
+#[derive(Deserialize)]
+struct RpcCoinMetadata {
+    decimals: u8,
+    symbol: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<RpcCoinMetadata>,
+    error: Option<serde_json::Value>,
+}
+
+/// Fetch a coin's metadata from a fullnode. Returns `None` for coins without metadata.
+pub async fn fetch_coin_metadata(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    coin_type: &str,
+) -> Result<Option<CoinMetadata>> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "suix_getCoinMetadata",
+        "params": [coin_type],
+    });
+    let response = client
+        .post(rpc_url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(&request)?)
+        .send()
+        .await?
+        .error_for_status()?;
+    let response: RpcResponse = serde_json::from_slice(&response.bytes().await?)?;
+    if let Some(error) = response.error {
+        return Err(anyhow!("suix_getCoinMetadata failed for {}: {}", coin_type, error));
+    }
+    Ok(response.result.map(|metadata| CoinMetadata {
+        coin_type: coin_type.to_string(),
+        decimals: i16::from(metadata.decimals),
+        symbol: metadata.symbol,
+        name: metadata.name,
+        fetched_at: chrono::Utc::now(),
+    }))
+}
+
+/// Queues the coin types of committed swaps for a metadata lookup. Never fails or blocks a
+/// write: when the queue is full, the coin type is looked up from a later swap instead.
+/// Run it after the database sinks in a `ChainedSink`.
+pub struct CoinMetadataSink {
+    /// Coin types that are stored or queued, so each is looked up once per run
+    seen: Arc<Mutex<HashSet<String>>>,
+    sender: mpsc::Sender<String>,
+}
+
+impl CoinMetadataSink {
+    /// Must be called from within a tokio runtime, which runs the lookup task
+    pub fn new(pg_pool: PgConnectionPool, rpc_url: String) -> Self {
+        let seen = Arc::new(Mutex::new(HashSet::new()));
+        let (sender, receiver) = mpsc::channel(LOOKUP_QUEUE_SIZE);
+        tokio::spawn(resolve_coin_types(pg_pool, rpc_url, seen.clone(), receiver));
+        Self { seen, sender }
+    }
+}
+
+#[async_trait]
+impl EventSink for CoinMetadataSink {
+    fn name(&self) -> &str {
+        "coin_metadata"
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        let mut seen = self.seen.lock().unwrap();
+        let coin_types = events
+            .swap_events
+            .iter()
+            .flat_map(|swap| [&swap.coin_type_a, &swap.coin_type_b])
+            .filter(|coin_type| !coin_type.is_empty());
+        for coin_type in coin_types {
+            if seen.insert(coin_type.clone()) && self.sender.try_send(coin_type.clone()).is_err() {
+                seen.remove(coin_type);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Look up queued coin types that aren't stored yet, one at a time
+async fn resolve_coin_types(
+    pg_pool: PgConnectionPool,
+    rpc_url: String,
+    seen: Arc<Mutex<HashSet<String>>>,
+    mut receiver: mpsc::Receiver<String>,
+) {
+    let client = reqwest::Client::new();
+    while let Some(coin_type) = receiver.recv().await {
+        if let Err(e) = resolve_coin_type(&pg_pool, &client, &rpc_url, &coin_type).await {
+            warn!("Failed to resolve metadata of {}: {:#}", coin_type, e);
+            // Try again the next time a swap involves the coin
+            seen.lock().unwrap().remove(&coin_type);
+        }
+    }
+}
+
+async fn resolve_coin_type(
+    pg_pool: &PgConnectionPool,
+    client: &reqwest::Client,
+    rpc_url: &str,
+    coin_type: &str,
+) -> Result<()> {
+    let mut conn = pg_pool.get().await?;
+    let stored = coin_metadata::table
+        .filter(coin_metadata::coin_type.eq(coin_type))
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await?;
+    if stored > 0 {
+        return Ok(());
+    }
+
+    match fetch_coin_metadata(client, rpc_url, coin_type).await? {
+        Some(metadata) => {
+            info!("Fetched metadata of {}: {} with {} decimals", coin_type, metadata.symbol, metadata.decimals);
+            diesel::insert_into(coin_metadata::table)
+                .values(&metadata)
+                .on_conflict_do_nothing()
+                .execute(&mut conn)
+                .await?;
+        }
+        None => warn!("{} has no coin metadata on chain", coin_type),
+    }
+    Ok(())
+}
@@ -0,0 +1,308 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Skipping of events that were already stored, so checkpoints reprocessed after a restart
+//! don't repeat their upserts. A bloom filter of stored event ids is kept in memory and saved
+//! to disk periodically. A false positive would skip an event that was never stored, so the
+//! filter is only consulted for checkpoints below the progress watermark at startup, whose
+//! events are all known to be stored; newer checkpoints are always written.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info, warn};
+
+use crate::registry::IndexedEvents;
+use crate::sink::EventSink;
+
+// Identifies a saved filter file, followed by the layout version
+const FILE_MAGIC: &[u8; 4] = b"BLM1";
+
+/// Fixed-size bloom filter over event ids, using double hashing of the id's SHA-256 so the
+/// bit positions stay the same across restarts and Rust versions
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+    /// Ids inserted so far; the false-positive rate grows once it exceeds the capacity
+    count: u64,
+    capacity: u64,
+}
+
+impl BloomFilter {
+    /// Size the filter for `capacity` ids at the given false-positive rate
+    pub fn new(capacity: u64, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1);
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-(capacity as f64) * false_positive_rate.ln() / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / capacity as f64) * ln2).round().max(1.0) as u32;
+        Self {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_hashes,
+            count: 0,
+            capacity,
+        }
+    }
+
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    fn positions(&self, id: &str) -> impl Iterator<Item = u64> {
+        let digest = Sha256::digest(id.as_bytes());
+        let h1 = u64::from_le_bytes(digest[0..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap());
+        let num_bits = self.num_bits();
+        (0..u64::from(self.num_hashes)).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.positions(id)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    pub fn insert(&mut self, id: &str) {
+        let positions = self.positions(id).collect::<Vec<_>>();
+        for bit in positions {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.count += 1;
+        if self.count == self.capacity + 1 {
+            warn!(
+                "Dedup bloom filter holds more than its capacity of {} ids; raise DEDUP_BLOOM_CAPACITY \
+                 and delete the filter file to keep false positives rare",
+                self.capacity
+            );
+        }
+    }
+
+    /// Load a filter saved by `save`. Returns `None` when the file doesn't exist or was sized
+    /// differently, in which case the caller starts from an empty filter.
+    pub fn load(path: &Path, capacity: u64, false_positive_rate: f64) -> Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read bloom filter {}", path.display())),
+        };
+        if bytes.len() < 24 || &bytes[0..4] != FILE_MAGIC {
+            bail!("{} is not a dedup bloom filter file", path.display());
+        }
+        let num_hashes = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        let count = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let num_words = u64::from_le_bytes(bytes[16..24].try_into().unwrap()) as usize;
+        if bytes.len() != 24 + num_words * 8 {
+            bail!("Bloom filter file {} is truncated", path.display());
+        }
+
+        let expected = Self::new(capacity, false_positive_rate);
+        if expected.bits.len() != num_words || expected.num_hashes != num_hashes {
+            warn!(
+                "Bloom filter {} was sized for a different DEDUP_BLOOM_CAPACITY or DEDUP_BLOOM_FP_RATE, starting empty",
+                path.display()
+            );
+            return Ok(None);
+        }
+        let bits = bytes[24..]
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        Ok(Some(Self {
+            bits,
+            num_hashes,
+            count,
+            capacity: expected.capacity,
+        }))
+    }
+
+    /// Write the filter to a temporary file and rename it over `path`, so a crash mid-save
+    /// leaves the previous filter intact
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut bytes = Vec::with_capacity(24 + self.bits.len() * 8);
+        bytes.extend_from_slice(FILE_MAGIC);
+        bytes.extend_from_slice(&self.num_hashes.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        bytes.extend_from_slice(&(self.bits.len() as u64).to_le_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bytes).with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Drops events of checkpoints below `stored_below` whose id is in the bloom filter before
+/// writing to the inner sink, and adds the ids of everything the inner sink accepted. Observed
+/// event type counts are always passed on.
+pub struct DedupSink {
+    inner: Box<dyn EventSink>,
+    filter: Arc<Mutex<BloomFilter>>,
+    /// The progress watermark at startup: every event of an earlier checkpoint was committed,
+    /// so a false positive there can't drop an unstored event
+    stored_below: u64,
+}
+
+impl DedupSink {
+    pub fn new(inner: Box<dyn EventSink>, filter: Arc<Mutex<BloomFilter>>, stored_below: u64) -> Self {
+        Self {
+            inner,
+            filter,
+            stored_below,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for DedupSink {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        let mut unseen = events.clone();
+        {
+            let filter = self.filter.lock().unwrap();
+            let stored = |checkpoint_seq: i64, id: &str| {
+                u64::try_from(checkpoint_seq).is_ok_and(|seq| seq < self.stored_below) && filter.contains(id)
+            };
+            unseen.swap_events.retain(|event| !stored(event.checkpoint_seq, &event.id));
+            unseen.add_liquidity_events.retain(|event| !stored(event.checkpoint_seq, &event.id));
+            unseen.remove_liquidity_events.retain(|event| !stored(event.checkpoint_seq, &event.id));
+            unseen.dynamic_events.retain(|event| !stored(event.checkpoint_seq, &event.id));
+        }
+        let skipped = events.event_count() - unseen.event_count();
+        if skipped > 0 {
+            debug!("Skipped {} already stored events", skipped);
+        }
+        if unseen.is_empty() {
+            return Ok(());
+        }
+
+        self.inner.write(&unseen).await?;
+
+        let mut filter = self.filter.lock().unwrap();
+        for id in unseen
+            .swap_events
+            .iter()
+            .map(|event| &event.id)
+            .chain(unseen.add_liquidity_events.iter().map(|event| &event.id))
+            .chain(unseen.remove_liquidity_events.iter().map(|event| &event.id))
+            .chain(unseen.dynamic_events.iter().map(|event| &event.id))
+        {
+            filter.insert(id);
+        }
+        Ok(())
+    }
+}
+
+/// Save the filter to `path` every `interval`, so a restart only repeats the upserts of
+/// the last interval
+pub fn spawn_filter_saver(filter: Arc<Mutex<BloomFilter>>, path: PathBuf, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately; there is nothing new to save yet
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = save_filter(&filter, &path) {
+                error!("Failed to save dedup bloom filter: {:#}", e);
+            }
+        }
+    });
+}
+
+/// Save the filter without holding its lock during the disk write
+pub fn save_filter(filter: &Mutex<BloomFilter>, path: &Path) -> Result<()> {
+    let snapshot = {
+        let filter = filter.lock().unwrap();
+        BloomFilter {
+            bits: filter.bits.clone(),
+            num_hashes: filter.num_hashes,
+            count: filter.count,
+            capacity: filter.capacity,
+        }
+    };
+    snapshot.save(path)?;
+    info!("Saved dedup bloom filter ({} ids) to {}", snapshot.count, path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DynamicEvent;
+
+    /// Inner sink that records the ids it was asked to write
+    #[derive(Default)]
+    struct RecordingSink {
+        written: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl EventSink for RecordingSink {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn write(&self, events: &IndexedEvents) -> Result<()> {
+            let mut written = self.written.lock().unwrap();
+            written.extend(events.dynamic_events.iter().map(|event| event.id.clone()));
+            Ok(())
+        }
+    }
+
+    fn event(id: &str, checkpoint_seq: i64) -> DynamicEvent {
+        DynamicEvent {
+            id: id.to_string(),
+            event_name: "test".to_string(),
+            event_type: "0x2::test::Event".to_string(),
+            package_id: "0x2".to_string(),
+            tx_digest: "digest".to_string(),
+            event_index: 0,
+            timestamp_ms: 0,
+            checkpoint_seq,
+            tx_index: 0,
+            event_seq: checkpoint_seq << 24,
+            data: serde_json::Value::Null,
+            created_at: None,
+        }
+    }
+
+    // A sink stored below checkpoint 100, whose filter claims every id was already stored
+    fn saturated_dedup_sink() -> (DedupSink, Arc<Mutex<Vec<String>>>) {
+        let mut filter = BloomFilter::new(1, 0.5);
+        filter.bits.fill(u64::MAX);
+        let inner = RecordingSink::default();
+        let written = inner.written.clone();
+        (DedupSink::new(Box::new(inner), Arc::new(Mutex::new(filter)), 100), written)
+    }
+
+    #[tokio::test]
+    async fn false_positives_at_or_above_the_watermark_are_written() {
+        let (sink, written) = saturated_dedup_sink();
+        let mut events = IndexedEvents::default();
+        events.dynamic_events.push(event("at-watermark", 100));
+        events.dynamic_events.push(event("above-watermark", 101));
+        sink.write(&events).await.unwrap();
+        assert_eq!(*written.lock().unwrap(), ["at-watermark", "above-watermark"]);
+    }
+
+    #[tokio::test]
+    async fn stored_events_below_the_watermark_are_skipped() {
+        let (sink, written) = saturated_dedup_sink();
+        let mut events = IndexedEvents::default();
+        events.dynamic_events.push(event("below-watermark", 99));
+        events.dynamic_events.push(event("new", 100));
+        sink.write(&events).await.unwrap();
+        assert_eq!(*written.lock().unwrap(), ["new"]);
+    }
+}
@@ -0,0 +1,429 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decoding of events described by a BCS layout in the protocols config, so new events can be
+//! indexed without recompiling. A config file lists events and their fields in declaration
+//! order, e.g.
+//!
+//! ```toml
+//! [[events]]
+//! name = "turbos_swap"
+//! event_type = "0x91bf...::pool::SwapEvent"
+//!
+//! [[events.fields]]
+//! name = "pool"
+//! type = "address"
+//!
+//! [[events.fields]]
+//! name = "amount_a"
+//! type = "u64"
+//! ```
+//!
+//! Decoded events are stored in `dynamic_events` with their fields as a JSON object. An event
+//! with a `view` also gets a view of that name over its rows, with one typed column per field,
+//! so a new protocol is added as data: a config entry, no migration and no Rust changes. The
+//! Cetus tables keep their compiled decoders and columns.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use diesel_async::SimpleAsyncConnection;
+use move_core_types::language_storage::StructTag;
+use move_core_types::u256::U256;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sui_types::base_types::ObjectID;
+
+use crate::indexer::{canonical_object_id, event_seq};
+use crate::models::DynamicEvent;
+use crate::registry::{EventContext, EventTable, IndexedEvent, Route};
+use crate::PgConnectionPool;
+
+// Columns of `dynamic_events` that every view has, so fields can't take their names
+const VIEW_POSITION_COLUMNS: &[&str] = &[
+    "id",
+    "package_id",
+    "tx_digest",
+    "event_index",
+    "timestamp_ms",
+    "checkpoint_seq",
+    "tx_index",
+    "event_seq",
+];
+
+/// Move type of a field in an event layout, written as e.g. `u64` or `vector<address>`
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum FieldType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    /// An address or object id, rendered as a 0x-prefixed 64 hex character string
+    Address,
+    /// UTF-8 or ASCII string, also used for `TypeName`
+    String,
+    Vector(Box<FieldType>),
+    /// `Option<T>`, encoded by BCS as a vector of zero or one element
+    Option(Box<FieldType>),
+}
+
+impl FromStr for FieldType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(inner) = s.strip_prefix("vector<").and_then(|rest| rest.strip_suffix('>')) {
+            return Ok(FieldType::Vector(Box::new(inner.parse()?)));
+        }
+        if let Some(inner) = s.strip_prefix("option<").and_then(|rest| rest.strip_suffix('>')) {
+            return Ok(FieldType::Option(Box::new(inner.parse()?)));
+        }
+        match s {
+            "bool" => Ok(FieldType::Bool),
+            "u8" => Ok(FieldType::U8),
+            "u16" => Ok(FieldType::U16),
+            "u32" => Ok(FieldType::U32),
+            "u64" => Ok(FieldType::U64),
+            "u128" => Ok(FieldType::U128),
+            "u256" => Ok(FieldType::U256),
+            "address" => Ok(FieldType::Address),
+            "string" => Ok(FieldType::String),
+            _ => Err(anyhow!(
+                "Unknown field type '{}', expected one of: bool, u8, u16, u32, u64, u128, u256, \
+                 address, string, vector<T>, option<T>",
+                s
+            )),
+        }
+    }
+}
+
+impl FieldType {
+    /// SQL expression reading a field of this type out of the `data` column. Integers wider
+    /// than `u32` become NUMERIC, vectors stay JSONB.
+    fn view_column(&self, key: &str) -> String {
+        let quoted = key.replace('\'', "''");
+        match self {
+            FieldType::Bool => format!("(data->>'{}')::BOOLEAN", quoted),
+            FieldType::U8 | FieldType::U16 | FieldType::U32 => format!("(data->>'{}')::INT8", quoted),
+            FieldType::U64 | FieldType::U128 | FieldType::U256 => format!("(data->>'{}')::NUMERIC", quoted),
+            FieldType::Address | FieldType::String => format!("data->>'{}'", quoted),
+            FieldType::Vector(_) => format!("data->'{}'", quoted),
+            // None is stored as null, Some as the value itself
+            FieldType::Option(element) => element.view_column(key),
+        }
+    }
+}
+
+impl TryFrom<String> for FieldType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+/// A named field of an event layout
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldLayout {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+}
+
+/// Layout of one event type, with its fields in declaration order
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventLayout {
+    /// Label stored with every decoded event, e.g. `turbos_swap`
+    pub name: String,
+    pub event_type: String,
+    pub fields: Vec<FieldLayout>,
+    /// Protocol tag prepended to the event ids, e.g. `turbos:`; defaults to ID_PREFIX
+    #[serde(default)]
+    pub id_prefix: Option<String>,
+    /// View created over this event's rows with a column per field, e.g. `turbos_swaps`
+    #[serde(default)]
+    pub view: Option<String>,
+}
+
+/// Contents of the protocols config file. Unknown keys are rejected rather than ignored, so a
+/// typo'd key fails at startup instead of silently disabling an event.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtocolsConfig {
+    #[serde(default)]
+    pub events: Vec<EventLayout>,
+}
+
+impl ProtocolsConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read protocols config {}", path.display()))?;
+        // toml errors name the offending key and its line and column
+        let config: Self = toml::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid protocols config {}: {}", path.display(), e))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid protocols config {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Check what deserialization can't: required strings are non-empty, every event has fields,
+    /// event and field names are unique, and view names are plain identifiers
+    pub fn validate(&self) -> Result<()> {
+        let mut event_names = HashSet::new();
+        let mut view_names = HashSet::new();
+        for (index, layout) in self.events.iter().enumerate() {
+            let label = format!("events[{}]", index);
+            if layout.name.trim().is_empty() {
+                bail!("{}: `name` must not be empty", label);
+            }
+            let label = format!("{} ({})", label, layout.name);
+            if !event_names.insert(layout.name.as_str()) {
+                bail!("{}: duplicate event name '{}'", label, layout.name);
+            }
+            if layout.event_type.trim().is_empty() {
+                bail!("{}: `event_type` must not be empty", label);
+            }
+            if layout.fields.is_empty() {
+                bail!("{}: `fields` must list at least one field", label);
+            }
+            let mut field_names = HashSet::new();
+            for (field_index, field) in layout.fields.iter().enumerate() {
+                if field.name.trim().is_empty() {
+                    bail!("{}: fields[{}] `name` must not be empty", label, field_index);
+                }
+                if !field_names.insert(field.name.as_str()) {
+                    bail!("{}: duplicate field name '{}'", label, field.name);
+                }
+                if layout.view.is_some() && VIEW_POSITION_COLUMNS.contains(&field.name.as_str()) {
+                    bail!("{}: field name '{}' is taken by a view column", label, field.name);
+                }
+            }
+            if let Some(view) = &layout.view {
+                // Interpolated into SQL, so only plain lowercase identifiers are allowed
+                let valid = !view.is_empty()
+                    && view.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                    && !view.starts_with(|c: char| c.is_ascii_digit());
+                if !valid {
+                    bail!("{}: invalid view name '{}', expected a lowercase identifier", label, view);
+                }
+                if !view_names.insert(view.as_str()) {
+                    bail!("{}: duplicate view name '{}'", label, view);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// SQL recreating the view of every event that has one. Views are dropped first, since
+    /// `CREATE OR REPLACE VIEW` can't drop or retype columns after a layout changes.
+    pub fn view_statements(&self) -> Vec<String> {
+        self.events
+            .iter()
+            .filter_map(|layout| {
+                let view = layout.view.as_ref()?;
+                let columns = layout
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "{} AS \"{}\"",
+                            field.field_type.view_column(&field.name),
+                            field.name.replace('"', "\"\"")
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                Some(format!(
+                    "DROP VIEW IF EXISTS {view}; \
+                     CREATE VIEW {view} AS SELECT {position}, {columns} \
+                     FROM dynamic_events WHERE event_name = '{name}';",
+                    view = view,
+                    position = VIEW_POSITION_COLUMNS.join(", "),
+                    columns = columns.join(", "),
+                    name = layout.name.replace('\'', "''"),
+                ))
+            })
+            .collect()
+    }
+
+    /// Create the configured views, in one transaction so readers never miss a view
+    pub async fn create_views(&self, pool: &PgConnectionPool) -> Result<()> {
+        let statements = self.view_statements();
+        if statements.is_empty() {
+            return Ok(());
+        }
+        let mut conn = pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+        conn.batch_execute(&format!("BEGIN; {} COMMIT;", statements.join(" ")))
+            .await
+            .context("Failed to create the protocol views")?;
+        Ok(())
+    }
+
+    /// Build a route for every event in the config
+    pub fn routes(&self) -> Result<Vec<Route>> {
+        self.events
+            .iter()
+            .map(|layout| DynamicDecoder::new(layout.clone()).map(DynamicDecoder::into_route))
+            .collect()
+    }
+}
+
+/// Decodes the BCS contents of an event according to an `EventLayout`
+pub struct DynamicDecoder {
+    struct_tag: StructTag,
+    layout: EventLayout,
+}
+
+impl DynamicDecoder {
+    pub fn new(layout: EventLayout) -> Result<Self> {
+        let struct_tag = StructTag::from_str(&layout.event_type)
+            .map_err(|e| anyhow!("Invalid event_type '{}' for {}: {}", layout.event_type, layout.name, e))?;
+        Ok(Self { struct_tag, layout })
+    }
+
+    /// Decode event contents into a JSON object keyed by field name. Fails when the contents
+    /// are shorter or longer than the layout describes.
+    pub fn decode(&self, contents: &[u8]) -> Result<Value> {
+        let mut reader = BcsReader::new(contents);
+        let mut object = Map::new();
+        for field in &self.layout.fields {
+            let value = reader
+                .read_value(&field.field_type)
+                .with_context(|| format!("Failed to decode field '{}' of {}", field.name, self.layout.name))?;
+            object.insert(field.name.clone(), value);
+        }
+        if reader.remaining() > 0 {
+            bail!(
+                "{} bytes left over after decoding {}, the layout does not match the event",
+                reader.remaining(),
+                self.layout.name
+            );
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// Route the layout's event type to `dynamic_events` through this decoder
+    pub fn into_route(self) -> Route {
+        let struct_tag = self.struct_tag.clone();
+        let id_prefix = self.layout.id_prefix.clone();
+        let decoder = Arc::new(self);
+        Route {
+            struct_tag,
+            table: EventTable::Dynamic,
+            decoder: Arc::new(move |ctx: &EventContext<'_>| decoder.decode_event(ctx)),
+            id_prefix,
+        }
+    }
+
+    fn decode_event(&self, ctx: &EventContext<'_>) -> Result<IndexedEvent> {
+        Ok(IndexedEvent::Dynamic(DynamicEvent {
+            id: ctx.id.clone(),
+            event_name: self.layout.name.clone(),
+            event_type: self.struct_tag.to_canonical_string(true),
+            package_id: ctx.event.package_id.to_string(),
+            tx_digest: ctx.tx_digest.to_string(),
+            event_index: ctx.event_index as i64,
+            timestamp_ms: ctx.timestamp_ms as i64,
+            checkpoint_seq: ctx.checkpoint_seq as i64,
+            tx_index: ctx.tx_index as i64,
+            event_seq: event_seq(ctx.checkpoint_seq, ctx.tx_index, ctx.event_index),
+            data: self.decode(&ctx.event.contents)?,
+            created_at: None,
+        }))
+    }
+}
+
+/// Minimal BCS reader for the primitive types supported in layouts
+struct BcsReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BcsReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            bail!("Unexpected end of input: needed {} bytes, {} left", len, self.remaining());
+        }
+        let bytes = &self.bytes[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// Sequence lengths are ULEB128 encoded and fit in a u32
+    fn read_length(&mut self) -> Result<usize> {
+        let mut value: u64 = 0;
+        for shift in (0..32).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                if value > u64::from(u32::MAX) {
+                    bail!("Sequence length {} out of range", value);
+                }
+                return Ok(value as usize);
+            }
+        }
+        bail!("Invalid ULEB128 sequence length")
+    }
+
+    fn read_value(&mut self, field_type: &FieldType) -> Result<Value> {
+        Ok(match field_type {
+            FieldType::Bool => match self.take(1)?[0] {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                other => bail!("Invalid bool byte {}", other),
+            },
+            FieldType::U8 => Value::from(self.take(1)?[0]),
+            FieldType::U16 => Value::from(u16::from_le_bytes(self.take_array()?)),
+            FieldType::U32 => Value::from(u32::from_le_bytes(self.take_array()?)),
+            // Wider integers are strings, since JSON numbers lose precision above 2^53
+            FieldType::U64 => Value::String(u64::from_le_bytes(self.take_array()?).to_string()),
+            FieldType::U128 => Value::String(u128::from_le_bytes(self.take_array()?).to_string()),
+            FieldType::U256 => Value::String(U256::from_le_bytes(&self.take_array()?).to_string()),
+            FieldType::Address => {
+                let id = ObjectID::from_bytes(self.take(ObjectID::LENGTH)?)?;
+                Value::String(canonical_object_id(id))
+            }
+            FieldType::String => {
+                let len = self.read_length()?;
+                let bytes = self.take(len)?;
+                Value::String(String::from_utf8(bytes.to_vec()).context("String field is not valid UTF-8")?)
+            }
+            FieldType::Vector(element) => {
+                let len = self.read_length()?;
+                let values = (0..len)
+                    .map(|_| self.read_value(element))
+                    .collect::<Result<Vec<_>>>()?;
+                Value::Array(values)
+            }
+            FieldType::Option(element) => match self.read_length()? {
+                0 => Value::Null,
+                1 => self.read_value(element)?,
+                len => bail!("Invalid option length {}", len),
+            },
+        })
+    }
+}
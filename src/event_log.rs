@@ -0,0 +1,162 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Append-only log of committed events on local disk, for recovering the event tables without
+//! a database backup. Every event is one JSON line, `{"table": "cetus_swap_events", "event":
+//! {...}}`, with the event in the same layout as the API. `FileLogSink` appends to
+//! `events-<UTC start time>.ndjson` files in a directory, starting a new file once the current
+//! one reaches a size or age limit; the `import_event_log` binary replays them into Postgres.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+use crate::models::{CetusAddLiquidityEvent, CetusRemoveLiquidityEvent, CetusSwapEvent, DynamicEvent};
+use crate::registry::{EventTable, IndexedEvent, IndexedEvents};
+use crate::sink::EventSink;
+
+/// One line of the log
+#[derive(Deserialize)]
+struct LogLine {
+    table: String,
+    event: serde_json::Value,
+}
+
+/// Parse a line written by `FileLogSink`
+pub fn parse_log_line(line: &str) -> Result<IndexedEvent> {
+    let line: LogLine = serde_json::from_str(line)?;
+    let event = if line.table == EventTable::Swap.table_name() {
+        IndexedEvent::Swap(serde_json::from_value::<CetusSwapEvent>(line.event)?)
+    } else if line.table == EventTable::AddLiquidity.table_name() {
+        IndexedEvent::AddLiquidity(serde_json::from_value::<CetusAddLiquidityEvent>(line.event)?)
+    } else if line.table == EventTable::RemoveLiquidity.table_name() {
+        IndexedEvent::RemoveLiquidity(serde_json::from_value::<CetusRemoveLiquidityEvent>(line.event)?)
+    } else if line.table == EventTable::Dynamic.table_name() {
+        IndexedEvent::Dynamic(serde_json::from_value::<DynamicEvent>(line.event)?)
+    } else {
+        bail!("Unknown table '{}'", line.table);
+    };
+    Ok(event)
+}
+
+/// Log files of a directory in the order they were written, which is their name order
+pub fn log_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = fs::read_dir(dir)
+        .with_context(|| format!("Failed to list event log directory {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    files.retain(|path| path.extension().is_some_and(|extension| extension == "ndjson"));
+    files.sort();
+    Ok(files)
+}
+
+/// File currently appended to
+struct LogFile {
+    file: File,
+    path: PathBuf,
+    size: u64,
+    opened_at: Instant,
+}
+
+/// Appends every written event to the log and syncs it to disk before returning, so a
+/// checkpoint is only marked done once its events are in the log. Run it next to the database
+/// sinks in a `FanoutSink`.
+pub struct FileLogSink {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    max_file_age: Duration,
+    current: Mutex<Option<LogFile>>,
+}
+
+impl FileLogSink {
+    /// Start a new file once the current one holds `max_file_bytes` or was opened `max_file_age` ago
+    pub fn new(dir: impl Into<PathBuf>, max_file_bytes: u64, max_file_age: Duration) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create event log directory {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            max_file_bytes,
+            max_file_age,
+            current: Mutex::new(None),
+        })
+    }
+
+    fn open_file(&self) -> Result<LogFile> {
+        // Second resolution plus a counter keeps names unique and sorted when files rotate quickly
+        let started = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+        let mut path = self.dir.join(format!("events-{}.ndjson", started));
+        let mut suffix = 1;
+        while path.exists() {
+            path = self.dir.join(format!("events-{}-{}.ndjson", started, suffix));
+            suffix += 1;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open event log {}", path.display()))?;
+        info!("Writing event log {}", path.display());
+        Ok(LogFile {
+            file,
+            path,
+            size: 0,
+            opened_at: Instant::now(),
+        })
+    }
+}
+
+#[async_trait]
+impl EventSink for FileLogSink {
+    fn name(&self) -> &str {
+        "event_log"
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        let mut lines = Vec::new();
+        let mut append = |table: EventTable, event: serde_json::Result<serde_json::Value>| -> Result<()> {
+            serde_json::to_writer(&mut lines, &json!({ "table": table.table_name(), "event": event? }))?;
+            lines.push(b'\n');
+            Ok(())
+        };
+        for event in &events.swap_events {
+            append(EventTable::Swap, serde_json::to_value(event))?;
+        }
+        for event in &events.add_liquidity_events {
+            append(EventTable::AddLiquidity, serde_json::to_value(event))?;
+        }
+        for event in &events.remove_liquidity_events {
+            append(EventTable::RemoveLiquidity, serde_json::to_value(event))?;
+        }
+        for event in &events.dynamic_events {
+            append(EventTable::Dynamic, serde_json::to_value(event))?;
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let mut current = self.current.lock().map_err(|_| anyhow!("Event log lock poisoned"))?;
+        let rotate = current
+            .as_ref()
+            .map_or(true, |log| log.size >= self.max_file_bytes || log.opened_at.elapsed() >= self.max_file_age);
+        if rotate {
+            *current = Some(self.open_file()?);
+        }
+        let log = current.as_mut().expect("opened above");
+        if let Err(e) = log.file.write_all(&lines).and_then(|()| log.file.sync_data()) {
+            let path = log.path.clone();
+            // The file may end in a partial line now; continue in a fresh file on the retry
+            *current = None;
+            return Err(e).with_context(|| format!("Failed to append to event log {}", path.display()));
+        }
+        log.size += lines.len() as u64;
+        Ok(())
+    }
+}
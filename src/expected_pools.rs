@@ -0,0 +1,101 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Check of swap coin types against a curated list of pools, to catch a misconfigured event
+//! type that matches another deployment's events. The file maps pool ids to their coin types,
+//! e.g.
+//!
+//! ```toml
+//! [pools]
+//! "0xb8d7...630" = ["0x2::sui::SUI", "0xdba3...::usdc::USDC"]
+//! ```
+//!
+//! Swaps in a listed pool whose coin types differ are logged and counted in
+//! `pool_coin_type_mismatches_total`, but still stored. Pools not in the file aren't checked.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use move_core_types::language_storage::TypeTag;
+use prometheus::IntCounterVec;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::indexer::normalize_object_id;
+use crate::metrics::IndexerMetrics;
+use crate::registry::{EventContext, EventEnricher, IndexedEvent};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectedPoolsFile {
+    /// Pool id to `[coin_type_a, coin_type_b]`
+    pools: HashMap<String, [String; 2]>,
+}
+
+/// Enricher that compares each swap's coin types with the expected ones of its pool
+pub struct PoolCoinTypeValidator {
+    /// Canonical pool id to canonical `(coin_type_a, coin_type_b)`
+    expected: HashMap<String, (String, String)>,
+    mismatches: IntCounterVec,
+}
+
+impl PoolCoinTypeValidator {
+    /// Load the expected coin types from a TOML file, normalizing ids and coin types so they
+    /// compare equal to the stored ones however they were written
+    pub fn load(path: impl AsRef<Path>, metrics: &IndexerMetrics) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read expected pools {}", path.display()))?;
+        let file: ExpectedPoolsFile = toml::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid expected pools {}: {}", path.display(), e))?;
+
+        let canonical_coin_type = |coin_type: &str| {
+            TypeTag::from_str(coin_type)
+                .map(|type_tag| type_tag.to_canonical_string(true))
+                .map_err(|e| anyhow!("Invalid coin type '{}' in {}: {}", coin_type, path.display(), e))
+        };
+        let expected = file
+            .pools
+            .iter()
+            .map(|(pool_id, [coin_type_a, coin_type_b])| {
+                let pool_id = normalize_object_id(pool_id)
+                    .with_context(|| format!("Invalid pool id '{}' in {}", pool_id, path.display()))?;
+                Ok((pool_id, (canonical_coin_type(coin_type_a)?, canonical_coin_type(coin_type_b)?)))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(Self {
+            expected,
+            mismatches: metrics.pool_coin_type_mismatches_total.clone(),
+        })
+    }
+
+    /// Number of pools being checked
+    pub fn pool_count(&self) -> usize {
+        self.expected.len()
+    }
+}
+
+impl EventEnricher for PoolCoinTypeValidator {
+    fn enrich(&self, event: &mut IndexedEvent, ctx: &EventContext<'_>) {
+        let IndexedEvent::Swap(swap) = event else {
+            return;
+        };
+        let Some((coin_type_a, coin_type_b)) = self.expected.get(&swap.pool_id) else {
+            return;
+        };
+        // Empty coin types mean the pool object wasn't in the transaction; nothing to compare
+        if swap.coin_type_a.is_empty() && swap.coin_type_b.is_empty() {
+            return;
+        }
+        if &swap.coin_type_a != coin_type_a || &swap.coin_type_b != coin_type_b {
+            warn!(
+                "Swap {} in pool {} has coin types ({}, {}), expected ({}, {}); check the event types",
+                ctx.tx_digest, swap.pool_id, swap.coin_type_a, swap.coin_type_b, coin_type_a, coin_type_b
+            );
+            self.mismatches.with_label_values(&[&swap.pool_id]).inc();
+        }
+    }
+}
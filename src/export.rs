@@ -0,0 +1,83 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Arrow layout of exported swaps, written as Parquet by `/api/swaps/export.parquet`
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use crate::models::CetusSwapEvent;
+
+/// Columns of `cetus_swap_events`, in table order
+pub fn swap_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("amount_in", DataType::Int64, false),
+        Field::new("amount_out", DataType::Int64, false),
+        Field::new("created_at", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), true),
+        Field::new("pool_id", DataType::Utf8, false),
+        Field::new("timestamp_ms", DataType::Int64, false),
+        Field::new("atob", DataType::Boolean, false),
+        Field::new("coin_type_a", DataType::Utf8, false),
+        Field::new("coin_type_b", DataType::Utf8, false),
+        Field::new("package_id", DataType::Utf8, false),
+        Field::new("tx_digest", DataType::Utf8, false),
+        Field::new("event_index", DataType::Int64, false),
+        // u128 values don't fit any Parquet integer type, so they stay decimal strings
+        Field::new("before_sqrt_price", DataType::Utf8, false),
+        Field::new("after_sqrt_price", DataType::Utf8, false),
+        Field::new("price_impact_bps", DataType::Int64, false),
+        Field::new("partner", DataType::Utf8, false),
+        Field::new("fee_amount", DataType::Int64, false),
+        Field::new("checkpoint_seq", DataType::Int64, false),
+        Field::new("tx_index", DataType::Int64, false),
+        Field::new("event_seq", DataType::Int64, false),
+        Field::new("sender", DataType::Utf8, false),
+    ]))
+}
+
+/// Convert a batch of swaps to an Arrow record batch with `swap_schema`
+pub fn swaps_to_record_batch(swaps: &[CetusSwapEvent]) -> Result<RecordBatch> {
+    let strings = |value: fn(&CetusSwapEvent) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(swaps.iter().map(value)))
+    };
+    let integers = |value: fn(&CetusSwapEvent) -> i64| -> ArrayRef {
+        Arc::new(Int64Array::from_iter_values(swaps.iter().map(value)))
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(|swap| &swap.id),
+        integers(|swap| swap.amount_in),
+        integers(|swap| swap.amount_out),
+        Arc::new(
+            TimestampMicrosecondArray::from(
+                swaps
+                    .iter()
+                    .map(|swap| swap.created_at.map(|created_at| created_at.timestamp_micros()))
+                    .collect::<Vec<_>>(),
+            )
+            .with_timezone("UTC"),
+        ),
+        strings(|swap| &swap.pool_id),
+        integers(|swap| swap.timestamp_ms),
+        Arc::new(BooleanArray::from(swaps.iter().map(|swap| swap.atob).collect::<Vec<_>>())),
+        strings(|swap| &swap.coin_type_a),
+        strings(|swap| &swap.coin_type_b),
+        strings(|swap| &swap.package_id),
+        strings(|swap| &swap.tx_digest),
+        integers(|swap| swap.event_index),
+        strings(|swap| &swap.before_sqrt_price),
+        strings(|swap| &swap.after_sqrt_price),
+        integers(|swap| swap.price_impact_bps),
+        strings(|swap| &swap.partner),
+        integers(|swap| swap.fee_amount),
+        integers(|swap| swap.checkpoint_seq),
+        integers(|swap| swap.tx_index),
+        integers(|swap| swap.event_seq),
+        strings(|swap| &swap.sender),
+    ];
+    Ok(RecordBatch::try_new(swap_schema(), columns)?)
+}
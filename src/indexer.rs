@@ -1,16 +1,23 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::Arc;
 
+use anyhow::{anyhow, Result};
 use move_core_types::language_storage::StructTag;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sui_types::{
-    base_types::{ObjectID},
+    base_types::{ObjectID, SuiAddress},
     full_checkpoint_content::{CheckpointData, CheckpointTransaction},
 };
+use uuid::Uuid;
 
 use crate::models::{CetusAddLiquidityEvent, CetusRemoveLiquidityEvent, CetusSwapEvent};
+use crate::registry::{
+    EventContext, EventEnricher, EventTable, IndexedEvent, IndexedEvents, NoopEnricher, Route, RouteRegistry,
+};
 
 // Define constants for Cetus event types
 // Cetus Protocol package ID on Sui Mainnet
@@ -18,6 +25,44 @@ const CETUS_SWAP_EVENT_TYPE: &str = "0x1eabed72c53feb3805120a081dc15963c204dc8d0
 const CETUS_ADD_LIQUIDITY_EVENT_TYPE: &str = "0x1eabed72c53feb3805120a081dc15963c204dc8d091542592abaf7a35689b2fb::pool::AddLiquidityEvent";
 const CETUS_REMOVE_LIQUIDITY_EVENT_TYPE: &str = "0x1eabed72c53feb3805120a081dc15963c204dc8d091542592abaf7a35689b2fb::pool::RemoveLiquidityEvent";
 
+// Namespace for UUIDv5 event ids, so the same event always maps to the same id
+const EVENT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6f1c2b1e_9a4d_4c3b_8e52_7d0fa1b3c5e7);
+
+/// How primary keys are generated for event rows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdScheme {
+    /// `{package_id}-{tx_digest}-{kind}-{n}`, with `n` counting events of the same kind in the transaction
+    #[default]
+    Composite,
+    /// Deterministic UUIDv5 of `(tx_digest, event_index)`
+    Uuid,
+}
+
+impl FromStr for IdScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "composite" => Ok(IdScheme::Composite),
+            "uuid" => Ok(IdScheme::Uuid),
+            _ => Err(anyhow!("Invalid ID_SCHEME '{}', expected 'composite' or 'uuid'", s)),
+        }
+    }
+}
+
+/// Deterministic UUID for the event at `event_index` in transaction `tx_digest`
+pub fn event_uuid(tx_digest: &str, event_index: usize) -> Uuid {
+    Uuid::new_v5(&EVENT_ID_NAMESPACE, format!("{}:{}", tx_digest, event_index).as_bytes())
+}
+
+/// Total order over events: `(checkpoint_seq << 24) | (tx_index << 10) | event_index`.
+/// Sui caps a transaction at 1024 events and checkpoints hold far fewer than 2^14 transactions,
+/// leaving 39 bits for the checkpoint sequence number.
+pub fn event_seq(checkpoint_seq: u64, tx_index: usize, event_index: usize) -> i64 {
+    debug_assert!(tx_index < 1 << 14 && event_index < 1 << 10);
+    ((checkpoint_seq << 24) | ((tx_index as u64) << 10) | event_index as u64) as i64
+}
+
 // Struct for SwapEvent - corrected based on binary format from logs
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SwapEventData {
@@ -61,10 +106,226 @@ pub struct RemoveLiquidityEventData {
     pub amount_b: u64,
 }
 
+/// Full `0x`-prefixed, 64 hex character form of an object id, as stored in the database
+pub fn canonical_object_id(id: ObjectID) -> String {
+    format!("0x{}", hex::encode(id.into_bytes()))
+}
+
+/// Check that the package address of an event type is written as a full 32-byte ObjectID. Struct
+/// tag parsing zero-pads short addresses, so a truncated or mistyped package id still parses and
+/// then silently matches nothing.
+pub fn check_event_type_address(event_type: &str) -> Result<()> {
+    let address = event_type.split("::").next().unwrap_or_default().trim();
+    let hex_digits = address.strip_prefix("0x").unwrap_or(address);
+    if hex_digits.len() != ObjectID::LENGTH * 2 || !hex_digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!(
+            "Event type '{}' has package address '{}', expected a full 32-byte ObjectID ({} hex characters)",
+            event_type,
+            address,
+            ObjectID::LENGTH * 2
+        ));
+    }
+    Ok(())
+}
+
+/// Normalize a user-supplied object id (with or without `0x` and leading zeros) to the stored form,
+/// so `0x2` and `0x000...02` match the same rows
+pub fn normalize_object_id(id: &str) -> Result<String> {
+    let digits = id
+        .strip_prefix("0x")
+        .or_else(|| id.strip_prefix("0X"))
+        .unwrap_or(id);
+    if digits.is_empty() || digits.len() > 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("Invalid object id '{}'", id));
+    }
+    Ok(format!("0x{:0>64}", digits.to_ascii_lowercase()))
+}
+
+/// Parse a comma-separated list of sender addresses, ignoring blank entries. Addresses are
+/// normalized like object ids, so short forms such as `0x2` are accepted.
+pub fn parse_sender_allowlist(value: &str) -> Result<HashSet<SuiAddress>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            let normalized = normalize_object_id(address).map_err(|_| anyhow!("Invalid sender address '{}'", address))?;
+            SuiAddress::from_str(&normalized).map_err(|e| anyhow!("Invalid sender address '{}': {}", address, e))
+        })
+        .collect()
+}
+
+/// Coin types of a Cetus pool, read from the `Pool<CoinTypeA, CoinTypeB>` object touched by the transaction
+fn pool_coin_types(transaction: &CheckpointTransaction, pool: ObjectID) -> Option<(String, String)> {
+    transaction
+        .output_objects
+        .iter()
+        .chain(transaction.input_objects.iter())
+        .find(|object| object.id() == pool)
+        .and_then(|object| object.struct_tag())
+        .and_then(|struct_tag| match struct_tag.type_params.as_slice() {
+            [coin_type_a, coin_type_b] => Some((
+                coin_type_a.to_canonical_string(true),
+                coin_type_b.to_canonical_string(true),
+            )),
+            _ => None,
+        })
+}
+
+/// Relative move between two sqrt prices, in basis points:
+/// `|after - before| / before * 10000`, computed in u128 without overflow
+pub fn price_impact_bps(before_sqrt_price: u128, after_sqrt_price: u128) -> i64 {
+    if before_sqrt_price == 0 {
+        return 0;
+    }
+    let diff = before_sqrt_price.abs_diff(after_sqrt_price);
+    let bps = match diff.checked_mul(10_000) {
+        Some(scaled) => scaled / before_sqrt_price,
+        // Only reachable for sqrt prices near u128::MAX; trade the low digits for range
+        None => diff / (before_sqrt_price / 10_000).max(1),
+    };
+    i64::try_from(bps).unwrap_or(i64::MAX)
+}
+
+/// Decode BCS contents into `T`, ignoring bytes after the known layout. Returns the value and
+/// the number of trailing bytes that were ignored.
+pub fn decode_known_prefix<T: DeserializeOwned>(contents: &[u8]) -> Result<(T, usize)> {
+    match bcs::from_bytes::<T>(contents) {
+        Err(bcs::Error::RemainingInput) => {
+            // BCS is read front to back, so the shortest prefix that decodes completely is
+            // exactly the bytes the full decode consumed
+            let (len, value) = (1..contents.len())
+                .find_map(|len| bcs::from_bytes::<T>(&contents[..len]).ok().map(|value| (len, value)))
+                .ok_or_else(|| anyhow!("Failed to decode a prefix of the event contents"))?;
+            Ok((value, contents.len() - len))
+        }
+        result => Ok((result?, 0)),
+    }
+}
+
+/// Decode BCS event contents, tolerating trailing bytes. When an on-chain struct gains a field
+/// at the end, the known prefix still decodes; the extra bytes are logged and ignored instead of
+/// failing every event of that type.
+pub fn decode_event_contents<T: DeserializeOwned>(contents: &[u8], ctx: &EventContext<'_>) -> Result<T> {
+    let (value, trailing_bytes) = decode_known_prefix(contents)?;
+    if trailing_bytes > 0 {
+        tracing::warn!(
+            "Event {} in tx {} has {} trailing bytes beyond the known layout, ignoring them",
+            ctx.event.type_,
+            ctx.tx_digest,
+            trailing_bytes
+        );
+    }
+    Ok(value)
+}
+
+/// Decode a Cetus `SwapEvent`
+fn decode_swap_event(ctx: &EventContext<'_>) -> Result<IndexedEvent> {
+    let swap_data = decode_event_contents::<SwapEventData>(&ctx.event.contents, ctx)?;
+    tracing::info!(
+        "Swap event details: amount_in={}, amount_out={}",
+        swap_data.amount_in,
+        swap_data.amount_out
+    );
+
+    let (coin_type_a, coin_type_b) = pool_coin_types(ctx.transaction, swap_data.pool)
+        .unwrap_or_else(|| {
+            tracing::warn!("Could not resolve coin types for pool {} in tx {}", swap_data.pool, ctx.tx_digest);
+            (String::new(), String::new())
+        });
+
+    Ok(IndexedEvent::Swap(CetusSwapEvent {
+        id: ctx.id.clone(),
+        amount_in: swap_data.amount_in as i64,
+        amount_out: swap_data.amount_out as i64,
+        created_at: None,
+        pool_id: canonical_object_id(swap_data.pool),
+        timestamp_ms: ctx.timestamp_ms as i64,
+        checkpoint_seq: ctx.checkpoint_seq as i64,
+        tx_index: ctx.tx_index as i64,
+        event_seq: event_seq(ctx.checkpoint_seq, ctx.tx_index, ctx.event_index),
+        atob: swap_data.atob,
+        coin_type_a,
+        coin_type_b,
+        package_id: ctx.event.package_id.to_string(),
+        tx_digest: ctx.tx_digest.to_string(),
+        event_index: ctx.event_index as i64,
+        before_sqrt_price: swap_data.before_sqrt_price.to_string(),
+        after_sqrt_price: swap_data.after_sqrt_price.to_string(),
+        price_impact_bps: price_impact_bps(swap_data.before_sqrt_price, swap_data.after_sqrt_price),
+        partner: canonical_object_id(swap_data.partner),
+        fee_amount: swap_data.fee_amount as i64,
+        sender: ctx.transaction.transaction.sender_address().to_string(),
+    }))
+}
+
+/// Decode a Cetus `AddLiquidityEvent`
+fn decode_add_liquidity_event(ctx: &EventContext<'_>) -> Result<IndexedEvent> {
+    let data = decode_event_contents::<AddLiquidityEventData>(&ctx.event.contents, ctx)?;
+    tracing::info!(
+        "Add liquidity event details: liquidity={}, after_liquidity={}",
+        data.liquidity,
+        data.after_liquidity
+    );
+
+    Ok(IndexedEvent::AddLiquidity(CetusAddLiquidityEvent {
+        id: ctx.id.clone(),
+        liquidity: data.liquidity.to_string(),
+        after_liquidity: data.after_liquidity.to_string(),
+        created_at: None,
+        package_id: ctx.event.package_id.to_string(),
+        tx_digest: ctx.tx_digest.to_string(),
+        event_index: ctx.event_index as i64,
+        pool: canonical_object_id(data.pool),
+        position: canonical_object_id(data.position),
+        timestamp_ms: ctx.timestamp_ms as i64,
+        checkpoint_seq: ctx.checkpoint_seq as i64,
+        tx_index: ctx.tx_index as i64,
+        event_seq: event_seq(ctx.checkpoint_seq, ctx.tx_index, ctx.event_index),
+    }))
+}
+
+/// Decode a Cetus `RemoveLiquidityEvent`
+fn decode_remove_liquidity_event(ctx: &EventContext<'_>) -> Result<IndexedEvent> {
+    let data = decode_event_contents::<RemoveLiquidityEventData>(&ctx.event.contents, ctx)?;
+    tracing::info!(
+        "Remove liquidity event details: liquidity={}, after_liquidity={}",
+        data.liquidity,
+        data.after_liquidity
+    );
+
+    Ok(IndexedEvent::RemoveLiquidity(CetusRemoveLiquidityEvent {
+        id: ctx.id.clone(),
+        liquidity: data.liquidity.to_string(),
+        after_liquidity: data.after_liquidity.to_string(),
+        created_at: None,
+        package_id: ctx.event.package_id.to_string(),
+        tx_digest: ctx.tx_digest.to_string(),
+        event_index: ctx.event_index as i64,
+        pool: canonical_object_id(data.pool),
+        position: canonical_object_id(data.position),
+        timestamp_ms: ctx.timestamp_ms as i64,
+        checkpoint_seq: ctx.checkpoint_seq as i64,
+        tx_index: ctx.tx_index as i64,
+        event_seq: event_seq(ctx.checkpoint_seq, ctx.tx_index, ctx.event_index),
+    }))
+}
+
+/// Indexer that matches checkpoint events against a route registry.
+/// By default it is populated with the Cetus swap and liquidity routes.
 pub struct CetusIndexer {
-    swap_event_type: StructTag,
-    add_liquidity_event_type: StructTag,
-    remove_liquidity_event_type: StructTag,
+    registry: RouteRegistry,
+    /// Record a histogram of every event type seen, for discovering new events
+    record_event_types: bool,
+    id_scheme: IdScheme,
+    /// Prepended to the ids of events whose route has no prefix of its own
+    id_prefix: String,
+    /// Applied to every decoded event before it is added to the batch
+    enricher: Box<dyn EventEnricher>,
+    /// Only transactions sent by these addresses are indexed; empty indexes every sender
+    sender_allowlist: HashSet<SuiAddress>,
+    /// Drop swaps with a zero input or output amount instead of storing them
+    exclude_zero_amount_swaps: bool,
 }
 
 impl std::default::Default for CetusIndexer {
@@ -80,25 +341,136 @@ impl std::default::Default for CetusIndexer {
 impl CetusIndexer {
     /// Create a new indexer with custom event types
     pub fn new(swap_event_type: String, add_liquidity_event_type: String, remove_liquidity_event_type: String) -> Self {
-        let swap_event_type = StructTag::from_str(&swap_event_type).unwrap();
-        let add_liquidity_event_type = StructTag::from_str(&add_liquidity_event_type).unwrap();
-        let remove_liquidity_event_type = StructTag::from_str(&remove_liquidity_event_type).unwrap();
+        Self::try_new(&swap_event_type, &add_liquidity_event_type, &remove_liquidity_event_type).unwrap()
+    }
+
+    /// Create a new indexer with custom event types, failing on types that don't parse. Types whose
+    /// package address isn't written as a full 32-byte ObjectID are logged as a warning.
+    pub fn try_new(swap_event_type: &str, add_liquidity_event_type: &str, remove_liquidity_event_type: &str) -> Result<Self> {
+        Self::build(swap_event_type, add_liquidity_event_type, remove_liquidity_event_type, false)
+    }
+
+    /// Like `try_new`, but fails on event types whose package address isn't a full 32-byte ObjectID
+    pub fn try_new_strict(swap_event_type: &str, add_liquidity_event_type: &str, remove_liquidity_event_type: &str) -> Result<Self> {
+        Self::build(swap_event_type, add_liquidity_event_type, remove_liquidity_event_type, true)
+    }
+
+    fn build(swap_event_type: &str, add_liquidity_event_type: &str, remove_liquidity_event_type: &str, strict: bool) -> Result<Self> {
+        let parse = |event_type: &str| {
+            let struct_tag =
+                StructTag::from_str(event_type).map_err(|e| anyhow!("Invalid event type '{}': {}", event_type, e))?;
+            if let Err(e) = check_event_type_address(event_type) {
+                if strict {
+                    return Err(e);
+                }
+                tracing::warn!("{}", e);
+            }
+            Ok::<_, anyhow::Error>(struct_tag)
+        };
+        let mut registry = RouteRegistry::new();
+        registry.register(Route {
+            struct_tag: parse(swap_event_type)?,
+            table: EventTable::Swap,
+            decoder: Arc::new(decode_swap_event),
+            id_prefix: None,
+        });
+        registry.register(Route {
+            struct_tag: parse(add_liquidity_event_type)?,
+            table: EventTable::AddLiquidity,
+            decoder: Arc::new(decode_add_liquidity_event),
+            id_prefix: None,
+        });
+        registry.register(Route {
+            struct_tag: parse(remove_liquidity_event_type)?,
+            table: EventTable::RemoveLiquidity,
+            decoder: Arc::new(decode_remove_liquidity_event),
+            id_prefix: None,
+        });
+
+        Ok(Self::with_registry(registry))
+    }
+
+    /// Create an indexer for SWAP_EVENT_TYPE, ADD_LIQUIDITY_EVENT_TYPE and REMOVE_LIQUIDITY_EVENT_TYPE
+    /// when all three are set, otherwise for the Cetus mainnet event types. With
+    /// STRICT_EVENT_TYPES=true, overrides with a short or truncated package address are rejected.
+    pub fn from_env() -> Result<Self> {
+        let strict = std::env::var("STRICT_EVENT_TYPES").map(|value| value == "true").unwrap_or(false);
+        match (
+            std::env::var("SWAP_EVENT_TYPE"),
+            std::env::var("ADD_LIQUIDITY_EVENT_TYPE"),
+            std::env::var("REMOVE_LIQUIDITY_EVENT_TYPE"),
+        ) {
+            (Ok(swap_event_type), Ok(add_liquidity_event_type), Ok(remove_liquidity_event_type)) => {
+                Self::build(&swap_event_type, &add_liquidity_event_type, &remove_liquidity_event_type, strict)
+            }
+            _ => Ok(Self::default()),
+        }
+    }
 
+    /// Create an indexer from an arbitrary set of routes
+    pub fn with_registry(registry: RouteRegistry) -> Self {
         Self {
-            swap_event_type,
-            add_liquidity_event_type,
-            remove_liquidity_event_type,
+            registry,
+            record_event_types: false,
+            id_scheme: IdScheme::default(),
+            id_prefix: String::new(),
+            enricher: Box::new(NoopEnricher),
+            sender_allowlist: HashSet::new(),
+            exclude_zero_amount_swaps: false,
         }
     }
 
-    /// Process a checkpoint and extract Cetus events
-    pub fn process_checkpoint(
-        &self,
-        data: &CheckpointData,
-    ) -> (Vec<CetusSwapEvent>, Vec<CetusAddLiquidityEvent>, Vec<CetusRemoveLiquidityEvent>) {
-        let mut swap_events = Vec::new();
-        let mut add_liquidity_events = Vec::new();
-        let mut remove_liquidity_events = Vec::new();
+    /// Run an enricher on every decoded event, replacing the default no-op
+    pub fn with_enricher(mut self, enricher: impl EventEnricher + 'static) -> Self {
+        self.enricher = Box::new(enricher);
+        self
+    }
+
+    /// Select how primary keys are generated for new rows
+    pub fn with_id_scheme(mut self, id_scheme: IdScheme) -> Self {
+        self.id_scheme = id_scheme;
+        self
+    }
+
+    /// Prepend a protocol tag such as `cetus:` to generated ids, so ids stay unique when several
+    /// protocols share tables. Empty by default, which keeps existing ids unchanged.
+    pub fn with_id_prefix(mut self, id_prefix: impl Into<String>) -> Self {
+        self.id_prefix = id_prefix.into();
+        self
+    }
+
+    /// Only index transactions sent by these addresses, e.g. to build a dataset of one market
+    /// maker's activity. An empty allowlist indexes every sender.
+    pub fn with_sender_allowlist(mut self, sender_allowlist: HashSet<SuiAddress>) -> Self {
+        self.sender_allowlist = sender_allowlist;
+        self
+    }
+
+    /// Skip swaps with `amount_in` or `amount_out` of zero, which carry no volume or price
+    /// information and would otherwise count as swaps in the aggregates
+    pub fn with_zero_amount_swaps_excluded(mut self, exclude_zero_amount_swaps: bool) -> Self {
+        self.exclude_zero_amount_swaps = exclude_zero_amount_swaps;
+        self
+    }
+
+    /// Enable or disable recording of all observed event types
+    pub fn with_event_type_recording(mut self, record_event_types: bool) -> Self {
+        self.record_event_types = record_event_types;
+        self
+    }
+
+    /// Add or replace a route on an existing indexer
+    pub fn register(&mut self, route: Route) {
+        self.registry.register(route);
+    }
+
+    pub fn registry(&self) -> &RouteRegistry {
+        &self.registry
+    }
+
+    /// Process a checkpoint and extract the events matched by the registry
+    pub fn process_checkpoint(&self, data: &CheckpointData) -> IndexedEvents {
+        let mut events = IndexedEvents::default();
 
         // Print checkpoint info
         tracing::info!(
@@ -108,34 +480,42 @@ impl CetusIndexer {
         );
 
         // Iterate through all transactions in the checkpoint
-        for transaction in &data.transactions {
+        for (tx_index, transaction) in data.transactions.iter().enumerate() {
             self.process_transaction(
                 transaction,
-                &mut swap_events,
-                &mut add_liquidity_events,
-                &mut remove_liquidity_events,
+                tx_index,
+                data.checkpoint_summary.sequence_number,
+                data.checkpoint_summary.timestamp_ms,
+                &mut events,
             );
         }
 
         tracing::info!(
             "Found {} swap events, {} add liquidity events, and {} remove liquidity events in checkpoint #{}",
-            swap_events.len(),
-            add_liquidity_events.len(),
-            remove_liquidity_events.len(),
+            events.swap_events.len(),
+            events.add_liquidity_events.len(),
+            events.remove_liquidity_events.len(),
             data.checkpoint_summary.sequence_number
         );
 
-        (swap_events, add_liquidity_events, remove_liquidity_events)
+        events
     }
 
     fn process_transaction(
         &self,
         transaction: &CheckpointTransaction,
-        swap_events: &mut Vec<CetusSwapEvent>,
-        add_liquidity_events: &mut Vec<CetusAddLiquidityEvent>,
-        remove_liquidity_events: &mut Vec<CetusRemoveLiquidityEvent>,
+        tx_index: usize,
+        checkpoint_seq: u64,
+        timestamp_ms: u64,
+        indexed_events: &mut IndexedEvents,
     ) {
         let tx_digest = transaction.transaction.digest().to_string();
+        if !self.sender_allowlist.is_empty()
+            && !self.sender_allowlist.contains(&transaction.transaction.sender_address())
+        {
+            tracing::debug!("Skipping transaction {} from a sender not in the allowlist", tx_digest);
+            return;
+        }
         
         // Extract all events from the transaction, if any
         if let Some(events) = &transaction.events {
@@ -154,102 +534,77 @@ impl CetusIndexer {
                 tracing::debug!("Event {} raw content (hex): {}", i, hex_content);
             }
             
-            // Keep track of event counts for each type to create unique IDs
-            let mut swap_count = 0;
-            let mut add_liquidity_count = 0;
-            let mut remove_liquidity_count = 0;
+            // Keep track of event counts for each table to create unique IDs
+            let mut counts: HashMap<EventTable, usize> = HashMap::new();
             
-            for event in &events.data {
-                // Use the event struct type as comparison
-                let event_type = &event.type_;
-                
-                // Generate a base ID for this event
-                let base_id = format!("{}-{}", event.package_id, &tx_digest);
-
-                // Check if it's a swap event
-                if event_type == &self.swap_event_type {
-                    tracing::info!("Found Cetus swap event in tx: {}", tx_digest);
-                    match bcs::from_bytes::<SwapEventData>(&event.contents) {
-                        Ok(swap_data) => {
-                            tracing::info!(
-                                "Swap event details: amount_in={}, amount_out={}",
-                                swap_data.amount_in,
-                                swap_data.amount_out
-                            );
-                            // Create a unique ID with counter
-                            let unique_id = format!("{}-swap-{}", base_id, swap_count);
-                            swap_count += 1;
-                            
-                            swap_events.push(CetusSwapEvent {
-                                id: unique_id,
-                                amount_in: swap_data.amount_in as i64,
-                                amount_out: swap_data.amount_out as i64,
-                            });
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to deserialize swap event: {}", e);
-                            // In ra dữ liệu thô để debug
-                            let hex_content = hex::encode(&event.contents);
-                            tracing::error!("Raw content (hex): {}", hex_content);
-                        }
-                    }
+            for (event_index, event) in events.data.iter().enumerate() {
+                // Use the event struct type to look up the route
+                let route = self.registry.get(&event.type_);
+                if self.record_event_types {
+                    indexed_events.observe_event_type(
+                        event.type_.to_canonical_string(true),
+                        checkpoint_seq,
+                        route.is_some(),
+                    );
                 }
-                // Check if it's an add liquidity event
-                else if event_type == &self.add_liquidity_event_type {
-                    tracing::info!("Found Cetus add liquidity event in tx: {}", tx_digest);
-                    match bcs::from_bytes::<AddLiquidityEventData>(&event.contents) {
-                        Ok(data) => {
-                            tracing::info!(
-                                "Add liquidity event details: liquidity={}, after_liquidity={}",
-                                data.liquidity,
-                                data.after_liquidity
-                            );
-                            
-                            // Create a unique ID with counter
-                            let unique_id = format!("{}-add-{}", base_id, add_liquidity_count);
-                            add_liquidity_count += 1;
-                            
-                            add_liquidity_events.push(CetusAddLiquidityEvent {
-                                id: unique_id,
-                                liquidity: data.liquidity.to_string(),
-                                after_liquidity: data.after_liquidity.to_string(),
-                            });
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to deserialize add liquidity event: {}", e);
-                            // In ra dữ liệu thô để debug
-                            let hex_content = hex::encode(&event.contents);
-                            tracing::error!("Raw content (hex): {}", hex_content);
-                        }
+                let Some(route) = route else {
+                    continue;
+                };
+                tracing::info!("Found {:?} event in tx: {}", route.table, tx_digest);
+
+                // Create a unique ID, either composite with a per-table counter or an opaque UUID,
+                // behind the route's protocol tag
+                let count = counts.entry(route.table).or_insert(0);
+                let id_prefix = route.id_prefix.as_deref().unwrap_or(&self.id_prefix);
+                let unique_id = match self.id_scheme {
+                    IdScheme::Composite => format!(
+                        "{}{}-{}-{}-{}",
+                        id_prefix,
+                        event.package_id,
+                        &tx_digest,
+                        route.table.id_kind(),
+                        count
+                    ),
+                    IdScheme::Uuid => format!("{}{}", id_prefix, event_uuid(&tx_digest, event_index)),
+                };
+                *count += 1;
+
+                let ctx = EventContext {
+                    event,
+                    transaction,
+                    id: unique_id,
+                    tx_digest: &tx_digest,
+                    event_index,
+                    checkpoint_seq,
+                    tx_index,
+                    timestamp_ms,
+                };
+
+                match (route.decoder)(&ctx) {
+                    Ok(IndexedEvent::Swap(swap))
+                        if route.table == EventTable::Swap
+                            && self.exclude_zero_amount_swaps
+                            && (swap.amount_in == 0 || swap.amount_out == 0) =>
+                    {
+                        tracing::debug!("Skipping zero-amount swap {} in tx {}", swap.id, tx_digest);
                     }
-                }
-                // Check if it's a remove liquidity event
-                else if event_type == &self.remove_liquidity_event_type {
-                    tracing::info!("Found Cetus remove liquidity event in tx: {}", tx_digest);
-                    match bcs::from_bytes::<RemoveLiquidityEventData>(&event.contents) {
-                        Ok(data) => {
-                            tracing::info!(
-                                "Remove liquidity event details: liquidity={}, after_liquidity={}",
-                                data.liquidity,
-                                data.after_liquidity
-                            );
-                            
-                            // Create a unique ID with counter
-                            let unique_id = format!("{}-remove-{}", base_id, remove_liquidity_count);
-                            remove_liquidity_count += 1;
-                            
-                            remove_liquidity_events.push(CetusRemoveLiquidityEvent {
-                                id: unique_id,
-                                liquidity: data.liquidity.to_string(),
-                                after_liquidity: data.after_liquidity.to_string(),
-                            });
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to deserialize remove liquidity event: {}", e);
-                            // In ra dữ liệu thô để debug
-                            let hex_content = hex::encode(&event.contents);
-                            tracing::error!("Raw content (hex): {}", hex_content);
-                        }
+                    Ok(mut decoded) if decoded.table() == route.table => {
+                        self.enricher.enrich(&mut decoded, &ctx);
+                        indexed_events.push(decoded);
+                    }
+                    Ok(decoded) => {
+                        tracing::error!(
+                            "Decoder for {} produced a {:?} event, expected {:?}",
+                            route.struct_tag,
+                            decoded.table(),
+                            route.table
+                        );
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to deserialize {:?} event: {}", route.table, e);
+                        // In ra dữ liệu thô để debug
+                        let hex_content = hex::encode(&event.contents);
+                        tracing::error!("Raw content (hex): {}", hex_content);
                     }
                 }
             }
@@ -258,3 +613,272 @@ impl CetusIndexer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use move_core_types::identifier::Identifier;
+    use sui_types::event::Event;
+    use sui_types::test_checkpoint_data_builder::TestCheckpointDataBuilder;
+
+    use super::*;
+
+    const SUI_FRAMEWORK: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+    const POOL: &str = "0xcf994611fd4c48e277ce3ffd4d4364c914af2c3cbb05f7bf6facd371de688630";
+    const POSITION: &str = "0x5b2b1b4e9a4e5a7c8b9f4d1b2e3c4a5f6e7d8c9b0a1f2e3d4c5b6a7f8e9d0c1b";
+
+    /// An event of the given type emitted by the type's package
+    fn event(event_type: &str, contents: Vec<u8>) -> Event {
+        let type_ = StructTag::from_str(event_type).unwrap();
+        Event {
+            package_id: ObjectID::from(type_.address),
+            transaction_module: Identifier::new("pool").unwrap(),
+            sender: TestCheckpointDataBuilder::derive_address(0),
+            type_,
+            contents,
+        }
+    }
+
+    /// A checkpoint with one transaction emitting the given events
+    fn checkpoint_with_events(events: Vec<Event>) -> CheckpointData {
+        TestCheckpointDataBuilder::new(1)
+            .start_transaction(0)
+            .with_events(events)
+            .finish_transaction()
+            .build_checkpoint()
+    }
+
+    fn swap_data() -> SwapEventData {
+        SwapEventData {
+            atob: true,
+            pool: ObjectID::from_hex_literal(POOL).unwrap(),
+            partner: ObjectID::from_hex_literal(SUI_FRAMEWORK).unwrap(),
+            amount_in: 1_000_000_000,
+            amount_out: 3_250_000,
+            ref_amount: 0,
+            fee_amount: 2_500_000,
+            vault_a_amount: 50_000_000_000,
+            vault_b_amount: 160_000_000,
+            before_sqrt_price: 1_051_535_484_133_117_419,
+            after_sqrt_price: 1_051_503_997_125_011_832,
+            steps: 1,
+        }
+    }
+
+    fn add_liquidity_data() -> AddLiquidityEventData {
+        AddLiquidityEventData {
+            pool: ObjectID::from_hex_literal(POOL).unwrap(),
+            position: ObjectID::from_hex_literal(POSITION).unwrap(),
+            tick_lower: -443_580,
+            tick_upper: 443_580,
+            liquidity: 12_345_678_901,
+            after_liquidity: 98_765_432_109_876,
+            amount_a: 1_000_000,
+            amount_b: 2_000_000,
+        }
+    }
+
+    /// Index a checkpoint holding one event with the given contents
+    fn index_event(event_type: &str, contents: Vec<u8>) -> IndexedEvents {
+        CetusIndexer::default().process_checkpoint(&checkpoint_with_events(vec![event(event_type, contents)]))
+    }
+
+    #[test]
+    fn normalize_object_id_pads_short_ids() {
+        assert_eq!(normalize_object_id("0x2").unwrap(), SUI_FRAMEWORK);
+        assert_eq!(normalize_object_id("2").unwrap(), SUI_FRAMEWORK);
+        assert_eq!(normalize_object_id("0X2").unwrap(), SUI_FRAMEWORK);
+    }
+
+    #[test]
+    fn normalize_object_id_keeps_full_ids() {
+        assert_eq!(normalize_object_id(SUI_FRAMEWORK).unwrap(), SUI_FRAMEWORK);
+        assert_eq!(normalize_object_id(&SUI_FRAMEWORK[2..]).unwrap(), SUI_FRAMEWORK);
+    }
+
+    #[test]
+    fn normalize_object_id_lowercases_hex() {
+        assert_eq!(
+            normalize_object_id("0xABCdef").unwrap(),
+            "0x0000000000000000000000000000000000000000000000000000000000abcdef"
+        );
+        assert_eq!(
+            normalize_object_id("0x1EABED72C53FEB3805120A081DC15963C204DC8D091542592ABAF7A35689B2FB").unwrap(),
+            "0x1eabed72c53feb3805120a081dc15963c204dc8d091542592abaf7a35689b2fb"
+        );
+    }
+
+    #[test]
+    fn normalize_object_id_rejects_invalid_ids() {
+        assert!(normalize_object_id("").is_err());
+        assert!(normalize_object_id("0x").is_err());
+        assert!(normalize_object_id(&format!("0x{}", "1".repeat(65))).is_err());
+        assert!(normalize_object_id("0xg1").is_err());
+        assert!(normalize_object_id("0x 2").is_err());
+        assert!(normalize_object_id("0x0x2").is_err());
+    }
+
+    #[test]
+    fn sqrt_prices_keep_u128_max() {
+        let data = SwapEventData {
+            before_sqrt_price: u128::MAX,
+            after_sqrt_price: u128::MAX,
+            ..swap_data()
+        };
+        let events = index_event(CETUS_SWAP_EVENT_TYPE, bcs::to_bytes(&data).unwrap());
+        let swap = &events.swap_events[0];
+        assert_eq!(swap.before_sqrt_price, "340282366920938463463374607431768211455");
+        assert_eq!(swap.after_sqrt_price, "340282366920938463463374607431768211455");
+
+        let json = serde_json::to_value(swap).unwrap();
+        assert_eq!(json["before_sqrt_price"], "340282366920938463463374607431768211455");
+        let round_trip: CetusSwapEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip.before_sqrt_price.parse::<u128>().unwrap(), u128::MAX);
+        assert_eq!(round_trip.after_sqrt_price.parse::<u128>().unwrap(), u128::MAX);
+    }
+
+    #[test]
+    fn liquidity_keeps_u128_max() {
+        let data = AddLiquidityEventData {
+            liquidity: u128::MAX,
+            after_liquidity: u128::MAX,
+            ..add_liquidity_data()
+        };
+        let events = index_event(CETUS_ADD_LIQUIDITY_EVENT_TYPE, bcs::to_bytes(&data).unwrap());
+        let add_liquidity = &events.add_liquidity_events[0];
+        assert_eq!(add_liquidity.liquidity, "340282366920938463463374607431768211455");
+
+        let json = serde_json::to_value(add_liquidity).unwrap();
+        assert_eq!(json["liquidity"], "340282366920938463463374607431768211455");
+        assert_eq!(json["after_liquidity"], "340282366920938463463374607431768211455");
+        let round_trip: CetusAddLiquidityEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip.liquidity.parse::<u128>().unwrap(), u128::MAX);
+        assert_eq!(round_trip.after_liquidity.parse::<u128>().unwrap(), u128::MAX);
+    }
+
+    /// Fills in the coin types of a known pool, which the decoders can't resolve without the pool object
+    struct KnownPoolCoinTypes;
+
+    impl EventEnricher for KnownPoolCoinTypes {
+        fn enrich(&self, event: &mut IndexedEvent, ctx: &EventContext<'_>) {
+            if let IndexedEvent::Swap(swap) = event {
+                if swap.pool_id == POOL && swap.coin_type_a.is_empty() {
+                    swap.coin_type_a = "0x2::sui::SUI".to_string();
+                    swap.coin_type_b = format!("{}::usdc::USDC", ctx.event.package_id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn enricher_runs_on_decoded_events() {
+        let checkpoint = checkpoint_with_events(vec![event(CETUS_SWAP_EVENT_TYPE, bcs::to_bytes(&swap_data()).unwrap())]);
+
+        let events = CetusIndexer::default().process_checkpoint(&checkpoint);
+        assert_eq!(events.swap_events[0].coin_type_a, "");
+
+        let events = CetusIndexer::default()
+            .with_enricher(KnownPoolCoinTypes)
+            .process_checkpoint(&checkpoint);
+        let swap = &events.swap_events[0];
+        assert_eq!(swap.coin_type_a, "0x2::sui::SUI");
+        assert_eq!(swap.coin_type_b, format!("{}::usdc::USDC", swap.package_id));
+        // The decoded fields are left as they were
+        assert_eq!(swap.amount_in, 1_000_000_000);
+        assert_eq!(swap.pool_id, POOL);
+    }
+
+    #[test]
+    fn decode_known_prefix_reports_trailing_bytes() {
+        let mut contents = bcs::to_bytes(&swap_data()).unwrap();
+        let (_, trailing_bytes) = decode_known_prefix::<SwapEventData>(&contents).unwrap();
+        assert_eq!(trailing_bytes, 0);
+
+        // A field appended to the on-chain struct, e.g. a u64 and a bool
+        contents.extend_from_slice(&[7, 0, 0, 0, 0, 0, 0, 0, 1]);
+        let (decoded, trailing_bytes) = decode_known_prefix::<SwapEventData>(&contents).unwrap();
+        assert_eq!(trailing_bytes, 9);
+        let expected = swap_data();
+        assert_eq!(decoded.atob, expected.atob);
+        assert_eq!(decoded.pool, expected.pool);
+        assert_eq!(decoded.partner, expected.partner);
+        assert_eq!(decoded.amount_in, expected.amount_in);
+        assert_eq!(decoded.amount_out, expected.amount_out);
+        assert_eq!(decoded.ref_amount, expected.ref_amount);
+        assert_eq!(decoded.fee_amount, expected.fee_amount);
+        assert_eq!(decoded.vault_a_amount, expected.vault_a_amount);
+        assert_eq!(decoded.vault_b_amount, expected.vault_b_amount);
+        assert_eq!(decoded.before_sqrt_price, expected.before_sqrt_price);
+        assert_eq!(decoded.after_sqrt_price, expected.after_sqrt_price);
+        assert_eq!(decoded.steps, expected.steps);
+    }
+
+    #[test]
+    fn decode_known_prefix_rejects_truncated_contents() {
+        let contents = bcs::to_bytes(&swap_data()).unwrap();
+        assert!(decode_known_prefix::<SwapEventData>(&contents[..contents.len() - 1]).is_err());
+        assert!(decode_known_prefix::<SwapEventData>(&contents[..1]).is_err());
+        assert!(decode_known_prefix::<SwapEventData>(&[]).is_err());
+    }
+
+    #[test]
+    fn swaps_with_trailing_bytes_are_indexed() {
+        let mut contents = bcs::to_bytes(&swap_data()).unwrap();
+        contents.extend_from_slice(&[1, 2, 3]);
+        let events = index_event(CETUS_SWAP_EVENT_TYPE, contents);
+        assert_eq!(events.swap_events.len(), 1);
+        assert_eq!(events.swap_events[0].amount_out, 3_250_000);
+    }
+
+    // Golden BCS vectors belong here once real mainnet payloads are captured, each citing the
+    // transaction digest and event index it was copied from. Vectors encoded by hand or from the
+    // Rust structs would only check the decoders against themselves.
+
+    /// Index swaps with the given (amount_in, amount_out) pairs, with or without the zero-amount filter
+    fn index_swaps(amounts: &[(u64, u64)], exclude_zero_amount_swaps: bool) -> Vec<(i64, i64)> {
+        let events = amounts
+            .iter()
+            .map(|&(amount_in, amount_out)| {
+                let data = SwapEventData {
+                    amount_in,
+                    amount_out,
+                    ..swap_data()
+                };
+                event(CETUS_SWAP_EVENT_TYPE, bcs::to_bytes(&data).unwrap())
+            })
+            .collect();
+        CetusIndexer::default()
+            .with_zero_amount_swaps_excluded(exclude_zero_amount_swaps)
+            .process_checkpoint(&checkpoint_with_events(events))
+            .swap_events
+            .iter()
+            .map(|swap| (swap.amount_in, swap.amount_out))
+            .collect()
+    }
+
+    #[test]
+    fn zero_amount_swaps_are_kept_by_default() {
+        assert_eq!(
+            index_swaps(&[(0, 5), (5, 0), (0, 0), (5, 7)], false),
+            [(0, 5), (5, 0), (0, 0), (5, 7)]
+        );
+    }
+
+    #[test]
+    fn zero_amount_swaps_are_excluded_when_enabled() {
+        assert_eq!(index_swaps(&[(0, 5), (5, 0), (0, 0), (5, 7)], true), [(5, 7)]);
+    }
+
+    #[test]
+    fn excluded_swaps_keep_the_ids_of_later_swaps() {
+        // Composite ids count swaps in the transaction, whether or not they are stored
+        let events = CetusIndexer::default()
+            .with_zero_amount_swaps_excluded(true)
+            .process_checkpoint(&checkpoint_with_events(vec![
+                event(CETUS_SWAP_EVENT_TYPE, bcs::to_bytes(&SwapEventData { amount_in: 0, ..swap_data() }).unwrap()),
+                event(CETUS_SWAP_EVENT_TYPE, bcs::to_bytes(&swap_data()).unwrap()),
+            ]));
+        assert_eq!(events.swap_events.len(), 1);
+        assert!(events.swap_events[0].id.ends_with("-swap-1"), "{}", events.swap_events[0].id);
+        assert_eq!(events.swap_events[0].event_index, 1);
+    }
+}
@@ -1,15 +1,33 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod aggregator;
 pub mod api;
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse_sink;
+pub mod coin_metadata;
+pub mod dedup;
+pub mod dynamic;
+pub mod event_log;
+pub mod expected_pools;
+pub mod export;
 pub mod indexer;
+pub mod metrics;
 pub mod models;
+pub mod price;
+pub mod progress;
+pub mod registry;
+pub mod retry;
 pub mod schema;
+pub mod self_test;
+pub mod sink;
+pub mod webhook;
 
 use dotenvy::dotenv;
 use std::env;
 
 use diesel::{ConnectionError, ConnectionResult};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 use diesel_async::pooled_connection::bb8::Pool;
 use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use diesel_async::pooled_connection::ManagerConfig;
@@ -23,11 +41,21 @@ pub type PgConnectionPool =
 pub type PgPoolConnection<'a> =
     diesel_async::pooled_connection::bb8::PooledConnection<'a, AsyncPgConnection>;
 
+/// Migrations in `migrations/`, embedded so the binaries can check and apply them
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+// Default maximum age of a pooled connection; 0 disables the limit
+const DEFAULT_DB_MAX_LIFETIME_SECS: u64 = 30 * 60;
+
 pub async fn get_connection_pool() -> PgConnectionPool {
     dotenv().ok();
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    get_connection_pool_for_url(database_url).await
+}
 
+/// Build a pool for an explicit database, e.g. a secondary written alongside DATABASE_URL
+pub async fn get_connection_pool_for_url(database_url: String) -> PgConnectionPool {
     let mut config = ManagerConfig::default();
     config.custom_setup = Box::new(establish_connection);
 
@@ -36,14 +64,27 @@ pub async fn get_connection_pool() -> PgConnectionPool {
         config,
     );
 
+    // Recycle stale connections (e.g. after a Postgres failover) by pinging each connection
+    // before handing it out and by capping how long a connection may live
+    let test_on_check_out = env::var("DB_TEST_ON_CHECK_OUT")
+        .map(|value| value != "false")
+        .unwrap_or(true);
+    let max_lifetime = env::var("DB_MAX_LIFETIME_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>().expect("DB_MAX_LIFETIME_SECS must be a number of seconds"))
+        .unwrap_or(DEFAULT_DB_MAX_LIFETIME_SECS);
+
     Pool::builder()
         .connection_timeout(Duration::from_secs(30))
+        .test_on_check_out(test_on_check_out)
+        .max_lifetime((max_lifetime > 0).then(|| Duration::from_secs(max_lifetime)))
         .build(manager)
         .await
         .expect("Could not build Postgres DB connection pool")
 }
 
-fn establish_connection(config: &str) -> BoxFuture<ConnectionResult<AsyncPgConnection>> {
+/// Open a single connection with the same TLS setup as the pools, e.g. for running migrations
+pub fn establish_connection(config: &str) -> BoxFuture<ConnectionResult<AsyncPgConnection>> {
     let fut = async {
         // We first set up the way we want rustls to work.
         let rustls_config = rustls::ClientConfig::builder()
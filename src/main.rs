@@ -1,183 +1,749 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use diesel::{dsl::sql, ExpressionMethods};
-use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, RunQueryDsl};
+use clap::Parser;
 use dotenvy::dotenv;
 use mysten_service::metrics::start_basic_prometheus_server;
 use prometheus::Registry;
 use rustls;
 use std::env;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use sui_data_ingestion_core::{
-    DataIngestionMetrics, FileProgressStore, IndexerExecutor, ReaderOptions, Worker, WorkerPool,
+    DataIngestionMetrics, IndexerExecutor, ReaderOptions, Worker, WorkerPool,
 };
 use sui_types::full_checkpoint_content::CheckpointData;
+use telemetry_subscribers::{TelemetryConfig, TracingHandle};
 use tokio::sync::oneshot;
-use tracing::info;
+use tracing::{error, info, warn};
 
 use suins_indexer::{
-    get_connection_pool,
-    indexer::CetusIndexer,
-    models::{CetusAddLiquidityEvent, CetusRemoveLiquidityEvent, CetusSwapEvent},
-    schema::{cetus_add_liquidity_events, cetus_remove_liquidity_events, cetus_swap_events},
-    PgConnectionPool,
+    aggregator::{
+        parse_view_names, spawn_pool_stats_refresher, spawn_swap_partition_maintainer, spawn_view_refresher,
+        SwapPartitionRetention,
+    },
+    coin_metadata::CoinMetadataSink,
+    dedup::{save_filter, spawn_filter_saver, BloomFilter, DedupSink},
+    dynamic::ProtocolsConfig,
+    event_log::FileLogSink,
+    expected_pools::PoolCoinTypeValidator,
+    get_connection_pool, get_connection_pool_for_url, PgConnectionPool,
+    indexer::{parse_sender_allowlist, CetusIndexer, IdScheme},
+    metrics::{spawn_connection_pool_sampler, ConnectionPoolMetrics, IndexerMetrics},
+    models::ProcessedCheckpoint,
+    progress::{reconcile_progress, record_processed_checkpoint, AnyProgressStore, ProgressStoreKind},
+    registry::EventTable,
+    retry::{retry, RetryPolicy},
+    self_test,
+    sink::{
+        AdaptiveBatchSize, BatchingSink, BestEffortSink, ChainedSink, EventSink, FanoutSink, PgSink, SinkKind, SwapPartitionCache,
+        DEFAULT_COMMIT_ORDER,
+    },
+    webhook::WebhookSink,
 };
 
+/// Default key of the worker pool's watermark in the progress store
+const DEFAULT_TASK_NAME: &str = "cetus_indexing";
+
+/// Cetus events indexer. Configuration is read from the environment (and `.env`)
+#[derive(Parser)]
+struct Args {
+    /// Check the event type overrides, database connectivity and migrations, then exit
+    /// with a non-zero status if any check failed, without starting ingestion
+    #[arg(long)]
+    self_test: bool,
+}
+
 pub struct CetusIndexerWorker {
     indexer: CetusIndexer,
+    sink: Box<dyn EventSink>,
+    /// Checkpoints below this sequence number predate the protocol and are skipped without parsing
+    skip_checkpoints_below: u64,
+    metrics: IndexerMetrics,
+    /// Warn about checkpoints with more transactions than this, to spot outliers slowing the pipeline
+    large_checkpoint_threshold: Option<usize>,
+    /// Primary database, where every finished checkpoint is recorded in `processed_checkpoints`
     pg_pool: PgConnectionPool,
-}
-
-impl CetusIndexerWorker {
-    /// Inserts or updates Cetus events in the database
-    async fn commit_to_db(
-        &self,
-        swap_events: &[CetusSwapEvent],
-        add_liquidity_events: &[CetusAddLiquidityEvent],
-        remove_liquidity_events: &[CetusRemoveLiquidityEvent],
-    ) -> Result<()> {
-        if swap_events.is_empty() && add_liquidity_events.is_empty() && remove_liquidity_events.is_empty() {
-            return Ok(());
-        }
-
-        let mut connection = self.pg_pool.get().await.unwrap();
-
-        connection
-            .transaction::<_, anyhow::Error, _>(|conn| {
-                async move {
-                    if !swap_events.is_empty() {
-                        diesel::insert_into(cetus_swap_events::table)
-                            .values(swap_events)
-                            .on_conflict(cetus_swap_events::id)
-                            .do_update()
-                            .set((
-                                cetus_swap_events::amount_in.eq(sql("excluded.amount_in")),
-                                cetus_swap_events::amount_out.eq(sql("excluded.amount_out")),
-                            ))
-                            .execute(conn)
-                            .await
-                            .unwrap_or_else(|_| {
-                                panic!("Failed to process swap events: {:?}", swap_events)
-                            });
-                    }
-
-                    if !add_liquidity_events.is_empty() {
-                        diesel::insert_into(cetus_add_liquidity_events::table)
-                            .values(add_liquidity_events)
-                            .on_conflict(cetus_add_liquidity_events::id)
-                            .do_update()
-                            .set((
-                                cetus_add_liquidity_events::liquidity.eq(sql("excluded.liquidity")),
-                                cetus_add_liquidity_events::after_liquidity.eq(sql("excluded.after_liquidity")),
-                            ))
-                            .execute(conn)
-                            .await
-                            .unwrap_or_else(|_| {
-                                panic!("Failed to process add liquidity events: {:?}", add_liquidity_events)
-                            });
-                    }
-
-                    if !remove_liquidity_events.is_empty() {
-                        diesel::insert_into(cetus_remove_liquidity_events::table)
-                            .values(remove_liquidity_events)
-                            .on_conflict(cetus_remove_liquidity_events::id)
-                            .do_update()
-                            .set((
-                                cetus_remove_liquidity_events::liquidity.eq(sql("excluded.liquidity")),
-                                cetus_remove_liquidity_events::after_liquidity.eq(sql("excluded.after_liquidity")),
-                            ))
-                            .execute(conn)
-                            .await
-                            .unwrap_or_else(|_| {
-                                panic!("Failed to process remove liquidity events: {:?}", remove_liquidity_events)
-                            });
-                    }
-
-                    Ok(())
-                }
-                .scope_boxed()
-            })
-            .await
-    }
+    /// Batch size of the BatchingSink, fed with the lag of each checkpoint
+    adaptive_batch_size: Option<Arc<AdaptiveBatchSize>>,
+    /// When the reader last handed over a checkpoint, watched for stalled remote downloads
+    last_checkpoint_at: Arc<Mutex<Instant>>,
 }
 
 #[async_trait]
 impl Worker for CetusIndexerWorker {
     type Result = ();
+    #[tracing::instrument(
+        name = "process_checkpoint",
+        skip_all,
+        fields(
+            checkpoint_seq = checkpoint.checkpoint_summary.sequence_number,
+            transactions = checkpoint.transactions.len(),
+            swap_events,
+            add_liquidity_events,
+            remove_liquidity_events,
+        )
+    )]
     async fn process_checkpoint(&self, checkpoint: &CheckpointData) -> Result<()> {
+        let started = Instant::now();
+        *self.last_checkpoint_at.lock().unwrap() = started;
         let checkpoint_seq_number = checkpoint.checkpoint_summary.sequence_number;
-        let (swap_events, add_liquidity_events, remove_liquidity_events) = self.indexer.process_checkpoint(checkpoint);
+        // Returning Ok still advances the progress store past skipped checkpoints
+        if checkpoint_seq_number < self.skip_checkpoints_below {
+            return Ok(());
+        }
+
+        let transaction_count = checkpoint.transactions.len();
+        self.metrics
+            .checkpoint_transaction_count
+            .observe(transaction_count as f64);
+        if self
+            .large_checkpoint_threshold
+            .is_some_and(|threshold| transaction_count > threshold)
+        {
+            warn!(
+                "Checkpoint {} has {} transactions, above LARGE_CHECKPOINT_THRESHOLD",
+                checkpoint_seq_number, transaction_count
+            );
+        }
+
+        let events = self.indexer.process_checkpoint(checkpoint);
+        let span = tracing::Span::current();
+        span.record("swap_events", events.swap_events.len());
+        span.record("add_liquidity_events", events.add_liquidity_events.len());
+        span.record("remove_liquidity_events", events.remove_liquidity_events.len());
 
         // Log progress every 1000 checkpoints
         if checkpoint_seq_number % 1000 == 0 {
             info!("Checkpoint sequence number: {}", checkpoint_seq_number);
         }
-        self.commit_to_db(&swap_events, &add_liquidity_events, &remove_liquidity_events).await?;
+        if let Some(adaptive_batch_size) = &self.adaptive_batch_size {
+            adaptive_batch_size.observe_checkpoint(checkpoint.checkpoint_summary.timestamp_ms);
+        }
+        self.sink.write(&events).await?;
+        // Only recorded once the events are committed, so a recorded checkpoint is complete
+        let processed = ProcessedCheckpoint {
+            checkpoint_seq: checkpoint_seq_number as i64,
+            timestamp_ms: checkpoint.checkpoint_summary.timestamp_ms as i64,
+            processed_at: chrono::Utc::now(),
+            swap_events: events.swap_events.len() as i32,
+            add_liquidity_events: events.add_liquidity_events.len() as i32,
+            remove_liquidity_events: events.remove_liquidity_events.len() as i32,
+            dynamic_events: events.dynamic_events.len() as i32,
+            duration_ms: started.elapsed().as_millis() as i64,
+        };
+        record_processed_checkpoint(&self.pg_pool, &processed).await?;
         Ok(())
     }
 }
 
+/// Subscriber configuration from the environment. Setting OTEL_EXPORTER_OTLP_ENDPOINT exports
+/// spans (checkpoint processing and commits) to that OTLP collector; tracing export is off otherwise.
+fn telemetry_config() -> TelemetryConfig {
+    let mut config = TelemetryConfig::new().with_env();
+    if let Ok(endpoint) = env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        config.enable_otlp_tracing = true;
+        // telemetry-subscribers reads the collector address from OTLP_ENDPOINT
+        if env::var("OTLP_ENDPOINT").is_err() {
+            env::set_var("OTLP_ENDPOINT", endpoint);
+        }
+    }
+    config
+}
+
+/// Options for the remote checkpoint store, as `(key, value)` pairs in object_store's
+/// `AmazonS3ConfigKey` format (e.g. `("aws_access_key_id", "...")`). Only variables that are set
+/// are passed on; with no keys, object_store falls back to the instance profile for S3 buckets.
+fn remote_store_options_from_env() -> Vec<(String, String)> {
+    [
+        ("AWS_ACCESS_KEY_ID", "aws_access_key_id"),
+        ("AWS_SECRET_ACCESS_KEY", "aws_secret_access_key"),
+        ("AWS_SESSION_TOKEN", "aws_session_token"),
+        ("AWS_REGION", "aws_region"),
+        ("AWS_ENDPOINT_URL", "aws_endpoint"),
+    ]
+    .into_iter()
+    .filter_map(|(var, key)| env::var(var).ok().map(|value| (key.to_string(), value)))
+    .collect()
+}
+
+/// Log filter directives from the environment, preferring INDEXER_LOG_LEVEL over RUST_LOG
+fn log_level_from_env() -> Option<String> {
+    env::var("INDEXER_LOG_LEVEL").or_else(|_| env::var("RUST_LOG")).ok()
+}
+
+/// Re-read `.env` on SIGHUP and apply the log level to the running subscriber, so logging can be
+/// raised to debug (and back) without restarting the indexer
+#[cfg(unix)]
+fn spawn_log_level_reloader(handle: TracingHandle) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            warn!("Could not install SIGHUP handler, log level reloading disabled: {}", e);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            // Values in .env replace the ones loaded at startup
+            dotenvy::dotenv_override().ok();
+            let Some(directives) = log_level_from_env() else {
+                warn!("SIGHUP received but neither INDEXER_LOG_LEVEL nor RUST_LOG is set");
+                continue;
+            };
+            match handle.update_log(&directives) {
+                Ok(()) => info!("Log level reloaded: {}", directives),
+                Err(e) => error!("Invalid log level '{}': {}", directives, e),
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn spawn_log_level_reloader(_handle: TracingHandle) {}
+
+/// Warn when no checkpoint arrives for longer than the remote download timeout. The reader
+/// retries timed-out downloads internally and gives workers no hook to see them, so a stall is
+/// the only sign of a hung or slow store; each one is also counted in checkpoint_fetch_stalls_total
+fn spawn_checkpoint_stall_watchdog(last_checkpoint_at: Arc<Mutex<Instant>>, timeout: Duration, metrics: IndexerMetrics) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(timeout);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let idle = last_checkpoint_at.lock().unwrap().elapsed();
+            if idle > timeout {
+                metrics.checkpoint_fetch_stalls_total.inc();
+                warn!(
+                    "No checkpoint received for {}s, longer than the {}s remote download timeout; \
+                     checkpoint downloads may be timing out and being retried",
+                    idle.as_secs(),
+                    timeout.as_secs()
+                );
+            }
+        }
+    });
+}
+
+/// ClickHouse sink configured by CLICKHOUSE_URL, CLICKHOUSE_DATABASE, CLICKHOUSE_USER and CLICKHOUSE_PASSWORD
+#[cfg(feature = "clickhouse")]
+async fn clickhouse_sink() -> Result<Box<dyn EventSink>> {
+    let url = env::var("CLICKHOUSE_URL").map_err(|_| anyhow!("SINK=clickhouse requires CLICKHOUSE_URL"))?;
+    let database = env::var("CLICKHOUSE_DATABASE").unwrap_or("default".to_string());
+    let sink = suins_indexer::clickhouse_sink::ClickHouseSink::connect(
+        &url,
+        &database,
+        env::var("CLICKHOUSE_USER").ok(),
+        env::var("CLICKHOUSE_PASSWORD").ok(),
+    )
+    .await?;
+    Ok(Box::new(sink))
+}
+
+// SinkKind only parses "clickhouse" when the feature is enabled
+#[cfg(not(feature = "clickhouse"))]
+async fn clickhouse_sink() -> Result<Box<dyn EventSink>> {
+    unreachable!("SINK=clickhouse is rejected without the clickhouse feature")
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize the crypto provider for rustls
     rustls::crypto::ring::default_provider().install_default().expect("Failed to install default crypto provider");
     
-    let _guard = mysten_service::logging::init();
+    let args = Args::parse();
     dotenv().ok();
+    if args.self_test {
+        let passed = self_test::run().await;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+
+    let (_guard, tracing_handle) = telemetry_config().init();
+
+    // INDEXER_LOG_LEVEL takes precedence over the RUST_LOG filter the subscriber was built with
+    if let Ok(directives) = env::var("INDEXER_LOG_LEVEL") {
+        if let Err(e) = tracing_handle.update_log(&directives) {
+            error!("Invalid INDEXER_LOG_LEVEL '{}': {}", directives, e);
+        }
+    }
+    spawn_log_level_reloader(tracing_handle);
     
     // Load configuration from environment variables
     let remote_storage = env::var("REMOTE_STORAGE").ok();
-    let swap_event_type = env::var("SWAP_EVENT_TYPE").ok();
-    let add_liquidity_event_type = env::var("ADD_LIQUIDITY_EVENT_TYPE").ok();
-    let remove_liquidity_event_type = env::var("REMOVE_LIQUIDITY_EVENT_TYPE").ok();
+    // Number of upcoming checkpoints downloaded concurrently from remote storage
+    let checkpoint_prefetch = env::var("CHECKPOINT_PREFETCH")
+        .ok()
+        .map(|value| value.parse::<usize>())
+        .transpose()?;
+    // Timeout of each remote checkpoint download, so a hung request is retried instead of blocking
+    let remote_read_timeout_secs = env::var("REMOTE_READ_TIMEOUT_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?;
     let backfill_progress_file_path = env::var("BACKFILL_PROGRESS_FILE_PATH")
         .unwrap_or("./backfill_progress/backfill_progress".to_string());
     let checkpoints_dir = env::var("CHECKPOINTS_DIR").unwrap_or("./checkpoints".to_string());
+    let task_name = env::var("TASK_NAME").unwrap_or(DEFAULT_TASK_NAME.to_string());
+    let record_event_types = env::var("RECORD_EVENT_TYPES").map(|value| value == "true").unwrap_or(false);
+    let pipeline_commits = env::var("PIPELINE_COMMITS").map(|value| value == "true").unwrap_or(false);
+    let parallel_table_commits = env::var("PARALLEL_TABLE_COMMITS").map(|value| value == "true").unwrap_or(false);
+    let id_scheme = env::var("ID_SCHEME")
+        .ok()
+        .map(|value| value.parse::<IdScheme>())
+        .transpose()?
+        .unwrap_or_default();
+    let skip_checkpoints_below = env::var("SKIP_CHECKPOINTS_BELOW")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(0);
+    let large_checkpoint_threshold = env::var("LARGE_CHECKPOINT_THRESHOLD")
+        .ok()
+        .map(|value| value.parse::<usize>())
+        .transpose()?;
+    // Comma-separated table write order, e.g. "swap,add_liquidity,remove_liquidity"
+    let commit_order = match env::var("COMMIT_ORDER") {
+        Ok(value) => value
+            .split(',')
+            .map(|table| table.trim().parse::<EventTable>())
+            .collect::<Result<Vec<_>>>()?,
+        Err(_) => DEFAULT_COMMIT_ORDER.to_vec(),
+    };
+    let secondary_database_url = env::var("SECONDARY_DATABASE_URL").ok();
+    // Batching is enabled by BUFFER_MAX_EVENTS; the timer bounds how long events wait when it isn't reached
+    let buffer_max_events = env::var("BUFFER_MAX_EVENTS")
+        .ok()
+        .map(|value| value.parse::<usize>())
+        .transpose()?;
+    let buffer_flush_interval_ms = env::var("BUFFER_FLUSH_INTERVAL_MS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(1000);
+    // With BUFFER_ADAPTIVE=true, BUFFER_MAX_EVENTS applies while backfilling and shrinks to
+    // BUFFER_MIN_EVENTS as the indexer catches up with the chain
+    let buffer_adaptive_size = if env::var("BUFFER_ADAPTIVE").map(|value| value == "true").unwrap_or(false) {
+        let max_events = buffer_max_events.ok_or_else(|| anyhow!("BUFFER_ADAPTIVE requires BUFFER_MAX_EVENTS"))?;
+        let min_events = env::var("BUFFER_MIN_EVENTS")
+            .ok()
+            .map(|value| value.parse::<usize>())
+            .transpose()?
+            .unwrap_or(1);
+        let head_lag_secs = env::var("BUFFER_HEAD_LAG_SECS")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()?
+            .unwrap_or(10);
+        let backfill_lag_secs = env::var("BUFFER_BACKFILL_LAG_SECS")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()?
+            .unwrap_or(3600);
+        Some(Arc::new(AdaptiveBatchSize::new(
+            min_events,
+            max_events,
+            Duration::from_secs(head_lag_secs),
+            Duration::from_secs(backfill_lag_secs),
+        )))
+    } else {
+        None
+    };
+    // SINK=clickhouse also writes every event to ClickHouse (CLICKHOUSE_URL) for analytics
+    let sink_kind = env::var("SINK")
+        .ok()
+        .map(|value| value.parse::<SinkKind>())
+        .transpose()?
+        .unwrap_or_default();
+    // ClickHouse is best-effort by default: a failed or slow insert is logged and skipped after
+    // CLICKHOUSE_TIMEOUT_SECS so it never holds up Postgres. CLICKHOUSE_REQUIRED=true makes
+    // every batch wait for ClickHouse too.
+    let clickhouse_required = env::var("CLICKHOUSE_REQUIRED").map(|value| value == "true").unwrap_or(false);
+    let clickhouse_timeout_secs = env::var("CLICKHOUSE_TIMEOUT_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(30);
+    // Append-only NDJSON log of committed events for disaster recovery, see import_event_log
+    let event_log_dir = env::var("EVENT_LOG_DIR").ok().map(PathBuf::from);
+    let event_log_max_file_mb = env::var("EVENT_LOG_MAX_FILE_MB")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(256);
+    let event_log_max_file_secs = env::var("EVENT_LOG_MAX_FILE_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(24 * 60 * 60);
+    // Number of databases that must accept a checkpoint's events; defaults to all of them
+    let sink_quorum = env::var("SINK_QUORUM")
+        .ok()
+        .map(|value| value.parse::<usize>())
+        .transpose()?;
+    // Fullnode used to look up the metadata of newly seen coin types; lookups are off when unset
+    let sui_rpc_url = env::var("SUI_RPC_URL").ok();
+    // Receiver of committed events; payloads are signed when WEBHOOK_SECRET is set
+    let webhook_url = env::var("WEBHOOK_URL").ok();
+    let webhook_secret = env::var("WEBHOOK_SECRET").ok();
+    let webhook_queue_size = env::var("WEBHOOK_QUEUE_SIZE")
+        .ok()
+        .map(|value| value.parse::<usize>())
+        .transpose()?
+        .unwrap_or(100);
+    // Skip events already stored, according to a bloom filter persisted across restarts
+    let dedup_bloom = env::var("DEDUP_BLOOM").map(|value| value == "true").unwrap_or(false);
+    let dedup_bloom_path = PathBuf::from(
+        env::var("DEDUP_BLOOM_PATH").unwrap_or("./dedup_bloom/bloom.bin".to_string()),
+    );
+    let dedup_bloom_capacity = env::var("DEDUP_BLOOM_CAPACITY")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(10_000_000);
+    let dedup_bloom_fp_rate = env::var("DEDUP_BLOOM_FP_RATE")
+        .ok()
+        .map(|value| value.parse::<f64>())
+        .transpose()?
+        .unwrap_or(1e-6);
+    let dedup_bloom_save_secs = env::var("DEDUP_BLOOM_SAVE_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(60);
+    // Where checkpoint progress is persisted; postgres lets replicas share the watermark
+    let progress_store_kind = env::var("PROGRESS_STORE")
+        .ok()
+        .map(|value| value.parse::<ProgressStoreKind>())
+        .transpose()?
+        .unwrap_or_default();
+    // How often the pool_stats summary behind /api/volume?approx=true is rebuilt; 0 disables the refresher
+    let pool_stats_refresh_secs = env::var("POOL_STATS_REFRESH_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(60);
+    // How often the connection pool gauges are sampled; 0 disables the sampler
+    let db_pool_sample_secs = env::var("DB_POOL_SAMPLE_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(5);
+    // Curated pools whose swaps must have the listed coin types; mismatches are logged and counted
+    let expected_pools_path = env::var("EXPECTED_POOLS").ok();
+    // Extra events decoded from BCS layouts in a config file, stored in dynamic_events
+    let protocols_config = env::var("PROTOCOLS_CONFIG")
+        .ok()
+        .map(ProtocolsConfig::load)
+        .transpose()?;
+    // A progress watermark further than this ahead of the newest indexed event is reported at startup
+    let progress_max_gap = env::var("PROGRESS_MAX_GAP")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(10_000);
+    let progress_rewind = env::var("PROGRESS_REWIND_ON_GAP")
+        .map(|value| value == "true")
+        .unwrap_or(false);
+    // Monthly swap partitions kept ahead of the current month; 0 disables partition maintenance
+    let swap_partition_months_ahead = env::var("SWAP_PARTITION_MONTHS_AHEAD")
+        .ok()
+        .map(|value| value.parse::<u32>())
+        .transpose()?
+        .unwrap_or(2);
+    // Monthly swap partitions older than this many months before the current one are detached,
+    // and dropped with SWAP_PARTITION_DROP_EXPIRED; unset keeps every month
+    let swap_partition_retention = env::var("SWAP_PARTITION_RETENTION_MONTHS")
+        .ok()
+        .map(|value| value.parse::<u32>())
+        .transpose()?
+        .map(|months| SwapPartitionRetention {
+            months,
+            drop: env::var("SWAP_PARTITION_DROP_EXPIRED")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+        });
+    // Materialized views (e.g. daily volume rollups) to refresh on a schedule
+    let refresh_views = env::var("REFRESH_VIEWS")
+        .ok()
+        .map(|value| parse_view_names(&value))
+        .transpose()?
+        .unwrap_or_default();
+    let refresh_views_interval_secs = env::var("REFRESH_VIEWS_INTERVAL_SECS")
+        .ok()
+        .map(|value| value.parse::<u64>())
+        .transpose()?
+        .unwrap_or(300);
+    // Attempts (including the first) of database connections and commits before giving up
+    let retry_policy = RetryPolicy {
+        max_attempts: env::var("RETRY_MAX_ATTEMPTS")
+            .ok()
+            .map(|value| value.parse::<u32>())
+            .transpose()?
+            .unwrap_or(RetryPolicy::default().max_attempts),
+        initial_delay: env::var("RETRY_INITIAL_DELAY_MS")
+            .ok()
+            .map(|value| value.parse::<u64>())
+            .transpose()?
+            .map(Duration::from_millis)
+            .unwrap_or(RetryPolicy::default().initial_delay),
+    };
+    // Only transactions from these senders are indexed; unset or empty indexes everyone
+    let sender_allowlist = env::var("SENDER_ALLOWLIST")
+        .ok()
+        .map(|value| parse_sender_allowlist(&value))
+        .transpose()?
+        .unwrap_or_default();
+    let exclude_zero_amount_swaps = env::var("EXCLUDE_ZERO_AMOUNT_SWAPS")
+        .map(|value| value == "true")
+        .unwrap_or(false);
+    // Collision checks cost an extra query per table, so they default to debug builds only
+    let check_id_collisions = env::var("CHECK_ID_COLLISIONS")
+        .map(|value| value == "true")
+        .unwrap_or(cfg!(debug_assertions));
 
     println!("Starting Cetus indexer with checkpoints dir: {}", checkpoints_dir);
 
     // Setup exit signal, progress tracking, and metrics
     let (_exit_sender, exit_receiver) = oneshot::channel();
-    let progress_store = FileProgressStore::new(PathBuf::from(backfill_progress_file_path));
     let registry: Registry = start_basic_prometheus_server();
     mysten_metrics::init_metrics(&registry);
     let metrics = DataIngestionMetrics::new(&registry);
+    let indexer_metrics = IndexerMetrics::new(&registry);
+    let pg_pool = get_connection_pool().await;
+    // Wait for the database, e.g. when it starts alongside the indexer
+    retry("db_connect", &retry_policy, &indexer_metrics, || async {
+        pg_pool
+            .get()
+            .await
+            .map(drop)
+            .map_err(|e| anyhow!("Failed to get DB connection: {}", e))
+    })
+    .await?;
+    let mut progress_store = match progress_store_kind {
+        ProgressStoreKind::File => AnyProgressStore::file(PathBuf::from(backfill_progress_file_path)),
+        ProgressStoreKind::Postgres => AnyProgressStore::postgres(pg_pool.clone()),
+    };
+    let progress = reconcile_progress(&mut progress_store, &pg_pool, &task_name, progress_max_gap, progress_rewind).await?;
+    if db_pool_sample_secs > 0 {
+        spawn_connection_pool_sampler(
+            pg_pool.clone(),
+            "primary",
+            ConnectionPoolMetrics::new(&registry),
+            Duration::from_secs(db_pool_sample_secs),
+        );
+    }
+    if pool_stats_refresh_secs > 0 {
+        spawn_pool_stats_refresher(pg_pool.clone(), Duration::from_secs(pool_stats_refresh_secs));
+    }
+    // Swap partitions the primary sink knows to exist, forgotten when the maintainer expires some
+    let swap_partition_cache = SwapPartitionCache::default();
+    if swap_partition_months_ahead > 0 || swap_partition_retention.is_some() {
+        spawn_swap_partition_maintainer(
+            pg_pool.clone(),
+            swap_partition_months_ahead,
+            swap_partition_retention,
+            swap_partition_cache.clone(),
+        );
+    }
+    if !refresh_views.is_empty() && refresh_views_interval_secs > 0 {
+        spawn_view_refresher(pg_pool.clone(), refresh_views, Duration::from_secs(refresh_views_interval_secs));
+    }
     let mut executor = IndexerExecutor::new(progress_store, 1, metrics);
 
+    if !sender_allowlist.is_empty() {
+        info!("Only indexing transactions from {} allowlisted senders", sender_allowlist.len());
+    }
+
     // Initialize the Cetus indexer with event type configuration
-    let indexer_setup = if let (Some(swap_event_type), Some(add_liquidity_event_type), Some(remove_liquidity_event_type)) =
-        (swap_event_type.clone(), add_liquidity_event_type.clone(), remove_liquidity_event_type.clone())
-    {
-        CetusIndexer::new(swap_event_type, add_liquidity_event_type, remove_liquidity_event_type)
+    let mut indexer_setup = CetusIndexer::from_env()?
+        .with_event_type_recording(record_event_types)
+        .with_id_scheme(id_scheme)
+        .with_id_prefix(env::var("ID_PREFIX").unwrap_or_default())
+        .with_sender_allowlist(sender_allowlist)
+        .with_zero_amount_swaps_excluded(exclude_zero_amount_swaps);
+    if let Some(protocols_config) = &protocols_config {
+        for route in protocols_config.routes()? {
+            indexer_setup.register(route);
+        }
+        protocols_config.create_views(&pg_pool).await?;
+    }
+    if let Some(path) = &expected_pools_path {
+        let validator = PoolCoinTypeValidator::load(path, &indexer_metrics)?;
+        info!("Checking the coin types of swaps in {} expected pools", validator.pool_count());
+        indexer_setup = indexer_setup.with_enricher(validator);
+    }
+    // Log what is matched, to rule out a wrong event type when no events show up
+    for route in indexer_setup.registry().routes() {
+        info!("Matching {} events into {}", route.struct_tag.to_canonical_string(true), route.table.table_name());
+    }
+
+    // Write to the primary database, and also to a secondary one when configured
+    let primary_sink = PgSink::new("primary", pg_pool.clone(), indexer_metrics.clone())
+        .with_id_collision_checks(check_id_collisions)
+        .with_pipelined_commits(pipeline_commits)
+        .with_parallel_table_commits(parallel_table_commits)
+        .with_retry_policy(retry_policy.clone())
+        .with_commit_order(commit_order.clone())
+        .with_swap_partition_cache(swap_partition_cache.clone());
+    let primary_sink = match swap_partition_retention {
+        Some(retention) => primary_sink.with_swap_retention_months(retention.months),
+        None => primary_sink,
+    };
+    let sink: Box<dyn EventSink> = match secondary_database_url {
+        Some(secondary_database_url) => {
+            let secondary_sink = PgSink::new(
+                "secondary",
+                get_connection_pool_for_url(secondary_database_url).await,
+                indexer_metrics.clone(),
+            )
+            .with_pipelined_commits(pipeline_commits)
+            .with_parallel_table_commits(parallel_table_commits)
+            .with_retry_policy(retry_policy)
+            .with_commit_order(commit_order);
+            let fanout = FanoutSink::new(vec![Box::new(primary_sink), Box::new(secondary_sink)]);
+            let fanout = match sink_quorum {
+                Some(quorum) => fanout.with_quorum(quorum),
+                None => fanout,
+            };
+            Box::new(fanout)
+        }
+        None => Box::new(primary_sink),
+    };
+    // Optionally copy events to ClickHouse, alongside the Postgres databases
+    let sink: Box<dyn EventSink> = match sink_kind {
+        SinkKind::Postgres => sink,
+        SinkKind::ClickHouse => {
+            let clickhouse = clickhouse_sink().await?;
+            let clickhouse: Box<dyn EventSink> = if clickhouse_required {
+                clickhouse
+            } else {
+                Box::new(BestEffortSink::new(
+                    Arc::from(clickhouse),
+                    Duration::from_secs(clickhouse_timeout_secs),
+                    indexer_metrics.clone(),
+                ))
+            };
+            Box::new(FanoutSink::new(vec![sink, clickhouse]))
+        }
+    };
+    // Optionally append events to a local log next to the databases
+    let sink: Box<dyn EventSink> = match event_log_dir {
+        Some(event_log_dir) => {
+            let event_log = FileLogSink::new(
+                event_log_dir,
+                event_log_max_file_mb * 1024 * 1024,
+                Duration::from_secs(event_log_max_file_secs),
+            )?;
+            Box::new(FanoutSink::new(vec![sink, Box::new(event_log)]))
+        }
+        None => sink,
+    };
+    // Optionally look up the metadata of coin types in committed swaps
+    let sink: Box<dyn EventSink> = match sui_rpc_url {
+        Some(sui_rpc_url) => Box::new(ChainedSink::new(vec![
+            sink,
+            Box::new(CoinMetadataSink::new(pg_pool.clone(), sui_rpc_url)),
+        ])),
+        None => sink,
+    };
+    // Optionally push committed events to a webhook, after the databases accepted them
+    let sink: Box<dyn EventSink> = match webhook_url {
+        Some(webhook_url) => Box::new(ChainedSink::new(vec![
+            sink,
+            Box::new(WebhookSink::new(webhook_url, webhook_secret, webhook_queue_size)),
+        ])),
+        None => sink,
+    };
+    // Optionally buffer events across checkpoints and commit them together
+    let sink: Box<dyn EventSink> = match buffer_max_events {
+        Some(max_events) => {
+            let batching = BatchingSink::new(Arc::from(sink), max_events, Duration::from_millis(buffer_flush_interval_ms));
+            match &buffer_adaptive_size {
+                Some(adaptive_size) => Box::new(batching.with_adaptive_size(adaptive_size.clone())),
+                None => Box::new(batching),
+            }
+        }
+        None => sink,
+    };
+    // Optionally skip events that a previous run already stored
+    let dedup_filter = if dedup_bloom {
+        // After a rewind the filter holds ids of events the restored database no longer has
+        let saved = if progress.rewound {
+            warn!("Progress was rewound, starting the dedup bloom filter empty");
+            None
+        } else {
+            BloomFilter::load(&dedup_bloom_path, dedup_bloom_capacity, dedup_bloom_fp_rate)?
+        };
+        let filter = saved.unwrap_or_else(|| BloomFilter::new(dedup_bloom_capacity, dedup_bloom_fp_rate));
+        let filter = Arc::new(Mutex::new(filter));
+        spawn_filter_saver(filter.clone(), dedup_bloom_path.clone(), Duration::from_secs(dedup_bloom_save_secs.max(1)));
+        Some(filter)
     } else {
-        CetusIndexer::default()
+        None
+    };
+    let sink: Box<dyn EventSink> = match &dedup_filter {
+        Some(filter) => Box::new(DedupSink::new(sink, filter.clone(), progress.watermark)),
+        None => sink,
     };
 
     // Setup and register the worker pool
+    let last_checkpoint_at = Arc::new(Mutex::new(Instant::now()));
     let worker_pool = WorkerPool::new(
         CetusIndexerWorker {
-            pg_pool: get_connection_pool().await,
             indexer: indexer_setup,
+            sink,
+            skip_checkpoints_below,
+            metrics: indexer_metrics.clone(),
+            large_checkpoint_threshold,
+            pg_pool: pg_pool.clone(),
+            adaptive_batch_size: buffer_adaptive_size,
+            last_checkpoint_at: last_checkpoint_at.clone(),
         },
-        "cetus_indexing".to_string(), // Task name used as key in progress store
+        task_name,
         100,                          // Concurrency level
     );
     executor.register(worker_pool).await?;
 
+    let remote_store_options = remote_store_options_from_env();
+    let mut reader_options = ReaderOptions::default();
+    if let Some(checkpoint_prefetch) = checkpoint_prefetch {
+        reader_options.batch_size = checkpoint_prefetch.max(1);
+    }
+    if let Some(timeout_secs) = remote_read_timeout_secs {
+        // The remote store client is built with this as its request timeout
+        reader_options.timeout_secs = timeout_secs.max(1);
+        info!("Remote checkpoint downloads time out after {}s", reader_options.timeout_secs);
+    }
+    if remote_storage.is_some() {
+        spawn_checkpoint_stall_watchdog(
+            last_checkpoint_at,
+            Duration::from_secs(reader_options.timeout_secs),
+            indexer_metrics,
+        );
+    }
+
     // Start processing checkpoints
     executor
         .run(
             PathBuf::from(checkpoints_dir),
             remote_storage,
-            vec![],                       // AWS credentials (empty)
-            ReaderOptions::default(),
+            remote_store_options,
+            reader_options,
             exit_receiver,
         )
         .await?;
+
+    if let Some(filter) = &dedup_filter {
+        save_filter(filter, &dedup_bloom_path)?;
+    }
     
     drop(_guard);
     Ok(())
 }
+
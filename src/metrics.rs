@@ -0,0 +1,158 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use prometheus::{
+    exponential_buckets, register_histogram_vec_with_registry, register_histogram_with_registry,
+    register_int_counter_vec_with_registry, register_int_counter_with_registry, register_int_gauge_vec_with_registry,
+    Histogram, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Registry,
+};
+
+use crate::PgConnectionPool;
+
+/// Prometheus metrics reported by the Cetus indexer worker
+#[derive(Clone)]
+pub struct IndexerMetrics {
+    /// Upserts that hit an existing row whose content differs from the new event
+    pub id_collisions_total: IntCounterVec,
+    /// Time spent committing a checkpoint's events, labelled by commit mode
+    pub commit_duration_seconds: HistogramVec,
+    /// Number of transactions in each processed checkpoint
+    pub checkpoint_transaction_count: Histogram,
+    /// Attempts made by the retry helper, labelled by operation and outcome
+    pub retries_total: IntCounterVec,
+    /// Swaps in an expected pool whose coin types differ from the expected ones
+    pub pool_coin_type_mismatches_total: IntCounterVec,
+    /// Writes dropped by a best-effort sink, labelled by sink
+    pub best_effort_write_failures_total: IntCounterVec,
+    /// Checks that found no checkpoint received within the remote download timeout
+    pub checkpoint_fetch_stalls_total: IntCounter,
+    /// Swaps not written because their month is older than the swap partition retention
+    pub expired_swaps_skipped_total: IntCounter,
+}
+
+impl IndexerMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            id_collisions_total: register_int_counter_vec_with_registry!(
+                "id_collisions_total",
+                "Number of upserted events whose id matched an existing row with different content",
+                &["table"],
+                registry,
+            )
+            .unwrap(),
+            commit_duration_seconds: register_histogram_vec_with_registry!(
+                "commit_duration_seconds",
+                "Time spent committing a checkpoint's events to the database",
+                &["mode"],
+                registry,
+            )
+            .unwrap(),
+            checkpoint_transaction_count: register_histogram_with_registry!(
+                "checkpoint_transaction_count",
+                "Number of transactions in each processed checkpoint",
+                // 1 to 16384 transactions
+                exponential_buckets(1.0, 2.0, 15).unwrap(),
+                registry,
+            )
+            .unwrap(),
+            retries_total: register_int_counter_vec_with_registry!(
+                "retries_total",
+                "Number of attempts of retried operations, by whether the attempt succeeded",
+                &["operation", "outcome"],
+                registry,
+            )
+            .unwrap(),
+            pool_coin_type_mismatches_total: register_int_counter_vec_with_registry!(
+                "pool_coin_type_mismatches_total",
+                "Number of swaps whose coin types differ from the ones expected for their pool",
+                &["pool"],
+                registry,
+            )
+            .unwrap(),
+            best_effort_write_failures_total: register_int_counter_vec_with_registry!(
+                "best_effort_write_failures_total",
+                "Number of writes that failed or timed out in a sink that does not block the indexer",
+                &["sink"],
+                registry,
+            )
+            .unwrap(),
+            checkpoint_fetch_stalls_total: register_int_counter_with_registry!(
+                "checkpoint_fetch_stalls_total",
+                "Number of checks that found no checkpoint received within the remote download timeout",
+                registry,
+            )
+            .unwrap(),
+            expired_swaps_skipped_total: register_int_counter_with_registry!(
+                "expired_swaps_skipped_total",
+                "Number of swaps skipped because their month is older than SWAP_PARTITION_RETENTION_MONTHS",
+                registry,
+            )
+            .unwrap(),
+        }
+    }
+}
+
+/// Utilization of the database connection pools, labelled by pool name. Sampled on a timer by
+/// `spawn_connection_pool_sampler` rather than when scraped, so the gauges show steady-state
+/// load and not only whatever the pool looked like at scrape time.
+#[derive(Clone)]
+pub struct ConnectionPoolMetrics {
+    /// Open connections, idle or checked out
+    pub connections: IntGaugeVec,
+    /// Open connections waiting in the pool
+    pub idle_connections: IntGaugeVec,
+    /// Connections currently checked out
+    pub in_use_connections: IntGaugeVec,
+}
+
+impl ConnectionPoolMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            connections: register_int_gauge_vec_with_registry!(
+                "db_pool_connections",
+                "Number of open connections in the database connection pool",
+                &["pool"],
+                registry,
+            )
+            .unwrap(),
+            idle_connections: register_int_gauge_vec_with_registry!(
+                "db_pool_idle_connections",
+                "Number of idle connections in the database connection pool",
+                &["pool"],
+                registry,
+            )
+            .unwrap(),
+            in_use_connections: register_int_gauge_vec_with_registry!(
+                "db_pool_in_use_connections",
+                "Number of connections checked out of the database connection pool",
+                &["pool"],
+                registry,
+            )
+            .unwrap(),
+        }
+    }
+}
+
+/// Record the state of `pool` under `name` every `interval` in the background
+pub fn spawn_connection_pool_sampler(
+    pool: PgConnectionPool,
+    name: &str,
+    metrics: ConnectionPoolMetrics,
+    interval: Duration,
+) {
+    let connections = metrics.connections.with_label_values(&[name]);
+    let idle_connections = metrics.idle_connections.with_label_values(&[name]);
+    let in_use_connections = metrics.in_use_connections.with_label_values(&[name]);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let state = pool.state();
+            connections.set(state.connections as i64);
+            idle_connections.set(state.idle_connections as i64);
+            in_use_connections.set(state.connections.saturating_sub(state.idle_connections) as i64);
+        }
+    });
+}
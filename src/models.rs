@@ -1,33 +1,356 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::schema::{cetus_add_liquidity_events, cetus_remove_liquidity_events, cetus_swap_events};
+use crate::schema::{
+    cetus_add_liquidity_events, cetus_remove_liquidity_events, cetus_swap_events, coin_metadata, coin_prices,
+    dynamic_events, observed_event_types, pool_stats, processed_checkpoints,
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Serialize)]
+/// Serialize an i64 as a decimal string, since JSON clients lose precision on numbers above 2^53
+pub fn serialize_i64_as_string<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Read an i64 written by `serialize_i64_as_string`, also accepting a plain JSON number
+pub fn deserialize_i64_from_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(i64),
+    }
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(value) => value.parse().map_err(serde::de::Error::custom),
+        StringOrNumber::Number(value) => Ok(value),
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Debug, Serialize, Deserialize)]
 #[diesel(table_name = cetus_swap_events)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct CetusSwapEvent {
     pub id: String,
+    #[serde(serialize_with = "serialize_i64_as_string", deserialize_with = "deserialize_i64_from_string")]
     pub amount_in: i64,
+    #[serde(serialize_with = "serialize_i64_as_string", deserialize_with = "deserialize_i64_from_string")]
     pub amount_out: i64,
+    /// Set by the database on insert; leave as `None` when writing
+    pub created_at: Option<DateTime<Utc>>,
+    pub pool_id: String,
+    /// Timestamp of the checkpoint containing the swap
+    pub timestamp_ms: i64,
+    /// True when the swap sells coin A for coin B
+    pub atob: bool,
+    pub coin_type_a: String,
+    pub coin_type_b: String,
+    pub package_id: String,
+    pub tx_digest: String,
+    /// Position of the event within its transaction's events
+    pub event_index: i64,
+    /// Pool sqrt price (Q64.64) before the swap, as a u128 string
+    pub before_sqrt_price: String,
+    /// Pool sqrt price (Q64.64) after the swap, as a u128 string
+    pub after_sqrt_price: String,
+    /// `|after_sqrt_price - before_sqrt_price| / before_sqrt_price`, in basis points
+    pub price_impact_bps: i64,
+    /// Referral partner object credited with the swap
+    pub partner: String,
+    #[serde(serialize_with = "serialize_i64_as_string", deserialize_with = "deserialize_i64_from_string")]
+    pub fee_amount: i64,
+    /// Sequence number of the checkpoint containing the swap
+    pub checkpoint_seq: i64,
+    /// Position of the transaction within its checkpoint
+    pub tx_index: i64,
+    /// Total order over all events: `(checkpoint_seq << 24) | (tx_index << 10) | event_index`
+    pub event_seq: i64,
+    /// Sender of the transaction that emitted the swap
+    pub sender: String,
 }
 
+/// USD reference price of a coin, maintained outside the indexer
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Serialize)]
+#[diesel(table_name = coin_prices)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CoinPrice {
+    pub coin_type: String,
+    /// Price of one whole token in USD
+    pub usd_price: f64,
+    pub decimals: i16,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// On-chain metadata of a coin, fetched from a fullnode when the coin type is first seen
+#[derive(Queryable, Selectable, Insertable, Debug, Serialize)]
+#[diesel(table_name = coin_metadata)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CoinMetadata {
+    pub coin_type: String,
+    pub decimals: i16,
+    pub symbol: String,
+    pub name: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// A checkpoint whose events were all committed, with what it contained and how long it took
+#[derive(Queryable, Selectable, Insertable, Debug, Serialize)]
+#[diesel(table_name = processed_checkpoints)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ProcessedCheckpoint {
+    pub checkpoint_seq: i64,
+    pub timestamp_ms: i64,
+    pub processed_at: DateTime<Utc>,
+    pub swap_events: i32,
+    pub add_liquidity_events: i32,
+    pub remove_liquidity_events: i32,
+    pub dynamic_events: i32,
+    /// Time from receiving the checkpoint to committing its events, including any batching wait
+    pub duration_ms: i64,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Debug, Serialize, Deserialize)]
 #[diesel(table_name = cetus_add_liquidity_events)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct CetusAddLiquidityEvent {
     pub id: String,
     pub liquidity: String,
     pub after_liquidity: String,
+    /// Set by the database on insert; leave as `None` when writing
+    pub created_at: Option<DateTime<Utc>>,
+    pub package_id: String,
+    pub tx_digest: String,
+    /// Position of the event within its transaction's events
+    pub event_index: i64,
+    pub pool: String,
+    pub position: String,
+    /// Timestamp of the checkpoint containing the event
+    pub timestamp_ms: i64,
+    /// Sequence number of the checkpoint containing the event
+    pub checkpoint_seq: i64,
+    /// Position of the transaction within its checkpoint
+    pub tx_index: i64,
+    /// Total order over all events: `(checkpoint_seq << 24) | (tx_index << 10) | event_index`
+    pub event_seq: i64,
 }
 
-#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Serialize)]
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Debug, Serialize, Deserialize)]
 #[diesel(table_name = cetus_remove_liquidity_events)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct CetusRemoveLiquidityEvent {
     pub id: String,
     pub liquidity: String,
     pub after_liquidity: String,
+    /// Set by the database on insert; leave as `None` when writing
+    pub created_at: Option<DateTime<Utc>>,
+    pub package_id: String,
+    pub tx_digest: String,
+    /// Position of the event within its transaction's events
+    pub event_index: i64,
+    pub pool: String,
+    pub position: String,
+    /// Timestamp of the checkpoint containing the event
+    pub timestamp_ms: i64,
+    /// Sequence number of the checkpoint containing the event
+    pub checkpoint_seq: i64,
+    /// Position of the transaction within its checkpoint
+    pub tx_index: i64,
+    /// Total order over all events: `(checkpoint_seq << 24) | (tx_index << 10) | event_index`
+    pub event_seq: i64,
+}
+
+/// Event decoded from a runtime layout in the protocols config
+#[derive(Queryable, Selectable, Insertable, Clone, Debug, Serialize, Deserialize)]
+#[diesel(table_name = dynamic_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DynamicEvent {
+    pub id: String,
+    /// Name of the layout that decoded the event
+    pub event_name: String,
+    pub event_type: String,
+    pub package_id: String,
+    pub tx_digest: String,
+    /// Position of the event within its transaction's events
+    pub event_index: i64,
+    /// Timestamp of the checkpoint containing the event
+    pub timestamp_ms: i64,
+    pub checkpoint_seq: i64,
+    pub tx_index: i64,
+    /// Total order over all events: `(checkpoint_seq << 24) | (tx_index << 10) | event_index`
+    pub event_seq: i64,
+    /// Decoded fields, keyed by the names in the layout
+    pub data: serde_json::Value,
+    /// Set by the database on insert; leave as `None` when writing
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Number of times an event type was seen, recorded in debug mode
+#[derive(Queryable, Selectable, Insertable, Clone, Debug, Serialize)]
+#[diesel(table_name = observed_event_types)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ObservedEventType {
+    pub event_type: String,
+    /// Whether a registered route matched this type
+    pub matched: bool,
+    pub count: i64,
+    /// Set by the database on write; leave as `None` when writing
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
+/// Swap totals of a pool, refreshed periodically from `cetus_swap_events`
+#[derive(Queryable, Selectable, Clone, Debug, Serialize)]
+#[diesel(table_name = pool_stats)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PoolStats {
+    pub pool_id: String,
+    /// Serialized as a decimal string, since the total can exceed i64
+    pub total_volume_in: BigDecimal,
+    pub total_volume_out: BigDecimal,
+    pub swap_count: i64,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// Common accessors for event rows, used to compare an incoming event with a stored one
+pub trait EventRecord {
+    fn id(&self) -> &str;
+
+    /// Whether both rows carry the same event data, ignoring database-managed columns
+    fn same_content(&self, other: &Self) -> bool;
+}
+
+impl EventRecord for CetusSwapEvent {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn same_content(&self, other: &Self) -> bool {
+        self.amount_in == other.amount_in
+            && self.amount_out == other.amount_out
+            && self.pool_id == other.pool_id
+            && self.timestamp_ms == other.timestamp_ms
+            && self.atob == other.atob
+            && self.coin_type_a == other.coin_type_a
+            && self.coin_type_b == other.coin_type_b
+            && self.package_id == other.package_id
+            && self.tx_digest == other.tx_digest
+            && self.event_index == other.event_index
+            && self.before_sqrt_price == other.before_sqrt_price
+            && self.after_sqrt_price == other.after_sqrt_price
+            && self.price_impact_bps == other.price_impact_bps
+            && self.partner == other.partner
+            && self.fee_amount == other.fee_amount
+            && self.checkpoint_seq == other.checkpoint_seq
+            && self.tx_index == other.tx_index
+            && self.event_seq == other.event_seq
+            && self.sender == other.sender
+    }
+}
+
+impl EventRecord for CetusAddLiquidityEvent {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn same_content(&self, other: &Self) -> bool {
+        self.liquidity == other.liquidity
+            && self.after_liquidity == other.after_liquidity
+            && self.package_id == other.package_id
+            && self.tx_digest == other.tx_digest
+            && self.event_index == other.event_index
+            && self.pool == other.pool
+            && self.position == other.position
+            && self.timestamp_ms == other.timestamp_ms
+            && self.checkpoint_seq == other.checkpoint_seq
+            && self.tx_index == other.tx_index
+            && self.event_seq == other.event_seq
+    }
+}
+
+impl EventRecord for CetusRemoveLiquidityEvent {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn same_content(&self, other: &Self) -> bool {
+        self.liquidity == other.liquidity
+            && self.after_liquidity == other.after_liquidity
+            && self.package_id == other.package_id
+            && self.tx_digest == other.tx_digest
+            && self.event_index == other.event_index
+            && self.pool == other.pool
+            && self.position == other.position
+            && self.timestamp_ms == other.timestamp_ms
+            && self.checkpoint_seq == other.checkpoint_seq
+            && self.tx_index == other.tx_index
+            && self.event_seq == other.event_seq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2^53 + 1, the first integer a JSON number parsed as an f64 can't represent
+    const ABOVE_2_53: i64 = 9_007_199_254_740_993;
+
+    fn swap() -> CetusSwapEvent {
+        CetusSwapEvent {
+            id: "swap-0".to_string(),
+            amount_in: ABOVE_2_53,
+            amount_out: 42,
+            created_at: None,
+            pool_id: "0x1".to_string(),
+            timestamp_ms: 1_700_000_000_000,
+            atob: true,
+            coin_type_a: "0x2::sui::SUI".to_string(),
+            coin_type_b: "0x3::usdc::USDC".to_string(),
+            package_id: "0x4".to_string(),
+            tx_digest: "digest".to_string(),
+            event_index: 0,
+            before_sqrt_price: "1".to_string(),
+            after_sqrt_price: "2".to_string(),
+            price_impact_bps: 10_000,
+            partner: "0x5".to_string(),
+            fee_amount: i64::MAX,
+            checkpoint_seq: 7,
+            tx_index: 0,
+            event_seq: 117_440_512,
+            sender: "0x6".to_string(),
+        }
+    }
+
+    #[test]
+    fn amounts_serialize_as_exact_strings() {
+        let json = serde_json::to_string(&swap()).unwrap();
+        assert!(json.contains(r#""amount_in":"9007199254740993""#), "{}", json);
+        assert!(json.contains(r#""amount_out":"42""#), "{}", json);
+        assert!(json.contains(r#""fee_amount":"9223372036854775807""#), "{}", json);
+    }
+
+    #[test]
+    fn amounts_round_trip() {
+        let json = serde_json::to_string(&swap()).unwrap();
+        let round_trip: CetusSwapEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip.amount_in, ABOVE_2_53);
+        assert_eq!(round_trip.amount_out, 42);
+        assert_eq!(round_trip.fee_amount, i64::MAX);
+        assert!(round_trip.same_content(&swap()));
+    }
+
+    #[test]
+    fn amounts_deserialize_from_numbers() {
+        let mut json = serde_json::to_value(swap()).unwrap();
+        json["amount_in"] = serde_json::json!(ABOVE_2_53);
+        let swap: CetusSwapEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(swap.amount_in, ABOVE_2_53);
+    }
+
+    #[test]
+    fn amounts_reject_non_integer_strings() {
+        let mut json = serde_json::to_value(swap()).unwrap();
+        json["amount_in"] = serde_json::json!("1.5");
+        assert!(serde_json::from_value::<CetusSwapEvent>(json).is_err());
+    }
 }
@@ -0,0 +1,34 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pool prices derived from swap sqrt prices
+
+/// Price of coin A in coin B, in raw (undecimalized) units, from a Q64.64 sqrt price
+pub fn sqrt_price_to_price(sqrt_price: u128) -> f64 {
+    let sqrt_price = sqrt_price as f64 / 2f64.powi(64);
+    sqrt_price * sqrt_price
+}
+
+/// Time-weighted average of a price series over `[start_ms, end_ms]`. `points` are
+/// `(timestamp_ms, price)` in time order; each price is weighted by the time until the next
+/// point (or `end_ms` for the last one), clipped to the window, so a point before `start_ms`
+/// carries the price in effect when the window opens. Returns the latest price when no time
+/// elapses between the points and `None` without points.
+pub fn time_weighted_average(points: &[(i64, f64)], start_ms: i64, end_ms: i64) -> Option<f64> {
+    let mut weighted_sum = 0.0;
+    let mut total_weight = 0.0;
+    for (index, (timestamp_ms, price)) in points.iter().enumerate() {
+        let from = (*timestamp_ms).max(start_ms);
+        let until = points.get(index + 1).map_or(end_ms, |(next, _)| *next).min(end_ms);
+        if until > from {
+            let weight = (until - from) as f64;
+            weighted_sum += price * weight;
+            total_weight += weight;
+        }
+    }
+    if total_weight > 0.0 {
+        Some(weighted_sum / total_weight)
+    } else {
+        points.last().map(|(_, price)| *price)
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use diesel::sql_types::{BigInt, Nullable};
+use diesel::{dsl::sql, ExpressionMethods, OptionalExtension, QueryDsl, QueryableByName};
+use diesel_async::RunQueryDsl;
+use sui_data_ingestion_core::{FileProgressStore, ProgressStore};
+use sui_types::messages_checkpoint::CheckpointSequenceNumber;
+use tracing::{error, info, warn};
+
+use crate::models::ProcessedCheckpoint;
+use crate::schema::{processed_checkpoints, progress};
+use crate::PgConnectionPool;
+
+/// Where the executor keeps its per-task checkpoint watermark
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProgressStoreKind {
+    /// Local file, see BACKFILL_PROGRESS_FILE_PATH
+    #[default]
+    File,
+    /// `progress` table, shared by every replica using the same database
+    Postgres,
+}
+
+impl FromStr for ProgressStoreKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "file" => Ok(ProgressStoreKind::File),
+            "postgres" => Ok(ProgressStoreKind::Postgres),
+            _ => Err(anyhow!("Invalid PROGRESS_STORE '{}', expected 'file' or 'postgres'", s)),
+        }
+    }
+}
+
+/// Progress store persisting watermarks in the `progress` table
+pub struct PgProgressStore {
+    pg_pool: PgConnectionPool,
+}
+
+impl PgProgressStore {
+    pub fn new(pg_pool: PgConnectionPool) -> Self {
+        Self { pg_pool }
+    }
+}
+
+#[async_trait]
+impl ProgressStore for PgProgressStore {
+    async fn load(&mut self, task_name: String) -> Result<CheckpointSequenceNumber> {
+        let mut conn = self.pg_pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+        let checkpoint_number = progress::table
+            .filter(progress::task_name.eq(task_name))
+            .select(progress::checkpoint_number)
+            .first::<i64>(&mut conn)
+            .await
+            .optional()?;
+        // A task that never saved progress starts from the beginning
+        Ok(checkpoint_number.unwrap_or(0) as CheckpointSequenceNumber)
+    }
+
+    async fn save(&mut self, task_name: String, checkpoint_number: CheckpointSequenceNumber) -> Result<()> {
+        let mut conn = self.pg_pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+        diesel::insert_into(progress::table)
+            .values((
+                progress::task_name.eq(task_name),
+                progress::checkpoint_number.eq(checkpoint_number as i64),
+            ))
+            .on_conflict(progress::task_name)
+            .do_update()
+            .set((
+                progress::checkpoint_number.eq(sql("excluded.checkpoint_number")),
+                progress::updated_at.eq(sql("now()")),
+            ))
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Progress store selected at startup through PROGRESS_STORE
+pub enum AnyProgressStore {
+    File(FileProgressStore),
+    Postgres(PgProgressStore),
+}
+
+impl AnyProgressStore {
+    pub fn file(path: PathBuf) -> Self {
+        AnyProgressStore::File(FileProgressStore::new(path))
+    }
+
+    pub fn postgres(pg_pool: PgConnectionPool) -> Self {
+        AnyProgressStore::Postgres(PgProgressStore::new(pg_pool))
+    }
+}
+
+#[async_trait]
+impl ProgressStore for AnyProgressStore {
+    async fn load(&mut self, task_name: String) -> Result<CheckpointSequenceNumber> {
+        match self {
+            AnyProgressStore::File(store) => store.load(task_name).await,
+            AnyProgressStore::Postgres(store) => store.load(task_name).await,
+        }
+    }
+
+    async fn save(&mut self, task_name: String, checkpoint_number: CheckpointSequenceNumber) -> Result<()> {
+        match self {
+            AnyProgressStore::File(store) => store.save(task_name, checkpoint_number).await,
+            AnyProgressStore::Postgres(store) => store.save(task_name, checkpoint_number).await,
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct MaxCheckpoint {
+    #[diesel(sql_type = Nullable<BigInt>)]
+    max_checkpoint: Option<i64>,
+}
+
+/// Highest checkpoint with an indexed Cetus event, or `None` when the tables are empty
+pub async fn max_indexed_checkpoint(pg_pool: &PgConnectionPool) -> Result<Option<u64>> {
+    let mut conn = pg_pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+    let row = diesel::sql_query(
+        r#"
+        SELECT GREATEST(
+            (SELECT MAX(checkpoint_seq) FROM cetus_swap_events),
+            (SELECT MAX(checkpoint_seq) FROM cetus_add_liquidity_events),
+            (SELECT MAX(checkpoint_seq) FROM cetus_remove_liquidity_events)
+        ) AS max_checkpoint
+        "#,
+    )
+    .get_result::<MaxCheckpoint>(&mut conn)
+    .await?;
+    // Rows indexed before checkpoint_seq was stored have 0, which says nothing about progress
+    Ok(row.max_checkpoint.filter(|seq| *seq > 0).map(|seq| seq as u64))
+}
+
+/// The watermark ingestion starts from, as left by `reconcile_progress`
+pub struct ReconciledProgress {
+    /// First checkpoint to process; every checkpoint below it was committed
+    pub watermark: u64,
+    /// Whether the watermark was moved back to the newest indexed checkpoint
+    pub rewound: bool,
+}
+
+/// Compare the progress watermark with the newest indexed event before ingestion starts.
+///
+/// The watermark is normally a little ahead of the newest event, since recent checkpoints may
+/// contain no Cetus events. A gap above `max_gap` checkpoints suggests the database was restored
+/// to an earlier state than the progress store, leaving the events in between missing. That is
+/// logged as an error and, when `rewind` is set, the watermark is moved back to the newest
+/// indexed checkpoint so the gap is re-indexed (upserts make the overlap harmless).
+pub async fn reconcile_progress(
+    progress_store: &mut AnyProgressStore,
+    pg_pool: &PgConnectionPool,
+    task_name: &str,
+    max_gap: u64,
+    rewind: bool,
+) -> Result<ReconciledProgress> {
+    let watermark = progress_store.load(task_name.to_string()).await?;
+    let unchanged = ReconciledProgress {
+        watermark,
+        rewound: false,
+    };
+    let Some(db_max) = max_indexed_checkpoint(pg_pool).await? else {
+        info!("No indexed events with a checkpoint yet, starting {} from checkpoint {}", task_name, watermark);
+        return Ok(unchanged);
+    };
+
+    if watermark <= db_max.saturating_add(max_gap) {
+        info!(
+            "Progress watermark {} for {} is consistent with the newest indexed checkpoint {}",
+            watermark, task_name, db_max
+        );
+        return Ok(unchanged);
+    }
+
+    error!(
+        "Progress watermark {} for {} is {} checkpoints ahead of the newest indexed checkpoint {}; \
+         was the database restored from an older backup? Events in between may be missing",
+        watermark,
+        task_name,
+        watermark - db_max,
+        db_max
+    );
+    if rewind {
+        warn!("Rewinding progress for {} from {} to {}", task_name, watermark, db_max);
+        progress_store.save(task_name.to_string(), db_max).await?;
+        return Ok(ReconciledProgress {
+            watermark: db_max,
+            rewound: true,
+        });
+    }
+    Ok(unchanged)
+}
+
+/// Record that every event of a checkpoint was committed. Checkpoints without events are
+/// recorded too, so `/api/admin/gaps` can tell skipped checkpoints from quiet ones. A
+/// reprocessed checkpoint replaces its earlier row.
+pub async fn record_processed_checkpoint(pg_pool: &PgConnectionPool, checkpoint: &ProcessedCheckpoint) -> Result<()> {
+    let mut conn = pg_pool.get().await.map_err(|e| anyhow!("Failed to get DB connection: {}", e))?;
+    diesel::insert_into(processed_checkpoints::table)
+        .values(checkpoint)
+        .on_conflict(processed_checkpoints::checkpoint_seq)
+        .do_update()
+        .set((
+            processed_checkpoints::timestamp_ms.eq(sql("excluded.timestamp_ms")),
+            processed_checkpoints::processed_at.eq(sql("excluded.processed_at")),
+            processed_checkpoints::swap_events.eq(sql("excluded.swap_events")),
+            processed_checkpoints::add_liquidity_events.eq(sql("excluded.add_liquidity_events")),
+            processed_checkpoints::remove_liquidity_events.eq(sql("excluded.remove_liquidity_events")),
+            processed_checkpoints::dynamic_events.eq(sql("excluded.dynamic_events")),
+            processed_checkpoints::duration_ms.eq(sql("excluded.duration_ms")),
+        ))
+        .execute(&mut conn)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,248 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use move_core_types::language_storage::StructTag;
+use serde::Serialize;
+use sui_types::event::Event;
+use sui_types::full_checkpoint_content::CheckpointTransaction;
+
+use crate::models::{
+    CetusAddLiquidityEvent, CetusRemoveLiquidityEvent, CetusSwapEvent, DynamicEvent, ObservedEventType,
+};
+
+/// Table that a route writes its decoded events to. The Cetus tables have compiled decoders
+/// and columns; other protocols are added as data in the protocols config, which routes them
+/// to `Dynamic` and can give each a typed view (see `crate::dynamic`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventTable {
+    Swap,
+    AddLiquidity,
+    RemoveLiquidity,
+    /// Events decoded from a runtime layout, stored as JSON
+    Dynamic,
+}
+
+impl EventTable {
+    /// Short label used when generating event ids
+    pub fn id_kind(&self) -> &'static str {
+        match self {
+            EventTable::Swap => "swap",
+            EventTable::AddLiquidity => "add",
+            EventTable::RemoveLiquidity => "remove",
+            EventTable::Dynamic => "dynamic",
+        }
+    }
+
+    /// Name of the database table backing this event kind
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            EventTable::Swap => "cetus_swap_events",
+            EventTable::AddLiquidity => "cetus_add_liquidity_events",
+            EventTable::RemoveLiquidity => "cetus_remove_liquidity_events",
+            EventTable::Dynamic => "dynamic_events",
+        }
+    }
+}
+
+impl FromStr for EventTable {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "swap" => Ok(EventTable::Swap),
+            "add_liquidity" => Ok(EventTable::AddLiquidity),
+            "remove_liquidity" => Ok(EventTable::RemoveLiquidity),
+            "dynamic" => Ok(EventTable::Dynamic),
+            _ => Err(anyhow!(
+                "Invalid event table '{}', expected one of: swap, add_liquidity, remove_liquidity, dynamic",
+                s
+            )),
+        }
+    }
+}
+
+/// A decoded event, ready to be written to its target table
+#[derive(Debug)]
+pub enum IndexedEvent {
+    Swap(CetusSwapEvent),
+    AddLiquidity(CetusAddLiquidityEvent),
+    RemoveLiquidity(CetusRemoveLiquidityEvent),
+    Dynamic(DynamicEvent),
+}
+
+impl IndexedEvent {
+    pub fn table(&self) -> EventTable {
+        match self {
+            IndexedEvent::Swap(_) => EventTable::Swap,
+            IndexedEvent::AddLiquidity(_) => EventTable::AddLiquidity,
+            IndexedEvent::RemoveLiquidity(_) => EventTable::RemoveLiquidity,
+            IndexedEvent::Dynamic(_) => EventTable::Dynamic,
+        }
+    }
+}
+
+/// Everything a decoder needs to turn a raw event into a row
+pub struct EventContext<'a> {
+    pub event: &'a Event,
+    /// Transaction that emitted the event, including its input and output objects
+    pub transaction: &'a CheckpointTransaction,
+    /// Unique id assigned to the resulting row
+    pub id: String,
+    pub tx_digest: &'a str,
+    /// Position of the event within its transaction's events
+    pub event_index: usize,
+    /// Sequence number of the checkpoint containing the event
+    pub checkpoint_seq: u64,
+    /// Position of the transaction within its checkpoint
+    pub tx_index: usize,
+    /// Timestamp of the checkpoint containing the event
+    pub timestamp_ms: u64,
+}
+
+/// Decodes the BCS contents of a matched event into a row. A closure, so decoders built at
+/// runtime (e.g. from a layout in the protocols config) can carry their own state.
+pub type Decoder = Arc<dyn Fn(&EventContext<'_>) -> Result<IndexedEvent> + Send + Sync>;
+
+/// Hook run on every decoded event before it is stored, so deployments can add their own
+/// logic (e.g. tagging rows with an internal pool category) without touching the decoders
+pub trait EventEnricher: Send + Sync {
+    fn enrich(&self, _event: &mut IndexedEvent, _ctx: &EventContext<'_>) {}
+}
+
+/// Enricher that leaves events unchanged
+#[derive(Default)]
+pub struct NoopEnricher;
+
+impl EventEnricher for NoopEnricher {}
+
+/// Routes events of a given Move type to a target table through a decoder
+pub struct Route {
+    pub struct_tag: StructTag,
+    pub table: EventTable,
+    pub decoder: Decoder,
+    /// Protocol tag prepended to the ids of this route's events, e.g. `turbos:`. Routes
+    /// without one use the indexer's prefix.
+    pub id_prefix: Option<String>,
+}
+
+/// Lookup of routes by event type
+#[derive(Default)]
+pub struct RouteRegistry {
+    routes: HashMap<StructTag, Route>,
+}
+
+impl RouteRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a route, replacing any existing route for the same event type
+    pub fn register(&mut self, route: Route) {
+        self.routes.insert(route.struct_tag.clone(), route);
+    }
+
+    pub fn get(&self, struct_tag: &StructTag) -> Option<&Route> {
+        self.routes.get(struct_tag)
+    }
+
+    pub fn routes(&self) -> impl Iterator<Item = &Route> {
+        self.routes.values()
+    }
+}
+
+/// Events extracted from one or more checkpoints, grouped by target table
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IndexedEvents {
+    pub swap_events: Vec<CetusSwapEvent>,
+    pub add_liquidity_events: Vec<CetusAddLiquidityEvent>,
+    pub remove_liquidity_events: Vec<CetusRemoveLiquidityEvent>,
+    pub dynamic_events: Vec<DynamicEvent>,
+    /// Histogram of every event type seen, keyed by type and checkpoint, when recording is enabled
+    pub observed_event_types: HashMap<(String, u64), ObservedEventType>,
+}
+
+impl IndexedEvents {
+    pub fn push(&mut self, event: IndexedEvent) {
+        match event {
+            IndexedEvent::Swap(event) => self.swap_events.push(event),
+            IndexedEvent::AddLiquidity(event) => self.add_liquidity_events.push(event),
+            IndexedEvent::RemoveLiquidity(event) => self.remove_liquidity_events.push(event),
+            IndexedEvent::Dynamic(event) => self.dynamic_events.push(event),
+        }
+    }
+
+    /// Count one occurrence of an event type in a checkpoint, matched or not
+    pub fn observe_event_type(&mut self, event_type: String, checkpoint_seq: u64, matched: bool) {
+        self.observed_event_types
+            .entry((event_type.clone(), checkpoint_seq))
+            .or_insert_with(|| ObservedEventType {
+                event_type,
+                matched,
+                count: 0,
+                last_seen_at: None,
+            })
+            .count += 1;
+    }
+
+    /// Append the events of another batch, summing the observed event type counts
+    pub fn extend(&mut self, other: IndexedEvents) {
+        self.swap_events.extend(other.swap_events);
+        self.add_liquidity_events.extend(other.add_liquidity_events);
+        self.remove_liquidity_events.extend(other.remove_liquidity_events);
+        self.dynamic_events.extend(other.dynamic_events);
+        for (key, observed) in other.observed_event_types {
+            self.observed_event_types
+                .entry(key)
+                .and_modify(|existing| existing.count += observed.count)
+                .or_insert(observed);
+        }
+    }
+
+    /// Split into one batch per non-empty table, with the observed event types in a batch of their own
+    pub fn split_by_table(&self) -> Vec<IndexedEvents> {
+        let parts = [
+            IndexedEvents {
+                swap_events: self.swap_events.clone(),
+                ..Default::default()
+            },
+            IndexedEvents {
+                add_liquidity_events: self.add_liquidity_events.clone(),
+                ..Default::default()
+            },
+            IndexedEvents {
+                remove_liquidity_events: self.remove_liquidity_events.clone(),
+                ..Default::default()
+            },
+            IndexedEvents {
+                dynamic_events: self.dynamic_events.clone(),
+                ..Default::default()
+            },
+            IndexedEvents {
+                observed_event_types: self.observed_event_types.clone(),
+                ..Default::default()
+            },
+        ];
+        parts.into_iter().filter(|part| !part.is_empty()).collect()
+    }
+
+    /// Number of decoded events, excluding observed event types
+    pub fn event_count(&self) -> usize {
+        self.swap_events.len()
+            + self.add_liquidity_events.len()
+            + self.remove_liquidity_events.len()
+            + self.dynamic_events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.swap_events.is_empty()
+            && self.add_liquidity_events.is_empty()
+            && self.remove_liquidity_events.is_empty()
+            && self.dynamic_events.is_empty()
+            && self.observed_event_types.is_empty()
+    }
+}
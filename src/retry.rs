@@ -0,0 +1,174 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shared retry loop for flaky operations (database connections, commits). Every attempt is
+//! counted in `retries_total{operation, outcome}`, so the failure rate of each operation shows
+//! up in Prometheus instead of only in the logs.
+
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+use tracing::warn;
+
+use crate::metrics::IndexerMetrics;
+
+// Upper bound of the delay between attempts, however many attempts are allowed
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// How often and how patiently an operation is retried
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one; 1 disables retries
+    pub max_attempts: u32,
+    /// Delay before the second attempt, doubled after each further failure
+    pub initial_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Run `attempt` until it succeeds or the policy's attempts are used up, sleeping with
+/// exponential backoff in between. Returns the last error when every attempt failed.
+pub async fn retry<T, F, Fut>(
+    operation: &str,
+    policy: &RetryPolicy,
+    metrics: &IndexerMetrics,
+    mut attempt: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut delay = policy.initial_delay;
+    for attempt_number in 1.. {
+        match attempt().await {
+            Ok(value) => {
+                metrics.retries_total.with_label_values(&[operation, "success"]).inc();
+                return Ok(value);
+            }
+            Err(e) => {
+                metrics.retries_total.with_label_values(&[operation, "failure"]).inc();
+                if attempt_number >= max_attempts {
+                    return Err(e);
+                }
+                warn!(
+                    "{} failed (attempt {} of {}): {:#}, retrying in {:?}",
+                    operation, attempt_number, max_attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+    unreachable!("the loop returns once the attempts are used up")
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use prometheus::Registry;
+
+    use super::*;
+
+    fn attempts(metrics: &IndexerMetrics, outcome: &str) -> u64 {
+        metrics.retries_total.with_label_values(&["test", outcome]).get()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn succeeds_after_failures() {
+        let metrics = IndexerMetrics::new(&Registry::new());
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(200),
+        };
+        let mut calls = 0;
+        let result = retry("test", &policy, &metrics, || {
+            calls += 1;
+            let call = calls;
+            async move {
+                if call < 3 {
+                    Err(anyhow!("attempt {} failed", call))
+                } else {
+                    Ok(call)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+        assert_eq!(attempts(&metrics, "failure"), 2);
+        assert_eq!(attempts(&metrics, "success"), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_max_attempts() {
+        let metrics = IndexerMetrics::new(&Registry::new());
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(200),
+        };
+        let mut calls = 0;
+        let result: Result<()> = retry("test", &policy, &metrics, || {
+            calls += 1;
+            let call = calls;
+            async move { Err(anyhow!("attempt {} failed", call)) }
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "attempt 3 failed");
+        assert_eq!(calls, 3);
+        assert_eq!(attempts(&metrics, "failure"), 3);
+        assert_eq!(attempts(&metrics, "success"), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn zero_max_attempts_still_tries_once() {
+        let metrics = IndexerMetrics::new(&Registry::new());
+        let policy = RetryPolicy {
+            max_attempts: 0,
+            initial_delay: Duration::from_millis(200),
+        };
+        let mut calls = 0;
+        let result: Result<()> = retry("test", &policy, &metrics, || {
+            calls += 1;
+            async { Err(anyhow!("failed")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        assert_eq!(attempts(&metrics, "failure"), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backoff_doubles_up_to_the_maximum_delay() {
+        let metrics = IndexerMetrics::new(&Registry::new());
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_delay: Duration::from_secs(3),
+        };
+        let mut started = Vec::new();
+        let result: Result<()> = retry("test", &policy, &metrics, || {
+            started.push(tokio::time::Instant::now());
+            async { Err(anyhow!("failed")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        let delays = started.windows(2).map(|pair| pair[1] - pair[0]).collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [3, 6, 10, 10].map(Duration::from_secs),
+            "delays double from the initial delay and stop at MAX_RETRY_DELAY"
+        );
+    }
+}
@@ -5,6 +5,16 @@ diesel::table! {
         id -> Varchar,
         liquidity -> Varchar,
         after_liquidity -> Varchar,
+        created_at -> Nullable<Timestamptz>,
+        package_id -> Varchar,
+        tx_digest -> Varchar,
+        event_index -> Int8,
+        pool -> Varchar,
+        position -> Varchar,
+        timestamp_ms -> Int8,
+        checkpoint_seq -> Int8,
+        tx_index -> Int8,
+        event_seq -> Int8,
     }
 }
 
@@ -13,19 +23,147 @@ diesel::table! {
         id -> Varchar,
         liquidity -> Varchar,
         after_liquidity -> Varchar,
+        created_at -> Nullable<Timestamptz>,
+        package_id -> Varchar,
+        tx_digest -> Varchar,
+        event_index -> Int8,
+        pool -> Varchar,
+        position -> Varchar,
+        timestamp_ms -> Int8,
+        checkpoint_seq -> Int8,
+        tx_index -> Int8,
+        event_seq -> Int8,
     }
 }
 
 diesel::table! {
-    cetus_swap_events (id) {
+    cetus_swap_event_ids (id) {
+        id -> Varchar,
+        timestamp_ms -> Int8,
+    }
+}
+
+diesel::table! {
+    cetus_swap_events (id, timestamp_ms) {
         id -> Varchar,
         amount_in -> Int8,
         amount_out -> Int8,
+        created_at -> Nullable<Timestamptz>,
+        pool_id -> Varchar,
+        timestamp_ms -> Int8,
+        atob -> Bool,
+        coin_type_a -> Varchar,
+        coin_type_b -> Varchar,
+        package_id -> Varchar,
+        tx_digest -> Varchar,
+        event_index -> Int8,
+        before_sqrt_price -> Varchar,
+        after_sqrt_price -> Varchar,
+        price_impact_bps -> Int8,
+        partner -> Varchar,
+        fee_amount -> Int8,
+        checkpoint_seq -> Int8,
+        tx_index -> Int8,
+        event_seq -> Int8,
+        sender -> Varchar,
+    }
+}
+
+diesel::table! {
+    coin_metadata (coin_type) {
+        coin_type -> Varchar,
+        decimals -> Int2,
+        symbol -> Varchar,
+        name -> Varchar,
+        fetched_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    coin_prices (coin_type) {
+        coin_type -> Varchar,
+        usd_price -> Float8,
+        decimals -> Int2,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    dynamic_events (id) {
+        id -> Varchar,
+        event_name -> Varchar,
+        event_type -> Varchar,
+        package_id -> Varchar,
+        tx_digest -> Varchar,
+        event_index -> Int8,
+        timestamp_ms -> Int8,
+        checkpoint_seq -> Int8,
+        tx_index -> Int8,
+        event_seq -> Int8,
+        data -> Jsonb,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    observed_event_type_checkpoints (event_type, checkpoint_seq) {
+        event_type -> Varchar,
+        checkpoint_seq -> Int8,
+        count -> Int8,
+    }
+}
+
+diesel::table! {
+    observed_event_types (event_type) {
+        event_type -> Varchar,
+        matched -> Bool,
+        count -> Int8,
+        last_seen_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    pool_stats (pool_id) {
+        pool_id -> Varchar,
+        total_volume_in -> Numeric,
+        total_volume_out -> Numeric,
+        swap_count -> Int8,
+        last_updated -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    processed_checkpoints (checkpoint_seq) {
+        checkpoint_seq -> Int8,
+        timestamp_ms -> Int8,
+        processed_at -> Timestamptz,
+        swap_events -> Int4,
+        add_liquidity_events -> Int4,
+        remove_liquidity_events -> Int4,
+        dynamic_events -> Int4,
+        duration_ms -> Int8,
+    }
+}
+
+diesel::table! {
+    progress (task_name) {
+        task_name -> Varchar,
+        checkpoint_number -> Int8,
+        updated_at -> Timestamptz,
     }
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
     cetus_add_liquidity_events,
     cetus_remove_liquidity_events,
+    cetus_swap_event_ids,
     cetus_swap_events,
+    coin_metadata,
+    coin_prices,
+    dynamic_events,
+    observed_event_type_checkpoints,
+    observed_event_types,
+    pool_stats,
+    processed_checkpoints,
+    progress,
 );
@@ -0,0 +1,114 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline configuration check run by `--self-test`, so CI can catch a bad deployment
+//! config without starting ingestion.
+
+use std::collections::HashSet;
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use diesel::pg::Pg;
+use diesel::sql_types::Text;
+use diesel::QueryableByName;
+use diesel_async::RunQueryDsl;
+use diesel_migrations::MigrationSource;
+use move_core_types::language_storage::StructTag;
+
+use crate::indexer::check_event_type_address;
+use crate::{get_connection_pool_for_url, MIGRATIONS};
+
+// Event type overrides checked by the self-test; unset variables fall back to the built-in types
+const EVENT_TYPE_VARS: &[&str] = &[
+    "SWAP_EVENT_TYPE",
+    "ADD_LIQUIDITY_EVENT_TYPE",
+    "REMOVE_LIQUIDITY_EVENT_TYPE",
+];
+
+// How long to wait for the database before reporting it unreachable
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(QueryableByName)]
+struct AppliedMigration {
+    #[diesel(sql_type = Text)]
+    version: String,
+}
+
+/// Run every check, printing one line per check. Returns whether all of them passed.
+pub async fn run() -> bool {
+    let mut passed = true;
+
+    for var in EVENT_TYPE_VARS {
+        match env::var(var) {
+            Ok(value) => match StructTag::from_str(&value) {
+                Ok(_) => match check_event_type_address(&value) {
+                    Ok(()) => report(true, &format!("{} parses as a Move struct tag", var)),
+                    Err(e) => {
+                        report(false, &format!("{}: {}", var, e));
+                        passed = false;
+                    }
+                },
+                Err(e) => {
+                    report(false, &format!("{} '{}' is not a valid struct tag: {}", var, value, e));
+                    passed = false;
+                }
+            },
+            Err(_) => report(true, &format!("{} not set, using the default event type", var)),
+        }
+    }
+
+    match check_database().await {
+        Ok(messages) => {
+            for message in messages {
+                report(true, &message);
+            }
+        }
+        Err(e) => {
+            report(false, &e.to_string());
+            passed = false;
+        }
+    }
+
+    println!("Self-test {}", if passed { "passed" } else { "failed" });
+    passed
+}
+
+fn report(ok: bool, message: &str) {
+    println!("[{}] {}", if ok { "ok" } else { "FAIL" }, message);
+}
+
+// Connect to DATABASE_URL and compare the applied migrations with the embedded ones
+async fn check_database() -> Result<Vec<String>> {
+    let database_url = env::var("DATABASE_URL").map_err(|_| anyhow!("DATABASE_URL is not set"))?;
+    let pool = get_connection_pool_for_url(database_url).await;
+    let mut conn = tokio::time::timeout(DB_CHECK_TIMEOUT, pool.get())
+        .await
+        .map_err(|_| anyhow!("Timed out connecting to DATABASE_URL"))?
+        .map_err(|e| anyhow!("Could not connect to DATABASE_URL: {}", e))?;
+
+    let applied = diesel::sql_query("SELECT version FROM __diesel_schema_migrations")
+        .load::<AppliedMigration>(&mut conn)
+        .await
+        .map_err(|e| anyhow!("Could not read applied migrations: {}", e))?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect::<HashSet<_>>();
+
+    let embedded = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+        .map_err(|e| anyhow!("Could not load embedded migrations: {}", e))?;
+    let pending = embedded
+        .iter()
+        .filter(|migration| !applied.contains(&migration.name().version().to_string()))
+        .map(|migration| migration.name().to_string())
+        .collect::<Vec<_>>();
+    if !pending.is_empty() {
+        return Err(anyhow!("Pending migrations: {}", pending.join(", ")));
+    }
+
+    Ok(vec![
+        "Connected to DATABASE_URL".to_string(),
+        format!("All {} migrations are applied", embedded.len()),
+    ])
+}
@@ -0,0 +1,1139 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::Datelike;
+use diesel::{
+    dsl::sql,
+    sql_types::{Array, BigInt, Text},
+    ExpressionMethods, QueryDsl, QueryResult, QueryableByName, SelectableHelper,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, RunQueryDsl};
+use futures::FutureExt;
+use tokio::sync::oneshot;
+use tracing::{error, warn};
+
+use crate::metrics::IndexerMetrics;
+use crate::models::{CetusAddLiquidityEvent, CetusRemoveLiquidityEvent, CetusSwapEvent, EventRecord, ObservedEventType};
+use crate::registry::{EventTable, IndexedEvents};
+use crate::retry::{retry, RetryPolicy};
+use crate::schema::{
+    cetus_add_liquidity_events, cetus_remove_liquidity_events, cetus_swap_events, dynamic_events,
+    observed_event_types,
+};
+use crate::PgConnectionPool;
+
+/// Destination for the events extracted from a checkpoint.
+///
+/// Invariant: the executor records a checkpoint in the progress store as soon as the worker's
+/// `write` returns `Ok`, so `write` must not return `Ok` before every event it was given is
+/// durably committed. Otherwise a crash would move progress past events that were never
+/// stored. Sinks that buffer (`BatchingSink`) or filter (`DedupSink`) wait for the inner
+/// write; sinks that deliver in the background (`WebhookSink`) must only run after the
+/// database sinks in a `ChainedSink`.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Label used in logs
+    fn name(&self) -> &str;
+
+    /// Persist the events. Writes must be idempotent, since a checkpoint is retried after an error
+    /// or reprocessed after a crash between the commit and the progress update.
+    async fn write(&self, events: &IndexedEvents) -> Result<()>;
+}
+
+/// Default table write order. The event tables have no foreign keys between them today,
+/// so any order is valid; change it when adding constraints (e.g. liquidity events
+/// referencing a pools table) so parents are written first.
+pub const DEFAULT_COMMIT_ORDER: [EventTable; 4] = [
+    EventTable::Swap,
+    EventTable::AddLiquidity,
+    EventTable::RemoveLiquidity,
+    EventTable::Dynamic,
+];
+
+/// Where the indexer writes events, selected with SINK
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SinkKind {
+    /// Postgres only
+    #[default]
+    Postgres,
+    /// Postgres plus a ClickHouse copy for analytics; needs the `clickhouse` feature
+    ClickHouse,
+}
+
+impl FromStr for SinkKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "postgres" => Ok(SinkKind::Postgres),
+            "clickhouse" if cfg!(feature = "clickhouse") => Ok(SinkKind::ClickHouse),
+            "clickhouse" => Err(anyhow!("SINK=clickhouse needs the indexer built with --features clickhouse")),
+            _ => Err(anyhow!("Invalid SINK '{}', expected 'postgres' or 'clickhouse'", s)),
+        }
+    }
+}
+
+/// Upserts events into a Postgres database
+pub struct PgSink {
+    name: String,
+    pg_pool: PgConnectionPool,
+    metrics: IndexerMetrics,
+    /// Compare upserted events against stored rows to catch id-scheme bugs
+    check_id_collisions: bool,
+    /// Pipeline the per-table upserts over the transaction's connection instead of running them in sequence
+    pipeline_commits: bool,
+    /// Order in which tables are written, parents before children
+    commit_order: Vec<EventTable>,
+    /// Write each table in its own transaction on its own connection, concurrently
+    parallel_table_commits: bool,
+    /// Retries of a failed transaction before the write fails
+    retry_policy: RetryPolicy,
+    /// Monthly swap partitions known to exist
+    swap_partitions: SwapPartitionCache,
+    /// Full months of swaps kept before the current one; older swaps are skipped
+    swap_retention_months: Option<u32>,
+}
+
+impl PgSink {
+    pub fn new(name: impl Into<String>, pg_pool: PgConnectionPool, metrics: IndexerMetrics) -> Self {
+        Self {
+            name: name.into(),
+            pg_pool,
+            metrics,
+            check_id_collisions: false,
+            pipeline_commits: false,
+            commit_order: DEFAULT_COMMIT_ORDER.to_vec(),
+            parallel_table_commits: false,
+            retry_policy: RetryPolicy::default(),
+            swap_partitions: SwapPartitionCache::default(),
+            swap_retention_months: None,
+        }
+    }
+
+    pub fn with_id_collision_checks(mut self, check_id_collisions: bool) -> Self {
+        self.check_id_collisions = check_id_collisions;
+        self
+    }
+
+    pub fn with_pipelined_commits(mut self, pipeline_commits: bool) -> Self {
+        self.pipeline_commits = pipeline_commits;
+        self
+    }
+
+    /// Commit each table in its own transaction, concurrently. Tables are then no longer atomically
+    /// consistent with each other, which is fine for append-style event data since every write is
+    /// an idempotent upsert and a failed checkpoint is retried as a whole.
+    pub fn with_parallel_table_commits(mut self, parallel_table_commits: bool) -> Self {
+        self.parallel_table_commits = parallel_table_commits;
+        self
+    }
+
+    /// Retry failed transactions (e.g. after a dropped connection) before failing the write
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Write tables in this order; tables not listed are written last
+    pub fn with_commit_order(mut self, commit_order: Vec<EventTable>) -> Self {
+        self.commit_order = commit_order;
+        self
+    }
+
+    /// Share the known swap partitions with the partition maintainer, which clears them after
+    /// expiring partitions
+    pub fn with_swap_partition_cache(mut self, swap_partitions: SwapPartitionCache) -> Self {
+        self.swap_partitions = swap_partitions;
+        self
+    }
+
+    /// Skip swaps of months older than SWAP_PARTITION_RETENTION_MONTHS, whose partitions the
+    /// maintainer expires, instead of recreating their partition
+    pub fn with_swap_retention_months(mut self, months: u32) -> Self {
+        self.swap_retention_months = Some(months);
+        self
+    }
+
+    /// The events without swaps older than the retention, or None when there are none
+    fn without_expired_swaps(&self, events: &IndexedEvents) -> Option<IndexedEvents> {
+        let months = self.swap_retention_months?;
+        let oldest_kept = oldest_kept_swap_month(chrono::Utc::now(), months);
+        let expired = |event: &CetusSwapEvent| {
+            swap_partition_month(event.timestamp_ms).is_some_and(|month| month < oldest_kept)
+        };
+        let skipped = events.swap_events.iter().filter(|event| expired(event)).count();
+        if skipped == 0 {
+            return None;
+        }
+        warn!(
+            "Skipping {} swaps older than the {} months kept by SWAP_PARTITION_RETENTION_MONTHS",
+            skipped, months
+        );
+        self.metrics.expired_swaps_skipped_total.inc_by(skipped as u64);
+        let mut retained = events.clone();
+        retained.swap_events.retain(|event| !expired(event));
+        Some(retained)
+    }
+
+    /// Counts incoming events whose id already exists with different content
+    fn record_id_collisions<T: EventRecord>(&self, table: &str, incoming: &[T], existing: &[T]) {
+        let existing: HashMap<&str, &T> = existing.iter().map(|row| (row.id(), row)).collect();
+        let collisions = incoming
+            .iter()
+            .filter(|event| {
+                existing
+                    .get(event.id())
+                    .is_some_and(|row| !row.same_content(event))
+            })
+            .count();
+
+        if collisions > 0 {
+            error!(
+                "Detected {} id collisions in {}: upsert overwrites rows with different content",
+                collisions, table
+            );
+            self.metrics
+                .id_collisions_total
+                .with_label_values(&[table])
+                .inc_by(collisions as u64);
+        }
+    }
+
+    /// Inserts or updates Cetus events in the database
+    #[tracing::instrument(
+        name = "commit_to_db",
+        skip_all,
+        fields(
+            sink = %self.name,
+            swap_events = events.swap_events.len(),
+            add_liquidity_events = events.add_liquidity_events.len(),
+            remove_liquidity_events = events.remove_liquidity_events.len(),
+            dynamic_events = events.dynamic_events.len(),
+        )
+    )]
+    async fn commit_to_db(&self, events: &IndexedEvents) -> Result<()> {
+        let retained = self.without_expired_swaps(events);
+        let events = retained.as_ref().unwrap_or(events);
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let mode = if self.parallel_table_commits {
+            "parallel"
+        } else if self.pipeline_commits {
+            "pipelined"
+        } else {
+            "sequential"
+        };
+        let _timer = self
+            .metrics
+            .commit_duration_seconds
+            .with_label_values(&[mode])
+            .start_timer();
+
+        self.create_swap_partitions(&events.swap_events).await?;
+
+        if !self.parallel_table_commits {
+            return self.commit_with_retries(events).await;
+        }
+
+        let results =
+            futures::future::join_all(events.split_by_table().iter().map(|part| self.commit_with_retries(part))).await;
+        results.into_iter().collect::<Result<Vec<_>>>()?;
+        Ok(())
+    }
+
+    /// Creates the monthly partitions of the swaps' months this sink hasn't written yet, so
+    /// backfilled history lands in its own month's partition. Runs before the commit
+    /// transaction, since creating a partition locks the whole swaps table.
+    async fn create_swap_partitions(&self, swap_events: &[CetusSwapEvent]) -> Result<()> {
+        let mut missing = HashMap::new();
+        {
+            let known = self.swap_partitions.0.lock().unwrap();
+            for event in swap_events {
+                if let Some(month) = swap_partition_month(event.timestamp_ms) {
+                    if !known.contains(&month) {
+                        missing.entry(month).or_insert(event.timestamp_ms);
+                    }
+                }
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let timestamps = missing.values().copied().collect::<Vec<_>>();
+        retry("swap_partitions", &self.retry_policy, &self.metrics, || async {
+            let mut connection = self
+                .pg_pool
+                .get()
+                .await
+                .map_err(|e| anyhow!("Failed to get DB connection for sink {}: {}", self.name, e))?;
+            diesel::sql_query(CREATE_SWAP_PARTITIONS_SQL)
+                .bind::<Array<BigInt>, _>(&timestamps)
+                .execute(&mut connection)
+                .await
+                .context("Failed to create swap partitions")?;
+            Ok(())
+        })
+        .await?;
+
+        self.swap_partitions.0.lock().unwrap().extend(missing.into_keys());
+        Ok(())
+    }
+
+    /// Commits the events in one transaction, retrying the whole transaction when it fails
+    async fn commit_with_retries(&self, events: &IndexedEvents) -> Result<()> {
+        retry("commit", &self.retry_policy, &self.metrics, || self.commit_transaction(events)).await
+    }
+
+    /// Inserts or updates the given events in a single transaction
+    async fn commit_transaction(&self, events: &IndexedEvents) -> Result<()> {
+        let swap_events = events.swap_events.as_slice();
+        let add_liquidity_events = events.add_liquidity_events.as_slice();
+        let remove_liquidity_events = events.remove_liquidity_events.as_slice();
+        let dynamic_events = events.dynamic_events.as_slice();
+        // Sorted, so concurrent commits lock the same rows in the same order
+        let mut observed_types = events.observed_event_types.iter().collect::<Vec<_>>();
+        observed_types.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut connection = self
+            .pg_pool
+            .get()
+            .await
+            .map_err(|e| anyhow!("Failed to get DB connection for sink {}: {}", self.name, e))?;
+
+        connection
+            .transaction::<_, anyhow::Error, _>(|conn| {
+                async move {
+                    if self.check_id_collisions {
+                        if !swap_events.is_empty() {
+                            let ids: Vec<&str> = swap_events.iter().map(|e| e.id.as_str()).collect();
+                            let existing = cetus_swap_events::table
+                                .filter(cetus_swap_events::id.eq_any(ids))
+                                .select(CetusSwapEvent::as_select())
+                                .load(conn)
+                                .await?;
+                            self.record_id_collisions("cetus_swap_events", swap_events, &existing);
+                        }
+
+                        if !add_liquidity_events.is_empty() {
+                            let ids: Vec<&str> = add_liquidity_events.iter().map(|e| e.id.as_str()).collect();
+                            let existing = cetus_add_liquidity_events::table
+                                .filter(cetus_add_liquidity_events::id.eq_any(ids))
+                                .select(CetusAddLiquidityEvent::as_select())
+                                .load(conn)
+                                .await?;
+                            self.record_id_collisions("cetus_add_liquidity_events", add_liquidity_events, &existing);
+                        }
+
+                        if !remove_liquidity_events.is_empty() {
+                            let ids: Vec<&str> = remove_liquidity_events.iter().map(|e| e.id.as_str()).collect();
+                            let existing = cetus_remove_liquidity_events::table
+                                .filter(cetus_remove_liquidity_events::id.eq_any(ids))
+                                .select(CetusRemoveLiquidityEvent::as_select())
+                                .load(conn)
+                                .await?;
+                            self.record_id_collisions("cetus_remove_liquidity_events", remove_liquidity_events, &existing);
+                        }
+                    }
+
+                    // Check deferrable constraints (e.g. foreign keys between event tables) at commit,
+                    // so rows within the batch may reference each other in any order
+                    diesel::sql_query("SET CONSTRAINTS ALL DEFERRED").execute(conn).await?;
+
+                    // Build the upserts up front. Futures returned by AsyncPgConnection don't
+                    // borrow the connection, so they can either be awaited one after another
+                    // or pipelined over the same connection inside the transaction.
+                    let swap_upsert = (!swap_events.is_empty()).then(|| {
+                        diesel::insert_into(cetus_swap_events::table)
+                            .values(swap_events)
+                            // The partition key is part of the primary key, so it can't be updated.
+                            // Ids stay unique through cetus_swap_event_ids: the insert trigger deletes
+                            // the stored swap when the same id arrives with another timestamp_ms.
+                            .on_conflict((cetus_swap_events::id, cetus_swap_events::timestamp_ms))
+                            .do_update()
+                            .set((
+                                cetus_swap_events::amount_in.eq(sql("excluded.amount_in")),
+                                cetus_swap_events::amount_out.eq(sql("excluded.amount_out")),
+                                cetus_swap_events::pool_id.eq(sql("excluded.pool_id")),
+                                cetus_swap_events::checkpoint_seq.eq(sql("excluded.checkpoint_seq")),
+                                cetus_swap_events::tx_index.eq(sql("excluded.tx_index")),
+                                cetus_swap_events::event_seq.eq(sql("excluded.event_seq")),
+                                cetus_swap_events::atob.eq(sql("excluded.atob")),
+                                cetus_swap_events::coin_type_a.eq(sql("excluded.coin_type_a")),
+                                cetus_swap_events::coin_type_b.eq(sql("excluded.coin_type_b")),
+                                cetus_swap_events::package_id.eq(sql("excluded.package_id")),
+                                cetus_swap_events::tx_digest.eq(sql("excluded.tx_digest")),
+                                cetus_swap_events::event_index.eq(sql("excluded.event_index")),
+                                cetus_swap_events::before_sqrt_price.eq(sql("excluded.before_sqrt_price")),
+                                cetus_swap_events::after_sqrt_price.eq(sql("excluded.after_sqrt_price")),
+                                cetus_swap_events::price_impact_bps.eq(sql("excluded.price_impact_bps")),
+                                cetus_swap_events::partner.eq(sql("excluded.partner")),
+                                cetus_swap_events::fee_amount.eq(sql("excluded.fee_amount")),
+                                cetus_swap_events::sender.eq(sql("excluded.sender")),
+                            ))
+                            .execute(conn)
+                    });
+
+                    let add_liquidity_upsert = (!add_liquidity_events.is_empty()).then(|| {
+                        diesel::insert_into(cetus_add_liquidity_events::table)
+                            .values(add_liquidity_events)
+                            .on_conflict(cetus_add_liquidity_events::id)
+                            .do_update()
+                            .set((
+                                cetus_add_liquidity_events::liquidity.eq(sql("excluded.liquidity")),
+                                cetus_add_liquidity_events::after_liquidity.eq(sql("excluded.after_liquidity")),
+                                cetus_add_liquidity_events::package_id.eq(sql("excluded.package_id")),
+                                cetus_add_liquidity_events::tx_digest.eq(sql("excluded.tx_digest")),
+                                cetus_add_liquidity_events::event_index.eq(sql("excluded.event_index")),
+                                cetus_add_liquidity_events::pool.eq(sql("excluded.pool")),
+                                cetus_add_liquidity_events::position.eq(sql("excluded.position")),
+                                cetus_add_liquidity_events::timestamp_ms.eq(sql("excluded.timestamp_ms")),
+                                cetus_add_liquidity_events::checkpoint_seq.eq(sql("excluded.checkpoint_seq")),
+                                cetus_add_liquidity_events::tx_index.eq(sql("excluded.tx_index")),
+                                cetus_add_liquidity_events::event_seq.eq(sql("excluded.event_seq")),
+                            ))
+                            .execute(conn)
+                    });
+
+                    let remove_liquidity_upsert = (!remove_liquidity_events.is_empty()).then(|| {
+                        diesel::insert_into(cetus_remove_liquidity_events::table)
+                            .values(remove_liquidity_events)
+                            .on_conflict(cetus_remove_liquidity_events::id)
+                            .do_update()
+                            .set((
+                                cetus_remove_liquidity_events::liquidity.eq(sql("excluded.liquidity")),
+                                cetus_remove_liquidity_events::after_liquidity.eq(sql("excluded.after_liquidity")),
+                                cetus_remove_liquidity_events::package_id.eq(sql("excluded.package_id")),
+                                cetus_remove_liquidity_events::tx_digest.eq(sql("excluded.tx_digest")),
+                                cetus_remove_liquidity_events::event_index.eq(sql("excluded.event_index")),
+                                cetus_remove_liquidity_events::pool.eq(sql("excluded.pool")),
+                                cetus_remove_liquidity_events::position.eq(sql("excluded.position")),
+                                cetus_remove_liquidity_events::timestamp_ms.eq(sql("excluded.timestamp_ms")),
+                                cetus_remove_liquidity_events::checkpoint_seq.eq(sql("excluded.checkpoint_seq")),
+                                cetus_remove_liquidity_events::tx_index.eq(sql("excluded.tx_index")),
+                                cetus_remove_liquidity_events::event_seq.eq(sql("excluded.event_seq")),
+                            ))
+                            .execute(conn)
+                    });
+
+                    let dynamic_upsert = (!dynamic_events.is_empty()).then(|| {
+                        diesel::insert_into(dynamic_events::table)
+                            .values(dynamic_events)
+                            .on_conflict(dynamic_events::id)
+                            .do_update()
+                            .set((
+                                dynamic_events::event_name.eq(sql("excluded.event_name")),
+                                dynamic_events::event_type.eq(sql("excluded.event_type")),
+                                dynamic_events::package_id.eq(sql("excluded.package_id")),
+                                dynamic_events::tx_digest.eq(sql("excluded.tx_digest")),
+                                dynamic_events::event_index.eq(sql("excluded.event_index")),
+                                dynamic_events::timestamp_ms.eq(sql("excluded.timestamp_ms")),
+                                dynamic_events::checkpoint_seq.eq(sql("excluded.checkpoint_seq")),
+                                dynamic_events::tx_index.eq(sql("excluded.tx_index")),
+                                dynamic_events::event_seq.eq(sql("excluded.event_seq")),
+                                dynamic_events::data.eq(sql("excluded.data")),
+                            ))
+                            .execute(conn)
+                    });
+
+                    // Run the upserts in the configured dependency order. When pipelined, join_all
+                    // polls them in order, so the statements are still sent in that order.
+                    let mut upserts = vec![
+                        (EventTable::Swap, run_upsert(swap_upsert).boxed()),
+                        (EventTable::AddLiquidity, run_upsert(add_liquidity_upsert).boxed()),
+                        (EventTable::RemoveLiquidity, run_upsert(remove_liquidity_upsert).boxed()),
+                        (EventTable::Dynamic, run_upsert(dynamic_upsert).boxed()),
+                    ];
+                    upserts.sort_by_key(|(table, _)| {
+                        self.commit_order.iter().position(|ordered| ordered == table).unwrap_or(usize::MAX)
+                    });
+                    let (tables, upserts): (Vec<_>, Vec<_>) = upserts.into_iter().unzip();
+
+                    let results = if self.pipeline_commits {
+                        futures::future::join_all(upserts).await
+                    } else {
+                        let mut results = Vec::with_capacity(upserts.len());
+                        for upsert in upserts {
+                            results.push(upsert.await);
+                        }
+                        results
+                    };
+
+                    // Returning an error rolls back the transaction so the checkpoint is retried
+                    for (table, result) in tables.into_iter().zip(results) {
+                        result.with_context(|| match table {
+                            EventTable::Swap => format!("Failed to process swap events: {:?}", swap_events),
+                            EventTable::AddLiquidity => {
+                                format!("Failed to process add liquidity events: {:?}", add_liquidity_events)
+                            }
+                            EventTable::RemoveLiquidity => {
+                                format!("Failed to process remove liquidity events: {:?}", remove_liquidity_events)
+                            }
+                            EventTable::Dynamic => format!("Failed to process dynamic events: {:?}", dynamic_events),
+                        })?;
+                    }
+
+                    if !observed_types.is_empty() {
+                        // Count each (type, checkpoint) once, so reprocessing a checkpoint
+                        // doesn't count its events again; only newly inserted counts are added
+                        let new_counts = diesel::sql_query(RECORD_OBSERVED_CHECKPOINTS_SQL)
+                            .bind::<Array<Text>, _>(
+                                observed_types.iter().map(|((event_type, _), _)| event_type.as_str()).collect::<Vec<_>>(),
+                            )
+                            .bind::<Array<BigInt>, _>(
+                                observed_types.iter().map(|((_, checkpoint_seq), _)| *checkpoint_seq as i64).collect::<Vec<_>>(),
+                            )
+                            .bind::<Array<BigInt>, _>(
+                                observed_types.iter().map(|(_, observed)| observed.count).collect::<Vec<_>>(),
+                            )
+                            .load::<ObservedCheckpointCount>(conn)
+                            .await?;
+                        let mut totals = BTreeMap::new();
+                        for new_count in new_counts {
+                            *totals.entry(new_count.event_type).or_insert(0) += new_count.count;
+                        }
+                        let matched = observed_types
+                            .iter()
+                            .map(|((event_type, _), observed)| (event_type.as_str(), observed.matched))
+                            .collect::<HashMap<_, _>>();
+                        let observed_totals = totals
+                            .into_iter()
+                            .map(|(event_type, count)| ObservedEventType {
+                                matched: matched.get(event_type.as_str()).copied().unwrap_or(false),
+                                event_type,
+                                count,
+                                last_seen_at: None,
+                            })
+                            .collect::<Vec<_>>();
+
+                        if !observed_totals.is_empty() {
+                            diesel::insert_into(observed_event_types::table)
+                                .values(observed_totals)
+                                .on_conflict(observed_event_types::event_type)
+                                .do_update()
+                                .set((
+                                    observed_event_types::matched.eq(sql("excluded.matched")),
+                                    observed_event_types::count
+                                        .eq(observed_event_types::count + sql::<BigInt>("excluded.count")),
+                                    observed_event_types::last_seen_at.eq(sql("now()")),
+                                ))
+                                .execute(conn)
+                                .await?;
+                        }
+                    }
+
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl EventSink for PgSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        self.commit_to_db(events).await
+    }
+}
+
+// Record per-checkpoint counts of observed event types, returning only the rows not recorded
+// before. Rows are inserted in array order, which the caller sorts.
+const RECORD_OBSERVED_CHECKPOINTS_SQL: &str = r#"
+    INSERT INTO observed_event_type_checkpoints (event_type, checkpoint_seq, count)
+    SELECT * FROM UNNEST($1::TEXT[], $2::INT8[], $3::INT8[])
+    ON CONFLICT (event_type, checkpoint_seq) DO NOTHING
+    RETURNING event_type, count
+"#;
+
+#[derive(QueryableByName)]
+struct ObservedCheckpointCount {
+    #[diesel(sql_type = Text)]
+    event_type: String,
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+// Create the monthly swap partitions holding the given timestamps, see the partitioning migration
+const CREATE_SWAP_PARTITIONS_SQL: &str = r#"
+    SELECT create_cetus_swap_events_partition(month)
+    FROM (
+        SELECT DISTINCT date_trunc('month', to_timestamp(timestamp_ms / 1000.0) AT TIME ZONE 'UTC')::DATE AS month
+        FROM UNNEST($1::INT8[]) AS timestamp_ms
+        WHERE timestamp_ms > 0
+    ) months
+    ORDER BY month
+"#;
+
+/// Monthly swap partitions (year, month) a PgSink knows to exist, so it only creates each once
+#[derive(Clone, Default)]
+pub struct SwapPartitionCache(Arc<Mutex<HashSet<(i32, u32)>>>);
+
+impl SwapPartitionCache {
+    /// Forget every partition, e.g. after some were detached; they are created again as needed
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// UTC (year, month) of the oldest swap partition kept with `months` full months before the
+/// current one, like OLDEST_KEPT_SWAP_PARTITION_SQL in the aggregator
+fn oldest_kept_swap_month(now: chrono::DateTime<chrono::Utc>, months: u32) -> (i32, u32) {
+    let month_index = i64::from(now.year()) * 12 + i64::from(now.month0()) - i64::from(months);
+    (month_index.div_euclid(12) as i32, month_index.rem_euclid(12) as u32 + 1)
+}
+
+/// UTC (year, month) of the partition holding a swap, or None for swaps without a timestamp,
+/// which are stored in the cetus_swap_events_untimed partition
+fn swap_partition_month(timestamp_ms: i64) -> Option<(i32, u32)> {
+    if timestamp_ms <= 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp_millis(timestamp_ms).map(|time| (time.year(), time.month()))
+}
+
+/// Await an optional upsert, treating a skipped upsert as zero rows written
+async fn run_upsert<F>(upsert: Option<F>) -> QueryResult<usize>
+where
+    F: Future<Output = QueryResult<usize>>,
+{
+    match upsert {
+        Some(upsert) => upsert.await,
+        None => Ok(0),
+    }
+}
+
+/// Writes to several sinks concurrently, succeeding once `quorum` of them have succeeded
+pub struct FanoutSink {
+    sinks: Vec<Box<dyn EventSink>>,
+    quorum: usize,
+}
+
+impl FanoutSink {
+    /// Fan out to all `sinks`, requiring every one of them to succeed
+    pub fn new(sinks: Vec<Box<dyn EventSink>>) -> Self {
+        let quorum = sinks.len();
+        Self { sinks, quorum }
+    }
+
+    /// Require only `quorum` successful writes, clamped to `1..=sinks.len()`
+    pub fn with_quorum(mut self, quorum: usize) -> Self {
+        self.quorum = quorum.clamp(1, self.sinks.len().max(1));
+        self
+    }
+}
+
+#[async_trait]
+impl EventSink for FanoutSink {
+    fn name(&self) -> &str {
+        "fanout"
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        let results = futures::future::join_all(self.sinks.iter().map(|sink| sink.write(events))).await;
+
+        let mut succeeded = 0;
+        let mut failures = Vec::new();
+        for (sink, result) in self.sinks.iter().zip(results) {
+            match result {
+                Ok(()) => succeeded += 1,
+                Err(e) => {
+                    warn!("Sink {} failed to write events: {:#}", sink.name(), e);
+                    failures.push(format!("{}: {:#}", sink.name(), e));
+                }
+            }
+        }
+
+        if succeeded >= self.quorum {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Only {} of {} sinks succeeded (quorum {}): {}",
+                succeeded,
+                self.sinks.len(),
+                self.quorum,
+                failures.join("; ")
+            ))
+        }
+    }
+}
+
+/// Writes to a sink that must not hold up the others, e.g. an analytics copy next to the
+/// Postgres source of truth. A failed or timed out write is logged, counted in
+/// `best_effort_write_failures_total` and reported as a success, so its events are missing
+/// from that sink until they are backfilled. Put it in a `FanoutSink` next to the required
+/// sinks: the fanout then waits at most `timeout` for it.
+pub struct BestEffortSink {
+    inner: Arc<dyn EventSink>,
+    timeout: Duration,
+    metrics: IndexerMetrics,
+}
+
+impl BestEffortSink {
+    pub fn new(inner: Arc<dyn EventSink>, timeout: Duration, metrics: IndexerMetrics) -> Self {
+        Self { inner, timeout, metrics }
+    }
+}
+
+#[async_trait]
+impl EventSink for BestEffortSink {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        let error = match tokio::time::timeout(self.timeout, self.inner.write(events)).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => format!("{:#}", e),
+            Err(_) => format!("timed out after {:?}", self.timeout),
+        };
+        error!(
+            "Best-effort sink {} dropped {} events: {}",
+            self.inner.name(),
+            events.event_count(),
+            error
+        );
+        self.metrics
+            .best_effort_write_failures_total
+            .with_label_values(&[self.inner.name()])
+            .inc();
+        Ok(())
+    }
+}
+
+/// Writes to several sinks one after another, stopping at the first failure, so later sinks
+/// (e.g. a webhook) only see events that the earlier ones (e.g. Postgres) have committed
+pub struct ChainedSink {
+    name: String,
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl ChainedSink {
+    pub fn new(sinks: Vec<Box<dyn EventSink>>) -> Self {
+        let name = sinks.iter().map(|sink| sink.name()).collect::<Vec<_>>().join(" -> ");
+        Self { name, sinks }
+    }
+}
+
+#[async_trait]
+impl EventSink for ChainedSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        for sink in &self.sinks {
+            sink.write(events)
+                .await
+                .with_context(|| format!("Sink {} failed to write events", sink.name()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Events buffered by a `BatchingSink`, with the writers waiting for them to be flushed
+#[derive(Default)]
+struct Batch {
+    events: IndexedEvents,
+    waiters: Vec<oneshot::Sender<Result<(), String>>>,
+}
+
+/// Flush threshold of a `BatchingSink` that follows how far the indexer is behind the chain:
+/// `max_events` while backfilling, shrinking to `min_events` near the head for low latency.
+/// The lag is the age of the last checkpoint handed to the sink, interpolated linearly
+/// between `head_lag` and `backfill_lag`.
+pub struct AdaptiveBatchSize {
+    min_events: usize,
+    max_events: usize,
+    head_lag: Duration,
+    backfill_lag: Duration,
+    current: AtomicUsize,
+}
+
+impl AdaptiveBatchSize {
+    /// Starts at `max_events` until the first checkpoint is observed
+    pub fn new(min_events: usize, max_events: usize, head_lag: Duration, backfill_lag: Duration) -> Self {
+        let min_events = min_events.clamp(1, max_events.max(1));
+        Self {
+            min_events,
+            max_events: max_events.max(min_events),
+            head_lag,
+            backfill_lag: backfill_lag.max(head_lag),
+            current: AtomicUsize::new(max_events.max(min_events)),
+        }
+    }
+
+    /// Update the threshold from the timestamp of the checkpoint about to be written
+    pub fn observe_checkpoint(&self, timestamp_ms: u64) {
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        let lag = Duration::from_millis(now_ms.saturating_sub(timestamp_ms));
+        let span = (self.backfill_lag - self.head_lag).as_secs_f64();
+        let behind = if span > 0.0 {
+            (lag.saturating_sub(self.head_lag).as_secs_f64() / span).min(1.0)
+        } else if lag > self.head_lag {
+            1.0
+        } else {
+            0.0
+        };
+        let size = self.min_events + ((self.max_events - self.min_events) as f64 * behind).round() as usize;
+        self.current.store(size, Ordering::Relaxed);
+    }
+
+    /// Number of buffered events that triggers a flush
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+}
+
+/// Buffers events from several checkpoints and writes them to the inner sink in one commit.
+/// A flush happens once `max_events` are buffered or every `flush_interval`, whichever comes
+/// first, so events are never delayed by more than the interval during quiet periods.
+/// `write` only returns once its events have been flushed, so checkpoint progress is never
+/// recorded ahead of the database.
+pub struct BatchingSink {
+    inner: Arc<dyn EventSink>,
+    batch: Arc<Mutex<Batch>>,
+    max_events: usize,
+    /// Replaces `max_events` with a threshold that follows the checkpoint lag
+    adaptive_size: Option<Arc<AdaptiveBatchSize>>,
+}
+
+impl BatchingSink {
+    /// Must be called from within a tokio runtime, which runs the flush timer
+    pub fn new(inner: Arc<dyn EventSink>, max_events: usize, flush_interval: Duration) -> Self {
+        let batch = Arc::new(Mutex::new(Batch::default()));
+        tokio::spawn(flush_periodically(inner.clone(), Arc::downgrade(&batch), flush_interval));
+        Self {
+            inner,
+            batch,
+            max_events,
+            adaptive_size: None,
+        }
+    }
+
+    /// Flush at the adaptive threshold instead of the fixed `max_events`
+    pub fn with_adaptive_size(mut self, adaptive_size: Arc<AdaptiveBatchSize>) -> Self {
+        self.adaptive_size = Some(adaptive_size);
+        self
+    }
+}
+
+/// Flush whatever is buffered every `flush_interval`, until the sink is dropped
+async fn flush_periodically(inner: Arc<dyn EventSink>, batch: Weak<Mutex<Batch>>, flush_interval: Duration) {
+    // tokio panics on a zero interval. The first tick comes one interval in, instead of right
+    // away, which would flush the first write on its own whatever max_events is.
+    let flush_interval = flush_interval.max(Duration::from_millis(1));
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + flush_interval, flush_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let Some(batch) = batch.upgrade() else {
+            return;
+        };
+        let pending = std::mem::take(&mut *batch.lock().unwrap());
+        drop(batch);
+        flush(inner.as_ref(), pending).await;
+    }
+}
+
+/// Write a batch to the sink and report the outcome to everyone waiting on it
+async fn flush(inner: &dyn EventSink, batch: Batch) {
+    if batch.waiters.is_empty() {
+        return;
+    }
+    let result = inner.write(&batch.events).await.map_err(|e| format!("{:#}", e));
+    for waiter in batch.waiters {
+        // The writer may have been cancelled; nothing to report to then
+        let _ = waiter.send(result.clone());
+    }
+}
+
+#[async_trait]
+impl EventSink for BatchingSink {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let max_events = self
+            .adaptive_size
+            .as_ref()
+            .map_or(self.max_events, |adaptive_size| adaptive_size.current());
+        let (sender, receiver) = oneshot::channel();
+        let full_batch = {
+            let mut batch = self.batch.lock().unwrap();
+            batch.events.extend(events.clone());
+            batch.waiters.push(sender);
+            (batch.events.event_count() >= max_events).then(|| std::mem::take(&mut *batch))
+        };
+        if let Some(full_batch) = full_batch {
+            flush(self.inner.as_ref(), full_batch).await;
+        }
+
+        receiver
+            .await
+            .map_err(|_| anyhow!("Batch was dropped before it was flushed"))?
+            .map_err(|e| anyhow!("Failed to flush batched events: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::Semaphore;
+
+    use super::*;
+    use crate::models::DynamicEvent;
+
+    /// Inner sink that holds every write until a permit is released, then fails or commits it
+    struct GatedSink {
+        permits: Semaphore,
+        fail: bool,
+        committed: AtomicUsize,
+    }
+
+    impl GatedSink {
+        fn blocking(fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                permits: Semaphore::new(0),
+                fail,
+                committed: AtomicUsize::new(0),
+            })
+        }
+
+        fn open(fail: bool) -> Arc<Self> {
+            let sink = Self::blocking(fail);
+            sink.permits.add_permits(Semaphore::MAX_PERMITS);
+            sink
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for GatedSink {
+        fn name(&self) -> &str {
+            "gated"
+        }
+
+        async fn write(&self, events: &IndexedEvents) -> Result<()> {
+            self.permits.acquire().await?.forget();
+            if self.fail {
+                return Err(anyhow!("database unavailable"));
+            }
+            self.committed.fetch_add(events.event_count(), Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn events(count: usize) -> IndexedEvents {
+        let mut events = IndexedEvents::default();
+        for index in 0..count {
+            events.dynamic_events.push(DynamicEvent {
+                id: format!("event-{}", index),
+                event_name: "test".to_string(),
+                event_type: "0x2::test::Event".to_string(),
+                package_id: "0x2".to_string(),
+                tx_digest: "digest".to_string(),
+                event_index: index as i64,
+                timestamp_ms: 0,
+                checkpoint_seq: 0,
+                tx_index: 0,
+                event_seq: index as i64,
+                data: serde_json::Value::Null,
+                created_at: None,
+            });
+        }
+        events
+    }
+
+    // Long enough that the timer never flushes during a test that relies on the size threshold
+    const NEVER: Duration = Duration::from_secs(3600);
+
+    /// Spawn a write and check that it is still waiting on the inner sink
+    async fn pending_write(sink: &Arc<BatchingSink>, count: usize) -> tokio::task::JoinHandle<Result<()>> {
+        let sink = sink.clone();
+        let write = tokio::spawn(async move { sink.write(&events(count)).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!write.is_finished(), "write returned before the flush committed");
+        write
+    }
+
+    #[tokio::test]
+    async fn write_waits_for_the_size_flush_to_commit() {
+        let inner = GatedSink::blocking(false);
+        let sink = Arc::new(BatchingSink::new(inner.clone(), 2, NEVER));
+
+        let write = pending_write(&sink, 2).await;
+        assert_eq!(inner.committed.load(Ordering::SeqCst), 0);
+
+        inner.permits.add_permits(1);
+        write.await.unwrap().unwrap();
+        assert_eq!(inner.committed.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn write_waits_for_the_batch_to_fill() {
+        let inner = GatedSink::open(false);
+        let sink = Arc::new(BatchingSink::new(inner.clone(), 3, NEVER));
+
+        let first = pending_write(&sink, 2).await;
+        sink.write(&events(1)).await.unwrap();
+        first.await.unwrap().unwrap();
+        assert_eq!(inner.committed.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn write_fails_when_the_size_flush_fails() {
+        let inner = GatedSink::open(true);
+        let sink = Arc::new(BatchingSink::new(inner, 3, NEVER));
+
+        let first = pending_write(&sink, 2).await;
+        let error = sink.write(&events(1)).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("database unavailable"), "{:#}", error);
+        assert!(first.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn write_waits_for_the_timer_flush_to_commit() {
+        let inner = GatedSink::blocking(false);
+        let sink = Arc::new(BatchingSink::new(inner.clone(), 1000, Duration::from_millis(10)));
+
+        let write = pending_write(&sink, 1).await;
+        inner.permits.add_permits(1);
+        write.await.unwrap().unwrap();
+        assert_eq!(inner.committed.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn write_fails_when_the_timer_flush_fails() {
+        let inner = GatedSink::blocking(true);
+        let sink = Arc::new(BatchingSink::new(inner.clone(), 1000, Duration::from_millis(10)));
+
+        let write = pending_write(&sink, 1).await;
+        inner.permits.add_permits(1);
+        let error = write.await.unwrap().unwrap_err();
+        assert!(format!("{:#}", error).contains("database unavailable"), "{:#}", error);
+    }
+
+    #[tokio::test]
+    async fn adaptive_size_flushes_near_the_head() {
+        let adaptive_size = Arc::new(AdaptiveBatchSize::new(
+            2,
+            1000,
+            Duration::from_secs(10),
+            Duration::from_secs(3600),
+        ));
+        adaptive_size.observe_checkpoint(chrono::Utc::now().timestamp_millis() as u64);
+        assert_eq!(adaptive_size.current(), 2);
+
+        let inner = GatedSink::blocking(false);
+        let sink = Arc::new(BatchingSink::new(inner.clone(), 1000, NEVER).with_adaptive_size(adaptive_size.clone()));
+        let write = pending_write(&sink, 2).await;
+        inner.permits.add_permits(1);
+        write.await.unwrap().unwrap();
+        assert_eq!(inner.committed.load(Ordering::SeqCst), 2);
+
+        // A week old checkpoint is a backfill, which waits for the maximum batch
+        adaptive_size.observe_checkpoint(chrono::Utc::now().timestamp_millis() as u64 - 7 * 24 * 3600 * 1000);
+        assert_eq!(adaptive_size.current(), 1000);
+    }
+
+    #[tokio::test]
+    async fn adaptive_size_flush_failures_reach_the_writer() {
+        let adaptive_size = Arc::new(AdaptiveBatchSize::new(
+            1,
+            1000,
+            Duration::from_secs(10),
+            Duration::from_secs(3600),
+        ));
+        adaptive_size.observe_checkpoint(chrono::Utc::now().timestamp_millis() as u64);
+
+        let inner = GatedSink::blocking(true);
+        let sink = Arc::new(BatchingSink::new(inner.clone(), 1000, NEVER).with_adaptive_size(adaptive_size));
+        let write = pending_write(&sink, 1).await;
+        inner.permits.add_permits(1);
+        assert!(write.await.unwrap().is_err());
+    }
+
+    fn best_effort(inner: Arc<GatedSink>, metrics: &IndexerMetrics) -> BestEffortSink {
+        BestEffortSink::new(inner, Duration::from_secs(30), metrics.clone())
+    }
+
+    fn dropped_writes(metrics: &IndexerMetrics) -> u64 {
+        metrics.best_effort_write_failures_total.with_label_values(&["gated"]).get()
+    }
+
+    #[tokio::test]
+    async fn best_effort_passes_successful_writes_through() {
+        let metrics = IndexerMetrics::new(&prometheus::Registry::new());
+        let inner = GatedSink::open(false);
+        best_effort(inner.clone(), &metrics).write(&events(3)).await.unwrap();
+        assert_eq!(inner.committed.load(Ordering::SeqCst), 3);
+        assert_eq!(dropped_writes(&metrics), 0);
+    }
+
+    #[tokio::test]
+    async fn best_effort_drops_failed_writes() {
+        let metrics = IndexerMetrics::new(&prometheus::Registry::new());
+        best_effort(GatedSink::open(true), &metrics).write(&events(3)).await.unwrap();
+        assert_eq!(dropped_writes(&metrics), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn best_effort_gives_up_on_slow_writes() {
+        let metrics = IndexerMetrics::new(&prometheus::Registry::new());
+        let inner = GatedSink::blocking(false);
+        let started = tokio::time::Instant::now();
+        best_effort(inner.clone(), &metrics).write(&events(3)).await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_secs(30));
+        assert_eq!(inner.committed.load(Ordering::SeqCst), 0);
+        assert_eq!(dropped_writes(&metrics), 1);
+    }
+
+    #[tokio::test]
+    async fn fanout_still_requires_the_other_sinks_next_to_a_best_effort_one() {
+        let metrics = IndexerMetrics::new(&prometheus::Registry::new());
+        let failing = GatedSink {
+            permits: Semaphore::new(Semaphore::MAX_PERMITS),
+            fail: true,
+            committed: AtomicUsize::new(0),
+        };
+        let analytics = GatedSink::open(false);
+        let sink = FanoutSink::new(vec![Box::new(failing), Box::new(best_effort(analytics.clone(), &metrics))]);
+        assert!(sink.write(&events(3)).await.is_err());
+        assert_eq!(analytics.committed.load(Ordering::SeqCst), 3);
+
+        let sink = FanoutSink::new(vec![
+            Box::new(best_effort(GatedSink::open(false), &metrics)),
+            Box::new(best_effort(GatedSink::open(true), &metrics)),
+        ]);
+        sink.write(&events(3)).await.unwrap();
+        assert_eq!(dropped_writes(&metrics), 1);
+    }
+
+    #[test]
+    fn oldest_kept_swap_month_counts_back_full_months() {
+        let october = chrono::DateTime::parse_from_rfc3339("2026-10-15T12:00:00Z").unwrap().to_utc();
+        assert_eq!(oldest_kept_swap_month(october, 0), (2026, 10));
+        assert_eq!(oldest_kept_swap_month(october, 9), (2026, 1));
+        assert_eq!(oldest_kept_swap_month(october, 10), (2025, 12));
+        assert_eq!(oldest_kept_swap_month(october, 24), (2024, 10));
+    }
+}
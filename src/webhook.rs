@@ -0,0 +1,187 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Server-to-server push of new events. `WebhookSink` POSTs each batch of events as JSON to a
+//! configured URL from a background task, so a slow receiver only holds up indexing once its
+//! bounded queue is full.
+//!
+//! Delivery is at-most-once across restarts: the queue lives in memory and `write` returns once
+//! the payload is queued, so the checkpoint's progress is recorded before delivery. Payloads
+//! still queued or being retried when the process stops are lost and never resent. Within a
+//! run, a payload may arrive twice when the receiver accepted it but the response was lost.
+//! Receivers that need every event should dedupe by event id and fill gaps in `event_seq` from
+//! the API.
+//!
+//! When `WEBHOOK_SECRET` is set, every payload is signed with HMAC-SHA256 over the raw request
+//! body and the signature is sent as `X-Signature: sha256=<hex>`, so receivers can check that
+//! the body came from the indexer and wasn't tampered with.
+//!
+//! Test vector: with secret `key` and body `The quick brown fox jumps over the lazy dog`, the
+//! header is `sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8`.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+use crate::registry::IndexedEvents;
+use crate::sink::EventSink;
+
+/// Header carrying the payload signature
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Value of the `X-Signature` header for a payload
+pub fn sign_payload(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("{}{}", SIGNATURE_PREFIX, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Check an `X-Signature` header against a payload, in constant time. Receivers written in
+/// Rust can use this directly; others compute the same HMAC-SHA256 of the raw body.
+pub fn verify_signature(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let Some(signature) = signature.trim().strip_prefix(SIGNATURE_PREFIX) else {
+        return false;
+    };
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+// Delay before the first retry of a failed delivery, doubled up to MAX_RETRY_DELAY
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// POSTs batches of new events to a URL. `write` only queues the payload; a background task
+/// delivers queued payloads in order, retrying with exponential backoff until the receiver
+/// accepts them. Once `queue_size` payloads are waiting, `write` blocks, so a slow receiver
+/// applies backpressure to the indexer instead of growing memory without bound. Queued
+/// payloads are lost if the process stops, see the module docs.
+pub struct WebhookSink {
+    name: String,
+    sender: mpsc::Sender<Vec<u8>>,
+}
+
+impl WebhookSink {
+    /// Must be called from within a tokio runtime, which runs the delivery task
+    pub fn new(url: String, secret: Option<String>, queue_size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(queue_size.max(1));
+        tokio::spawn(deliver(reqwest::Client::new(), url.clone(), secret, receiver));
+        Self {
+            name: format!("webhook {}", url),
+            sender,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn write(&self, events: &IndexedEvents) -> Result<()> {
+        if events.event_count() == 0 {
+            return Ok(());
+        }
+        let payload = serde_json::to_vec(&json!({
+            "swap_events": events.swap_events,
+            "add_liquidity_events": events.add_liquidity_events,
+            "remove_liquidity_events": events.remove_liquidity_events,
+            "dynamic_events": events.dynamic_events,
+        }))?;
+        self.sender
+            .send(payload)
+            .await
+            .map_err(|_| anyhow!("Webhook delivery task for {} has stopped", self.name))
+    }
+}
+
+/// Deliver queued payloads one at a time, in order, until the sink is dropped
+async fn deliver(client: reqwest::Client, url: String, secret: Option<String>, mut receiver: mpsc::Receiver<Vec<u8>>) {
+    while let Some(payload) = receiver.recv().await {
+        let mut delay = INITIAL_RETRY_DELAY;
+        loop {
+            let mut request = client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(payload.clone());
+            if let Some(secret) = &secret {
+                request = request.header(SIGNATURE_HEADER, sign_payload(secret.as_bytes(), &payload));
+            }
+            match request.send().await {
+                Ok(response) if response.status().is_success() => break,
+                // The receiver rejected the payload itself; retrying won't help
+                Ok(response) if response.status().is_client_error() => {
+                    error!("Webhook {} rejected a payload with status {}, dropping it", url, response.status());
+                    break;
+                }
+                Ok(response) => warn!(
+                    "Webhook {} returned status {}, retrying in {:?}",
+                    url,
+                    response.status(),
+                    delay
+                ),
+                Err(e) => warn!("Failed to deliver webhook to {}: {}, retrying in {:?}", url, e, delay),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+    #[test]
+    fn sign_payload_matches_the_hmac_sha256_test_vector() {
+        assert_eq!(
+            sign_payload(b"key", BODY),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn verify_signature_accepts_its_own_signature() {
+        let signature = sign_payload(b"key", BODY);
+        assert!(verify_signature(b"key", BODY, &signature));
+        assert!(verify_signature(b"key", BODY, &format!(" {}\n", signature)));
+    }
+
+    #[test]
+    fn verify_signature_rejects_a_modified_body() {
+        let signature = sign_payload(b"key", BODY);
+        assert!(!verify_signature(b"key", b"The quick brown fox jumps over the lazy cog", &signature));
+        assert!(!verify_signature(b"other key", BODY, &signature));
+    }
+
+    #[test]
+    fn verify_signature_rejects_malformed_signatures() {
+        let signature = sign_payload(b"key", BODY);
+        let digest = signature.strip_prefix(SIGNATURE_PREFIX).unwrap();
+        // Missing prefix
+        assert!(!verify_signature(b"key", BODY, digest));
+        assert!(!verify_signature(b"key", BODY, &format!("sha1={}", digest)));
+        // Bad hex
+        assert!(!verify_signature(b"key", BODY, &format!("sha256={}zz", &digest[2..])));
+        assert!(!verify_signature(b"key", BODY, &format!("sha256={}", &digest[1..])));
+        // Truncated digest
+        assert!(!verify_signature(b"key", BODY, &format!("sha256={}", &digest[..32])));
+        assert!(!verify_signature(b"key", BODY, "sha256="));
+    }
+}